@@ -0,0 +1,72 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::spanned::Spanned;
+
+pub(crate) fn derive_from_config_impl(derive_input: syn::DeriveInput) -> syn::Result<TokenStream> {
+    let syn::Data::Struct(syn::DataStruct {
+        fields: syn::Fields::Named(fields),
+        ..
+    }) = &derive_input.data
+    else {
+        return Err(syn::Error::new(
+            derive_input.ident.span(),
+            "`FromConfig` can only be derived for structs with named fields",
+        ));
+    };
+
+    let mut reads = vec![];
+    for field in &fields.named {
+        let ident = field.ident.as_ref().unwrap();
+        let key = ident.to_string();
+        let read = if defaulted(field)? {
+            quote!(__section.get(#key)?.unwrap_or_default())
+        } else if is_option(&field.ty) {
+            quote!(__section.get(#key)?)
+        } else {
+            quote!(__section.require(#key)?)
+        };
+        reads.push(quote!(#ident: #read));
+    }
+
+    let ident = &derive_input.ident;
+    let (impl_generics, ty_generics, where_clause) = derive_input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::dep_inj::config::FromConfig for #ident #ty_generics #where_clause {
+            fn from_config(
+                __section: &::dep_inj::config::Section<'_>,
+            ) -> ::core::result::Result<Self, ::dep_inj::config::ConfigError> {
+                ::core::result::Result::Ok(Self {
+                    #(#reads,)*
+                })
+            }
+        }
+    })
+}
+
+/// `#[config(default)]`
+fn defaulted(field: &syn::Field) -> syn::Result<bool> {
+    let mut default = false;
+    for attr in field
+        .attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("config"))
+    {
+        match attr.parse_args::<syn::Ident>() {
+            Ok(ident) if ident == "default" => default = true,
+            _ => return Err(syn::Error::new(attr.span(), "expect `#[config(default)]`")),
+        }
+    }
+    Ok(default)
+}
+
+/// `Option<T>`, read as missing if the key is missing
+fn is_option(ty: &syn::Type) -> bool {
+    match ty {
+        syn::Type::Path(path) => path
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "Option"),
+        _ => false,
+    }
+}
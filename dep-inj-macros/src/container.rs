@@ -0,0 +1,805 @@
+use crate::{parse_access, snapshot::impl_snapshot};
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote, ToTokens};
+use syn::{
+    parse::{Parse, ParseStream},
+    parse_quote,
+    punctuated::Punctuated,
+    spanned::Spanned,
+    Token,
+};
+
+mod actor;
+mod builder;
+mod config;
+mod delegate;
+mod graph;
+mod health;
+mod hot_swap;
+mod isolation;
+mod layers;
+mod metadata;
+mod overridable;
+mod pin;
+mod provide;
+mod remote;
+mod scope;
+mod supervisor;
+mod tag;
+mod transaction;
+
+use actor::actor_state;
+use builder::Builder;
+pub(crate) use delegate::{delegate_impl, DelegateInput};
+use hot_swap::{HotSwapArgs, HotSwapSlot};
+use overridable::Overridable;
+use provide::impl_as_ref;
+use scope::Scope;
+
+/// All the receivers an interface method can be forwarded from, see `receiver_kind`.
+const RECEIVER_KINDS: [&str; 11] = [
+    "value",
+    "ref",
+    "ref_mut",
+    "box",
+    "rc",
+    "arc",
+    "pin_ref",
+    "pin_ref_mut",
+    "pin_box",
+    "pin_rc",
+    "pin_arc",
+];
+
+pub(crate) fn derive_container_impl(derive_input: syn::DeriveInput) -> syn::Result<TokenStream> {
+    let container = Container::parse(&derive_input)?;
+    let target = container.target();
+    let interfaces = container.interfaces()?;
+
+    let as_refs = container
+        .components
+        .iter()
+        .enumerate()
+        .map(|(i, component)| {
+            let member = &component.member;
+            let checkpoint = component.rollback.then_some(i);
+            impl_as_ref(&target, component, quote!(#member), true, checkpoint)
+        });
+    let transactional = container.transactional(&target);
+    let check_transactional = container.check_transactional(&interfaces);
+    let isolation = container.isolation(&target);
+    let supervised = container.supervised();
+    let dependency_graph = container.dependency_graph(&target);
+    let component_metadata = container.component_metadata(&target);
+    let health = container.health(&target);
+    let configure = container.configure(&target);
+    let delegatable = container.delegatable();
+    let actors = container
+        .components
+        .iter()
+        .filter(|component| component.actor.is_some())
+        .map(|component| container.actor(component, &interfaces));
+    let delegates = container.delegates(&container.delegating(), &[])?;
+    let structural_pins = container.structural_pins();
+    let snapshot = container.snapshot.then(|| {
+        let fields = container
+            .components
+            .iter()
+            .map(|component| (component.member.clone(), &component.ty))
+            .collect::<Vec<_>>();
+        impl_snapshot(&derive_input, &fields)
+    });
+    let forwards = interfaces
+        .iter()
+        .map(|(interface, dispatch)| impl_interface(&target, interface, dispatch));
+    let checks = container
+        .hot_swaps
+        .iter()
+        .map(|slot| container.check_hot_swap(slot));
+    // the deps of a delegatable container are checked by the top one
+    let depends = (!container.delegatable).then(|| container.check_depends(&target));
+    let scopes = container
+        .scopes
+        .iter()
+        .map(|scope| container.scope_def(scope, &interfaces))
+        .collect::<syn::Result<Vec<_>>>()?;
+    let builder = container.builder_def()?;
+    let overrides = container
+        .overridables
+        .iter()
+        .map(|overridable| container.override_def(overridable, &interfaces));
+
+    Ok(quote! {
+        #(#as_refs)*
+        #structural_pins
+        #snapshot
+        #transactional
+        #check_transactional
+        #isolation
+        #supervised
+        #dependency_graph
+        #component_metadata
+        #health
+        #configure
+        #delegatable
+        #(#actors)*
+        #(#delegates)*
+        #(#forwards)*
+        #(#checks)*
+        #depends
+        #(#scopes)*
+        #(#overrides)*
+        #builder
+    })
+}
+
+struct Container<'a> {
+    derive_input: &'a syn::DeriveInput,
+    components: Vec<Component>,
+    hot_swaps: Vec<HotSwapSlot>,
+    /// `#[transaction] tx: Transaction`
+    transaction: Option<syn::Member>,
+    /// `#[poison] poison: Poison`
+    poison: Option<syn::Member>,
+    /// `#[supervisor] supervisor: Supervisor`
+    supervisor: Option<syn::Member>,
+    scopes: Vec<Scope>,
+    overridables: Vec<Overridable>,
+    /// `#[snapshot]`, implementing `Snapshot` over all the components
+    snapshot: bool,
+    /// `#[builder(pub struct GlobalBuilder)]`
+    builder: Option<Builder>,
+    /// `#[delegatable]`, generating the macro reached by `#[component(delegate)]`
+    delegatable: bool,
+}
+
+/// `#[component(proxy = EvenProxy, layers(Metrics), provide(IsEven))] even_state: EvenState`
+struct Component {
+    member: syn::Member,
+    ty: syn::Type,
+    proxy: Option<syn::Path>,
+    /// wrapping the proxy, the outermost first
+    layers: Vec<syn::Path>,
+    provides: Vec<syn::Path>,
+    /// structurally pinned, `#[component(pin)]`
+    pin: bool,
+    /// checkpointed in a transaction, `#[component(rollback)]`
+    rollback: bool,
+    /// catching the panics of the proxy, `#[component(isolate)]`
+    isolate: bool,
+    /// reset to `Default` by the supervisor with the components it depends on,
+    /// `#[component(restart)]`
+    restart: bool,
+    /// a `CounterActor<State>` client of a thread running the proxy,
+    /// `#[component(actor = CounterActor)]`
+    actor: Option<syn::Ident>,
+    /// a client implementing the interfaces itself, `#[component(remote)]`
+    remote: bool,
+    /// checked by the `HealthCheck` of the proxy, `#[component(health)]`
+    health: bool,
+    /// loading the `#[config]` of the state, `#[component(config)]`
+    config: bool,
+    /// a sub-container whose components and interfaces are forwarded, `#[component(delegate)]`
+    delegate: bool,
+    /// falling back to `Default` if not supplied to the builder, `#[component(default)]`
+    default: bool,
+    /// reached by `Has<Replica, CacheState>` instead of `AsRef`, `#[component(tag = Replica)]`
+    tag: Option<syn::Path>,
+    /// reached by `Provide<CacheState>` only, `#[component(access = Provide)]`, or `AsRef<CacheState>`
+    /// only, `#[component(access = AsRef)]`, instead of both
+    access: Option<bool>,
+    /// the index among the components of the container
+    index: usize,
+}
+
+/// How the methods of an interface are dispatched.
+#[derive(Clone)]
+enum Dispatch<'a> {
+    /// to the only component providing the interface
+    Static(&'a Component),
+    /// to the current one of the candidates in the slot
+    HotSwap(&'a HotSwapSlot, Vec<&'a Component>),
+    /// to the `stub` of an override
+    Stub,
+}
+
+/// The type the impls are generated for, the container, one of its scopes or overrides.
+struct Target {
+    generics: syn::Generics,
+    self_ty: syn::Type,
+    /// `parent.` of a scope or `inner.` of an override,
+    /// through which the fields of the container are reached
+    container: TokenStream,
+    /// `tx` or `inner.tx`, the `#[transaction]` of the container
+    transaction: Option<TokenStream>,
+    /// `poison`, `parent.poison` or `inner.poison`, the `#[poison]` of the container
+    poison: Option<TokenStream>,
+}
+
+enum ComponentArg {
+    Pin,
+    Rollback,
+    Isolate,
+    Restart,
+    Actor(syn::Ident),
+    Remote,
+    Health,
+    Config,
+    Delegate,
+    Default,
+    Tag(syn::Path),
+    Access(bool),
+    Proxy(syn::Path),
+    Layers(Punctuated<syn::Path, Token![,]>),
+    Provide(Punctuated<syn::Path, Token![,]>),
+}
+
+impl Parse for ComponentArg {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let ident: syn::Ident = input.parse()?;
+        if ident == "pin" {
+            Ok(Self::Pin)
+        } else if ident == "rollback" {
+            Ok(Self::Rollback)
+        } else if ident == "isolate" {
+            Ok(Self::Isolate)
+        } else if ident == "restart" {
+            Ok(Self::Restart)
+        } else if ident == "actor" {
+            input.parse::<Token![=]>()?;
+            Ok(Self::Actor(input.parse()?))
+        } else if ident == "remote" {
+            Ok(Self::Remote)
+        } else if ident == "health" {
+            Ok(Self::Health)
+        } else if ident == "config" {
+            Ok(Self::Config)
+        } else if ident == "delegate" {
+            Ok(Self::Delegate)
+        } else if ident == "default" {
+            Ok(Self::Default)
+        } else if ident == "tag" {
+            input.parse::<Token![=]>()?;
+            Ok(Self::Tag(input.parse()?))
+        } else if ident == "access" {
+            Ok(Self::Access(parse_access(input)?))
+        } else if ident == "proxy" {
+            input.parse::<Token![=]>()?;
+            Ok(Self::Proxy(input.parse()?))
+        } else if ident == "layers" {
+            let content;
+            syn::parenthesized!(content in input);
+            Ok(Self::Layers(content.parse_terminated(syn::Path::parse)?))
+        } else if ident == "provide" {
+            Ok(Self::Provide(parse_provide(input)?))
+        } else {
+            Err(syn::Error::new(
+                ident.span(),
+                "expect `pin`, `rollback`, `isolate`, `restart`, `actor = Client`, `remote`, `health`, `config`, \
+                `delegate`, `default`, `tag = Tag`, `access = Provide`, `access = AsRef`, `proxy = Proxy`, `layers(Layer, ..)` or `provide(Interface, ..)`",
+            ))
+        }
+    }
+}
+
+/// `(IsOdd, IsEven)`
+fn parse_provide(input: ParseStream) -> syn::Result<Punctuated<syn::Path, Token![,]>> {
+    let content;
+    syn::parenthesized!(content in input);
+    let provides = content.parse_terminated(syn::Path::parse)?;
+    for interface in &provides {
+        check_interface(interface)?;
+    }
+    Ok(provides)
+}
+
+fn check_interface(interface: &syn::Path) -> syn::Result<()> {
+    match interface
+        .segments
+        .iter()
+        .find(|segment| !segment.arguments.is_empty())
+    {
+        Some(segment) => Err(syn::Error::new(
+            segment.span(),
+            "generic interfaces are not supported",
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Parses `#[component(..)]` of a field.
+fn parse_component(
+    member: syn::Member,
+    ty: &syn::Type,
+    attr: &syn::Attribute,
+) -> syn::Result<Component> {
+    let mut component = Component {
+        member,
+        ty: ty.clone(),
+        proxy: None,
+        layers: vec![],
+        provides: vec![],
+        pin: false,
+        rollback: false,
+        isolate: false,
+        restart: false,
+        actor: None,
+        remote: false,
+        health: false,
+        config: false,
+        delegate: false,
+        default: false,
+        tag: None,
+        access: None,
+        index: 0,
+    };
+    if !attr.tokens.is_empty() {
+        let args = attr.parse_args_with(Punctuated::<ComponentArg, Token![,]>::parse_terminated)?;
+        for arg in args {
+            match arg {
+                ComponentArg::Pin => component.pin = true,
+                ComponentArg::Rollback => component.rollback = true,
+                ComponentArg::Isolate => component.isolate = true,
+                ComponentArg::Restart => component.restart = true,
+                ComponentArg::Actor(client) => component.actor = Some(client),
+                ComponentArg::Remote => component.remote = true,
+                ComponentArg::Health => component.health = true,
+                ComponentArg::Config => component.config = true,
+                ComponentArg::Delegate => component.delegate = true,
+                ComponentArg::Default => component.default = true,
+                ComponentArg::Tag(tag) => component.tag = Some(tag),
+                ComponentArg::Access(via_provide) => component.access = Some(via_provide),
+                ComponentArg::Proxy(proxy) => component.proxy = Some(proxy),
+                ComponentArg::Layers(layers) => component.layers.extend(layers),
+                ComponentArg::Provide(provides) => component.provides.extend(provides),
+            }
+        }
+    }
+    tag::check_tag(&component, attr)?;
+    provide::check_access(&component, attr)?;
+    if component.delegate {
+        if component.proxy.is_some()
+            || !component.layers.is_empty()
+            || !component.provides.is_empty()
+            || component.pin
+            || component.rollback
+            || component.isolate
+            || component.restart
+            || component.actor.is_some()
+            || component.remote
+            || component.health
+            || component.config
+        {
+            return Err(syn::Error::new(
+                attr.span(),
+                "a delegated sub-container forwards its own components and interfaces, \
+                which takes no other arguments",
+            ));
+        }
+        return Ok(component);
+    }
+    if component.remote {
+        if component.proxy.is_some()
+            || component.pin
+            || component.rollback
+            || component.restart
+            || component.actor.is_some()
+            || component.health
+            || component.config
+        {
+            return Err(syn::Error::new(
+                attr.span(),
+                "a remote component implements the interfaces itself, which cannot have a proxy, \
+                be pinned, rolled back, restarted, an actor, health checked or configured",
+            ));
+        }
+        return Ok(component);
+    }
+    if let (None, Some(interface)) = (&component.proxy, component.provides.first()) {
+        return Err(syn::Error::new(
+            interface.span(),
+            "the component needs a `proxy = Proxy` to provide interfaces",
+        ));
+    }
+    if let (None, Some(layer)) = (&component.proxy, component.layers.first()) {
+        return Err(syn::Error::new(
+            layer.span(),
+            "the component needs a `proxy = Proxy` to be wrapped in layers",
+        ));
+    }
+    if let (true, None) = (component.health, &component.proxy) {
+        return Err(syn::Error::new(
+            attr.span(),
+            "the component needs a `proxy = Proxy` implementing `HealthCheck` to be health checked",
+        ));
+    }
+    if component.restart && (component.pin || component.actor.is_some()) {
+        return Err(syn::Error::new(
+            attr.span(),
+            "a pinned or actor component cannot be reset in place to be restarted",
+        ));
+    }
+    if component.actor.is_some() && (component.pin || component.rollback) {
+        return Err(syn::Error::new(
+            attr.span(),
+            "an actor owns its state on its own thread, which cannot be pinned or rolled back",
+        ));
+    }
+    if let (Some(_), None) = (&component.actor, &component.proxy) {
+        return Err(syn::Error::new(
+            attr.span(),
+            "the actor needs a `proxy = Proxy` to run on its thread",
+        ));
+    }
+    if component.actor.is_some() {
+        actor_state(&component)?;
+    }
+    Ok(component)
+}
+
+fn field_member(i: usize, field: &syn::Field) -> syn::Member {
+    match &field.ident {
+        Some(ident) => syn::Member::Named(ident.clone()),
+        None => syn::Member::Unnamed(i.into()),
+    }
+}
+
+impl<'a> Container<'a> {
+    fn parse(derive_input: &'a syn::DeriveInput) -> syn::Result<Self> {
+        let syn::Data::Struct(data) = &derive_input.data else {
+            return Err(syn::Error::new(
+                derive_input.span(),
+                "`Container` can only be derived for structs",
+            ));
+        };
+
+        let mut components = vec![];
+        let mut hot_swaps = vec![];
+        let mut transaction = None;
+        let mut poison = None;
+        let mut supervisor = None;
+        for (i, field) in data.fields.iter().enumerate() {
+            for attr in &field.attrs {
+                if attr.path.is_ident("component") {
+                    let mut component = parse_component(field_member(i, field), &field.ty, attr)?;
+                    component.index = components.len();
+                    if component.isolate && component.index >= 64 {
+                        return Err(syn::Error::new(
+                            attr.span(),
+                            "only the first 64 components can be isolated",
+                        ));
+                    }
+                    if component.actor.is_some() && !derive_input.generics.params.is_empty() {
+                        return Err(syn::Error::new(
+                            attr.span(),
+                            "the actor of a generic container is not supported",
+                        ));
+                    }
+                    components.push(component);
+                } else if attr.path.is_ident("poison") {
+                    if poison.replace(field_member(i, field)).is_some() {
+                        return Err(syn::Error::new(
+                            attr.span(),
+                            "a container has at most one `#[poison]`",
+                        ));
+                    }
+                } else if attr.path.is_ident("supervisor") {
+                    if supervisor.replace(field_member(i, field)).is_some() {
+                        return Err(syn::Error::new(
+                            attr.span(),
+                            "a container has at most one `#[supervisor]`",
+                        ));
+                    }
+                } else if attr.path.is_ident("transaction") {
+                    if transaction.replace(field_member(i, field)).is_some() {
+                        return Err(syn::Error::new(
+                            attr.span(),
+                            "a container has at most one `#[transaction]`",
+                        ));
+                    }
+                } else if attr.path.is_ident("hot_swap") {
+                    let args: HotSwapArgs = attr.parse_args()?;
+                    hot_swaps.push(HotSwapSlot {
+                        member: field_member(i, field),
+                        ty: field.ty.clone(),
+                        interface: args.interface,
+                        candidates: args.candidates.into_iter().collect(),
+                    });
+                }
+            }
+        }
+
+        let mut scopes = vec![];
+        let mut overridables = vec![];
+        let mut snapshot = false;
+        let mut builder = None;
+        let mut delegatable = false;
+        for attr in &derive_input.attrs {
+            if attr.path.is_ident("scope") {
+                scopes.push(Scope::parse(attr.parse_args()?)?);
+            } else if attr.path.is_ident("overridable") {
+                overridables.push(attr.parse_args()?);
+            } else if attr.path.is_ident("builder") {
+                if builder.replace(attr.parse_args()?).is_some() {
+                    return Err(syn::Error::new(
+                        attr.span(),
+                        "a container has at most one `#[builder(..)]`",
+                    ));
+                }
+            } else if attr.path.is_ident("snapshot") {
+                if !attr.tokens.is_empty() {
+                    return Err(syn::Error::new(attr.span(), "expect `#[snapshot]`"));
+                }
+                snapshot = true;
+            } else if attr.path.is_ident("delegatable") {
+                if !attr.tokens.is_empty() {
+                    return Err(syn::Error::new(attr.span(), "expect `#[delegatable]`"));
+                }
+                if !derive_input.generics.params.is_empty() {
+                    return Err(syn::Error::new(
+                        derive_input.generics.span(),
+                        "generic containers cannot be delegated",
+                    ));
+                }
+                if transaction.is_some() || poison.is_some() {
+                    return Err(syn::Error::new(
+                        attr.span(),
+                        "a container with a `#[transaction]` or a `#[poison]` cannot be delegated",
+                    ));
+                }
+                if let Some(component) = components.iter().find(|component| component.pin) {
+                    return Err(syn::Error::new(
+                        component.ty.span(),
+                        "a container with pinned components cannot be delegated",
+                    ));
+                }
+                delegatable = true;
+            }
+        }
+
+        if let (None, Some(component)) = (
+            &transaction,
+            components.iter().find(|component| component.rollback),
+        ) {
+            return Err(syn::Error::new(
+                component.ty.span(),
+                "the container needs a `#[transaction]` field to roll back the components",
+            ));
+        }
+        if let (None, Some(component)) = (
+            &poison,
+            components.iter().find(|component| component.isolate),
+        ) {
+            return Err(syn::Error::new(
+                component.ty.span(),
+                "the container needs a `#[poison]` field to isolate the components",
+            ));
+        }
+        if let (None, Some(component)) = (
+            &builder,
+            components.iter().find(|component| component.default),
+        ) {
+            return Err(syn::Error::new(
+                component.ty.span(),
+                "the container needs a `#[builder(..)]` to default the components",
+            ));
+        }
+        if let (None, Some(component)) = (
+            &supervisor,
+            components.iter().find(|component| component.restart),
+        ) {
+            return Err(syn::Error::new(
+                component.ty.span(),
+                "the container needs a `#[supervisor]` field to restart the components",
+            ));
+        }
+        if let (None, Some(member)) = (&poison, &supervisor) {
+            return Err(syn::Error::new(
+                member.span(),
+                "the container needs a `#[poison]` field to supervise the components",
+            ));
+        }
+
+        Ok(Self {
+            derive_input,
+            components,
+            hot_swaps,
+            transaction,
+            poison,
+            supervisor,
+            scopes,
+            overridables,
+            snapshot,
+            builder,
+            delegatable,
+        })
+    }
+
+    fn target(&self) -> Target {
+        let ident = &self.derive_input.ident;
+        let (_, ty_generics, _) = self.derive_input.generics.split_for_impl();
+        Target {
+            generics: self.derive_input.generics.clone(),
+            self_ty: parse_quote!(#ident #ty_generics),
+            container: TokenStream::new(),
+            transaction: self.transaction.as_ref().map(|member| quote!(#member)),
+            poison: self.poison.as_ref().map(|member| quote!(#member)),
+        }
+    }
+
+    /// The interfaces provided by the components and how they are dispatched,
+    /// in the order of their first appearance.
+    fn interfaces(&self) -> syn::Result<Vec<(&syn::Path, Dispatch<'_>)>> {
+        let providers = providers(&self.components);
+        for slot in &self.hot_swaps {
+            if !providers
+                .iter()
+                .any(|(interface, _)| same_path(interface, &slot.interface))
+            {
+                return Err(syn::Error::new(
+                    slot.interface.span(),
+                    "the interface is not provided by any component",
+                ));
+            }
+        }
+
+        providers
+            .into_iter()
+            .map(|(interface, components)| {
+                let slot = self
+                    .hot_swaps
+                    .iter()
+                    .find(|slot| same_path(&slot.interface, interface));
+                let dispatch = match slot {
+                    Some(slot) => Dispatch::HotSwap(slot, self.candidates(slot)?),
+                    None => static_dispatch(interface, &components)?,
+                };
+                Ok((interface, dispatch))
+            })
+            .collect()
+    }
+}
+
+/// The components providing each interface, in the order of the first appearance.
+fn providers(components: &[Component]) -> Vec<(&syn::Path, Vec<&Component>)> {
+    let mut providers: Vec<(&syn::Path, Vec<&Component>)> = vec![];
+    for component in components {
+        for interface in &component.provides {
+            match providers
+                .iter_mut()
+                .find(|(provided, _)| same_path(provided, interface))
+            {
+                Some((_, components)) => components.push(component),
+                None => providers.push((interface, vec![component])),
+            }
+        }
+    }
+    providers
+}
+
+fn static_dispatch<'a>(
+    interface: &syn::Path,
+    components: &[&'a Component],
+) -> syn::Result<Dispatch<'a>> {
+    match components {
+        [component] => Ok(Dispatch::Static(component)),
+        _ => Err(syn::Error::new(
+            interface.span(),
+            "the interface is provided by several components, \
+            put them into a `#[hot_swap(..)]` slot",
+        )),
+    }
+}
+
+/// `const _: () = { macro_rules! __dep_inj_dispatch { .. } IsEven! { .. } };`,
+/// where `IsEven!` is generated by `#[interface]` and calls back `__dep_inj_dispatch!`.
+fn impl_interface(target: &Target, interface: &syn::Path, dispatch: &Dispatch) -> TokenStream {
+    let Target {
+        generics,
+        self_ty,
+        transaction,
+        poison,
+        ..
+    } = target;
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+
+    let arms = RECEIVER_KINDS.iter().map(|kind| {
+        let kind_ident = format_ident!("{}", kind);
+        let call = |component: &Component| {
+            let call = if component.actor.is_some() {
+                actor::actor_call(target, interface, component, kind)
+            } else if component.remote {
+                remote::remote_call(target, interface, component, kind)
+            } else {
+                layers::layered_call(interface, component, kind)
+            };
+            match poison {
+                Some(poison) if component.isolate => {
+                    isolation::isolate_call(poison, component, call)
+                }
+                _ => call,
+            }
+        };
+
+        let body = match dispatch {
+            Dispatch::Static(component) => call(component),
+            Dispatch::Stub => match overridable::stub_call(interface, kind) {
+                Ok(call) => call,
+                Err(message) => {
+                    return quote! {
+                        (#kind_ident $($tt:tt)*) => { ::core::compile_error!(#message) };
+                    };
+                }
+            },
+            Dispatch::HotSwap(slot, candidates) => {
+                hot_swap::hot_swap_call(target, slot, candidates, kind, call)
+            }
+        };
+        // a `&mut self` call is a transaction, rolled back by the top-level one on failure
+        let body = match transaction {
+            Some(_) if *kind == "ref_mut" => transaction::transaction_call(body),
+            _ => body,
+        };
+
+        quote! {
+            (#kind_ident $this:ident $method:ident($($arg:ident),*)) => { #body };
+        }
+    });
+
+    quote! {
+        const _: () = {
+            macro_rules! __dep_inj_dispatch {
+                #(#arms)*
+            }
+
+            #interface! {
+                impl [#impl_generics] [#interface] for [#self_ty] [#where_clause]
+                => __dep_inj_dispatch
+            }
+        };
+    }
+}
+
+/// `EvenProxy` -> `EvenProxy<Self>`, `FooProxy<T>` -> `FooProxy<T, Self>`,
+/// or `Metrics` -> `Metrics<EvenProxy<Self>>` for a layer
+fn proxy_type(proxy: &syn::Path, deps: syn::Type) -> syn::Type {
+    let mut proxy = proxy.clone();
+    let last = proxy.segments.last_mut().unwrap();
+    match &mut last.arguments {
+        syn::PathArguments::AngleBracketed(args) => args.args.push(parse_quote!(#deps)),
+        arguments => *arguments = syn::PathArguments::AngleBracketed(parse_quote!(<#deps>)),
+    }
+    parse_quote!(#proxy)
+}
+
+/// `arc` -> `inj_arc`, `value` -> `inj`
+fn inj_ident(kind: &str) -> syn::Ident {
+    match kind {
+        "value" => format_ident!("inj"),
+        kind => format_ident!("inj_{}", kind),
+    }
+}
+
+/// `Actor<CounterState>` rather than `Actor < CounterState >`.
+fn type_name(ty: &syn::Type) -> String {
+    let tokens = ty.to_token_stream().to_string();
+    let mut name = String::with_capacity(tokens.len());
+    let mut chars = tokens.chars().peekable();
+    while let Some(c) = chars.next() {
+        let word = |c: char| c.is_alphanumeric() || c == '_' || c == '\'';
+        // keep the spaces between words only, like `dyn Send`
+        if c == ' ' && !(name.ends_with(word) && chars.peek().copied().is_some_and(word)) {
+            continue;
+        }
+        name.push(c);
+    }
+    name
+}
+
+fn same_path(a: &syn::Path, b: &syn::Path) -> bool {
+    a.to_token_stream().to_string() == b.to_token_stream().to_string()
+}
+
+fn same_type(a: &syn::Type, b: &syn::Type) -> bool {
+    a.to_token_stream().to_string() == b.to_token_stream().to_string()
+}
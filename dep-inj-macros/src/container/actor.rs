@@ -0,0 +1,253 @@
+use super::{
+    inj_ident, proxy_type, same_path, Component, Container, Dispatch, Target, RECEIVER_KINDS,
+};
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{parse_quote, spanned::Spanned};
+
+impl Container<'_> {
+    /// `CounterActor<State>` of `#[component(actor = CounterActor)]`, the client implementing the
+    /// interfaces provided by the component, and the deps of the proxy on the thread, implementing
+    /// the other interfaces of the container through a `Handle` of it.
+    pub(super) fn actor(
+        &self,
+        component: &Component,
+        interfaces: &[(&syn::Path, Dispatch)],
+    ) -> TokenStream {
+        let vis = &self.derive_input.vis;
+        let container = &self.derive_input.ident;
+        let client = component.actor.as_ref().unwrap();
+        let client_name = client.to_string();
+        let deps = format_ident!("__{}Deps", client);
+        let state = actor_state(component).unwrap();
+
+        let calls = component.provides.iter().map(|interface| {
+            let arms = RECEIVER_KINDS.iter().map(|kind| {
+                let kind_ident = format_ident!("{}", kind);
+                let deps = match *kind {
+                    "ref" => quote!(&**__deps),
+                    "ref_mut" => quote!(::dep_inj::actor::deps_mut(__deps)),
+                    "arc" => quote!(::std::sync::Arc::clone(__deps)),
+                    _ => {
+                        let message =
+                            format!("a `{kind}` receiver cannot be forwarded to an actor");
+                        return quote! {
+                            (#kind_ident $($tt:tt)*) => { ::core::compile_error!(#message) };
+                        };
+                    }
+                };
+                // inferred as the deps on the thread, casted from the innermost
+                let inj = inj_ident(kind);
+                let mut proxy = proxy_type(component.proxy.as_ref().unwrap(), parse_quote!(_));
+                let mut this = quote!(<#proxy>::#inj(#deps));
+                for layer in component.layers.iter().rev() {
+                    proxy = proxy_type(layer, proxy);
+                    this = quote!(<#proxy>::#inj(#this));
+                }
+                quote! {
+                    (#kind_ident $this:ident $method:ident($($arg:ident),*)) => {
+                        ::dep_inj::actor::Actor::call(&$this.actor, move |__deps| {
+                            #interface::$method(#this $(, $arg)*)
+                        })
+                    };
+                }
+            });
+            quote! {
+                const _: () = {
+                    macro_rules! __dep_inj_dispatch {
+                        #(#arms)*
+                    }
+
+                    #interface! {
+                        impl [] [#interface] for [#client<#state>] [] => __dep_inj_dispatch
+                    }
+                };
+            }
+        });
+
+        let forwards = interfaces
+            .iter()
+            .filter(|(interface, _)| {
+                !component
+                    .provides
+                    .iter()
+                    .any(|provided| same_path(provided, interface))
+            })
+            .map(|(interface, _)| {
+                let handle = quote!(::dep_inj::actor::Handle::get(&$this.container));
+                // implemented only if every method takes `&self` or `Arc<Self>`, so that a proxy
+                // depending on another one fails to compile
+                quote! {
+                    const _: () = {
+                        macro_rules! __dep_inj_dispatch {
+                            (ref $this:ident $method:ident($($arg:ident),*)) => {
+                                <#container as #interface>::$method(&*#handle $(, $arg)*)
+                            };
+                            (arc $this:ident $method:ident($($arg:ident),*)) => {
+                                <#container as #interface>::$method(#handle $(, $arg)*)
+                            };
+                        }
+
+                        macro_rules! __dep_inj_receivers {
+                            () => {
+                                #interface! {
+                                    impl [] [#interface] for [#deps<#state>] []
+                                    => __dep_inj_dispatch
+                                }
+                            };
+                            (ref $($kind:ident)*) => { __dep_inj_receivers! { $($kind)* } };
+                            (arc $($kind:ident)*) => { __dep_inj_receivers! { $($kind)* } };
+                            ($other:ident $($kind:ident)*) => {};
+                        }
+
+                        #interface! { receivers => __dep_inj_receivers }
+                    };
+                }
+            });
+
+        quote! {
+            /// The client of an actor, generated by `#[derive(Container)]`.
+            #vis struct #client<__S> {
+                actor: ::dep_inj::actor::Actor<#deps<__S>>,
+                container: ::dep_inj::actor::Handle<#container>,
+            }
+
+            #[doc(hidden)]
+            #vis struct #deps<__S> {
+                state: __S,
+                container: ::dep_inj::actor::Handle<#container>,
+            }
+
+            impl #client<#state> {
+                /// Spawn the thread owning `state`.
+                #vis fn spawn(state: #state) -> Self {
+                    let container = ::dep_inj::actor::Handle::new();
+                    Self {
+                        actor: ::dep_inj::actor::Actor::spawn(#deps {
+                            state,
+                            container: ::core::clone::Clone::clone(&container),
+                        }),
+                        container,
+                    }
+                }
+
+                /// Bind the actor to `container`, through which the proxy calls the other
+                /// components, if it is not bound yet.
+                #vis fn bind(&self, container: &::std::sync::Arc<#container>) {
+                    ::dep_inj::actor::Handle::bind(&self.container, container);
+                }
+            }
+
+            impl ::core::default::Default for #client<#state>
+            where
+                for<'__a> #state: ::core::default::Default,
+            {
+                fn default() -> Self {
+                    Self::spawn(::core::default::Default::default())
+                }
+            }
+
+            impl<__S> ::core::clone::Clone for #client<__S> {
+                fn clone(&self) -> Self {
+                    Self {
+                        actor: ::core::clone::Clone::clone(&self.actor),
+                        container: ::core::clone::Clone::clone(&self.container),
+                    }
+                }
+            }
+
+            impl<__S> ::core::fmt::Debug for #client<__S> {
+                fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                    f.debug_struct(#client_name)
+                        .field("actor", &self.actor)
+                        .field("container", &self.container)
+                        .finish()
+                }
+            }
+
+            impl<__S: ::dep_inj::graph::Dependencies> ::dep_inj::graph::Dependencies for #client<__S> {
+                const DEPENDENCIES: &'static [&'static str] = __S::DEPENDENCIES;
+            }
+
+            impl<__S> ::core::convert::AsRef<__S> for #deps<__S> {
+                fn as_ref(&self) -> &__S {
+                    &self.state
+                }
+            }
+
+            impl<__S> ::core::convert::AsMut<__S> for #deps<__S> {
+                fn as_mut(&mut self) -> &mut __S {
+                    &mut self.state
+                }
+            }
+
+            impl<__S> ::dep_inj::provide::Provide<__S> for #deps<__S> {
+                fn provide(&self) -> &__S {
+                    &self.state
+                }
+            }
+
+            impl<__S> ::dep_inj::provide::ProvideMut<__S> for #deps<__S> {
+                fn provide_mut(&mut self) -> &mut __S {
+                    &mut self.state
+                }
+            }
+
+            #(#calls)*
+            #(#forwards)*
+        }
+    }
+}
+
+/// `<CounterActor<CounterState> as Count>::incr(&mut $this.counter, n)`, the client of the actor
+/// bound to the container by its first `Arc<Self>` call.
+pub(super) fn actor_call(
+    target: &Target,
+    interface: &syn::Path,
+    component: &Component,
+    kind: &str,
+) -> TokenStream {
+    let container = &target.container;
+    let member = &component.member;
+    let ty = &component.ty;
+    let field = quote!((*$this).#container #member);
+    let receiver = match kind {
+        "ref" => quote!(&#field),
+        "ref_mut" => quote!(&mut #field),
+        // a scope or an override cannot bind the container, which it doesn't own
+        "arc" if container.is_empty() => quote! {{
+            <#ty>::bind(&#field, &$this);
+            ::std::sync::Arc::new(<#ty as ::core::clone::Clone>::clone(&#field))
+        }},
+        "arc" => quote!(::std::sync::Arc::new(<#ty as ::core::clone::Clone>::clone(&#field))),
+        _ => {
+            let message = format!("a `{kind}` receiver cannot be forwarded to an actor");
+            return quote!(::core::compile_error!(#message));
+        }
+    };
+    quote! {
+        <#ty as #interface>::$method(#receiver $(, $arg)*)
+    }
+}
+
+/// `CounterState` of a `CounterActor<CounterState>` field of `#[component(actor = CounterActor)]`.
+pub(super) fn actor_state(component: &Component) -> syn::Result<&syn::Type> {
+    let client = component.actor.as_ref().unwrap();
+    if let syn::Type::Path(syn::TypePath { qself: None, path }) = &component.ty {
+        if let [segment] = path.segments.iter().collect::<Vec<_>>()[..] {
+            if let (true, syn::PathArguments::AngleBracketed(args)) =
+                (segment.ident == *client, &segment.arguments)
+            {
+                if let [syn::GenericArgument::Type(state)] =
+                    args.args.iter().collect::<Vec<_>>()[..]
+                {
+                    return Ok(state);
+                }
+            }
+        }
+    }
+    Err(syn::Error::new(
+        component.ty.span(),
+        format!("expect the field of `{client}<State>`"),
+    ))
+}
@@ -0,0 +1,198 @@
+use super::Container;
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote, ToTokens};
+use syn::{
+    parse::{Parse, ParseStream},
+    parse_quote, Token,
+};
+
+/// `#[builder(pub struct GlobalBuilder)]`
+pub(super) struct Builder {
+    vis: syn::Visibility,
+    ident: syn::Ident,
+}
+
+impl Parse for Builder {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let vis = input.parse()?;
+        input.parse::<Token![struct]>()?;
+        Ok(Self {
+            vis,
+            ident: input.parse()?,
+        })
+    }
+}
+
+impl Container<'_> {
+    /// `struct GlobalBuilder`, taking a type parameter per required component, which is the marker
+    /// of the field until supplied by `with_odd_state(..)`, and `build()` once all are supplied.
+    pub(super) fn builder_def(&self) -> syn::Result<Option<TokenStream>> {
+        let Some(Builder {
+            vis,
+            ident: builder_ident,
+        }) = &self.builder
+        else {
+            return Ok(None);
+        };
+        let syn::Data::Struct(syn::DataStruct {
+            fields: syn::Fields::Named(fields),
+            ..
+        }) = &self.derive_input.data
+        else {
+            return Err(syn::Error::new(
+                builder_ident.span(),
+                "a builder needs the container to have named fields",
+            ));
+        };
+        let ident = &self.derive_input.ident;
+        let (impl_generics, ty_generics, where_clause) =
+            self.derive_input.generics.split_for_impl();
+        let markers = format_ident!("__{}", builder_ident);
+
+        // the required components, and the other fields falling back to `Default`
+        let mut required = vec![];
+        let mut optional = vec![];
+        for field in &fields.named {
+            let member = field.ident.as_ref().unwrap();
+            let component = self.components.iter().find(|component| {
+                matches!(&component.member, syn::Member::Named(ident) if ident == member)
+            });
+            match component {
+                Some(component) if !component.default => required.push((
+                    member,
+                    &field.ty,
+                    format_ident!("__{}", camel_case(&member.to_string())),
+                )),
+                _ => optional.push((member, &field.ty)),
+            }
+        }
+
+        // the generics of the container, then the type-states
+        let args = self
+            .derive_input
+            .generics
+            .params
+            .iter()
+            .map(|param| match param {
+                syn::GenericParam::Type(param) => param.ident.to_token_stream(),
+                syn::GenericParam::Lifetime(param) => param.lifetime.to_token_stream(),
+                syn::GenericParam::Const(param) => param.ident.to_token_stream(),
+            })
+            .collect::<Vec<_>>();
+        let states = required
+            .iter()
+            .map(|(_, _, param)| param)
+            .collect::<Vec<_>>();
+        let mut generics = self.derive_input.generics.clone();
+        generics
+            .params
+            .extend(required.iter().map::<syn::GenericParam, _>(
+                |(member, _, param)| parse_quote!(#param = #markers::#member),
+            ));
+        let (builder_impl_generics, builder_ty_generics, builder_where_clause) =
+            generics.split_for_impl();
+
+        let required_members = required
+            .iter()
+            .map(|(member, _, _)| *member)
+            .collect::<Vec<_>>();
+        let optional_members = optional
+            .iter()
+            .map(|(member, _)| *member)
+            .collect::<Vec<_>>();
+        let optional_tys = optional.iter().map(|(_, ty)| *ty);
+        let supply = required.iter().enumerate().map(|(i, (member, ty, _))| {
+            let method = format_ident!("with_{}", member);
+            let supplied = states.iter().enumerate().map(|(j, param)| match i == j {
+                true => ty.to_token_stream(),
+                false => param.to_token_stream(),
+            });
+            let others = required_members.iter().filter(|other| *other != member);
+            quote! {
+                #vis fn #method(self, #member: #ty) -> #builder_ident<#(#args,)* #(#supplied),*> {
+                    #builder_ident {
+                        #member,
+                        #(#others: self.#others,)*
+                        #(#optional_members: self.#optional_members,)*
+                        __container: ::core::marker::PhantomData,
+                    }
+                }
+            }
+        });
+        let set = optional.iter().map(|(member, ty)| {
+            let method = format_ident!("with_{}", member);
+            quote! {
+                #vis fn #method(mut self, #member: #ty) -> Self {
+                    self.#member = ::core::option::Option::Some(#member);
+                    self
+                }
+            }
+        });
+        let required_tys = required.iter().map(|(_, ty, _)| ty);
+
+        let doc = format!("A builder of `{ident}`, see `dep_inj::builder`.");
+        Ok(Some(quote! {
+            #[doc(hidden)]
+            #[allow(non_snake_case)]
+            #vis mod #markers {
+                #(
+                    #[allow(non_camel_case_types)]
+                    pub struct #required_members;
+                )*
+            }
+
+            #[doc = #doc]
+            #[must_use]
+            #vis struct #builder_ident #generics #where_clause {
+                #(#required_members: #states,)*
+                #(#optional_members: ::core::option::Option<#optional_tys>,)*
+                __container: ::core::marker::PhantomData<fn() -> #ident #ty_generics>,
+            }
+
+            impl #impl_generics #ident #ty_generics #where_clause {
+                /// A builder with none of the components supplied.
+                #vis fn builder() -> #builder_ident<#(#args),*> {
+                    #builder_ident {
+                        #(#required_members: #markers::#required_members,)*
+                        #(#optional_members: ::core::option::Option::None,)*
+                        __container: ::core::marker::PhantomData,
+                    }
+                }
+            }
+
+            impl #builder_impl_generics #builder_ident #builder_ty_generics #builder_where_clause {
+                #(#supply)*
+
+                #(#set)*
+
+                /// The container, once all the required components are supplied.
+                #vis fn build(self) -> #ident #ty_generics
+                where
+                    #(#states: ::dep_inj::builder::Supplied<#required_tys>,)*
+                {
+                    #ident {
+                        #(#required_members: ::dep_inj::builder::Supplied::supplied(
+                            self.#required_members,
+                        ),)*
+                        #(#optional_members: self.#optional_members.unwrap_or_default(),)*
+                    }
+                }
+            }
+        }))
+    }
+}
+
+/// `OddState` of `odd_state`.
+pub(super) fn camel_case(ident: &str) -> String {
+    ident
+        .split('_')
+        .flat_map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_uppercase().chain(chars))
+                .into_iter()
+                .flatten()
+        })
+        .collect()
+}
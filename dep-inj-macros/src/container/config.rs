@@ -0,0 +1,54 @@
+use super::{actor_state, Component, Container, Target};
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+
+impl Container<'_> {
+    /// `fn configure(&mut self, source: &Source)`, if any component is `#[component(config)]`.
+    pub(super) fn configure(&self, target: &Target) -> Option<TokenStream> {
+        if !self.components.iter().any(|component| component.config) {
+            return None;
+        }
+        let Target {
+            generics, self_ty, ..
+        } = target;
+        let (impl_generics, _, where_clause) = generics.split_for_impl();
+        let vis = &self.derive_input.vis;
+        let configures = self
+            .components
+            .iter()
+            .filter(|component| component.config)
+            .map(|component| {
+                let Component { member, ty, .. } = component;
+                let name = member.to_token_stream().to_string();
+                if component.actor.is_some() {
+                    let state = actor_state(component).unwrap();
+                    return quote! {
+                        ::dep_inj::actor::configure::<#state, _>(
+                            &self.#member.actor,
+                            &source.section(#name),
+                        )?;
+                    };
+                }
+                quote! {
+                    ::dep_inj::config::Configured::configure(
+                        &mut self.#member,
+                        <<#ty as ::dep_inj::config::Configured>::Config
+                            as ::dep_inj::config::FromConfig>::from_config(&source.section(#name))?,
+                    );
+                }
+            });
+        Some(quote! {
+            impl #impl_generics #self_ty #where_clause {
+                /// Load the configs of the components from `source`, generated by
+                /// `#[derive(Container)]`.
+                #vis fn configure(
+                    &mut self,
+                    source: &::dep_inj::config::Source,
+                ) -> ::core::result::Result<(), ::dep_inj::config::ConfigError> {
+                    #(#configures)*
+                    ::core::result::Result::Ok(())
+                }
+            }
+        })
+    }
+}
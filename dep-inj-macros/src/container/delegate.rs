@@ -0,0 +1,152 @@
+use super::{impl_as_ref, impl_interface, Container, Target};
+use crate::exported_macro_ident;
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use syn::{
+    parse::{Parse, ParseStream},
+    parse_quote,
+    punctuated::Punctuated,
+    spanned::Spanned,
+    Token,
+};
+
+impl Container<'_> {
+    /// `macro_rules! StorageContainer` of a `#[delegatable]` container, through which a top
+    /// container delegating to this one reaches the definition of this one, calling back
+    /// `dep_inj::__delegate!` with it.
+    pub(super) fn delegatable(&self) -> Option<TokenStream> {
+        if !self.delegatable {
+            return None;
+        }
+        let derive_input = self.derive_input;
+        let vis = &derive_input.vis;
+        let ident = &derive_input.ident;
+        let macro_ident = exported_macro_ident("container", ident, derive_input);
+        Some(quote! {
+            #[doc(hidden)]
+            #[macro_export]
+            macro_rules! #macro_ident {
+                ($($delegate:tt)*) => {
+                    ::dep_inj::__delegate! { [$($delegate)*] #derive_input }
+                };
+            }
+
+            // used by the containers delegating to this one, if any
+            #[doc(hidden)]
+            #[allow(unused_imports)]
+            #vis use #macro_ident as #ident;
+        })
+    }
+
+    /// `struct GlobalStruct<T> where ..;`, the container delegating to its sub-containers.
+    pub(super) fn delegating(&self) -> TokenStream {
+        let ident = &self.derive_input.ident;
+        let generics = &self.derive_input.generics;
+        let where_clause = &generics.where_clause;
+        quote!(struct #ident #generics #where_clause;)
+    }
+
+    /// `StorageContainer! { struct GlobalStruct; storage }` for the `#[component(delegate)]`s,
+    /// under the fields `prefix` of the top container.
+    pub(super) fn delegates(
+        &self,
+        top: &TokenStream,
+        prefix: &[syn::Member],
+    ) -> syn::Result<Vec<TokenStream>> {
+        self.components
+            .iter()
+            .filter(|component| component.delegate)
+            .map(|component| {
+                let path = match &component.ty {
+                    syn::Type::Path(ty) if ty.qself.is_none() => &ty.path,
+                    ty => {
+                        return Err(syn::Error::new(
+                            ty.span(),
+                            "expect a sub-container deriving `Container`",
+                        ))
+                    }
+                };
+                if let Some(segment) = path
+                    .segments
+                    .iter()
+                    .find(|segment| !segment.arguments.is_empty())
+                {
+                    return Err(syn::Error::new(
+                        segment.span(),
+                        "generic sub-containers cannot be delegated",
+                    ));
+                }
+                let member = &component.member;
+                Ok(quote! {
+                    #path! { #top #(#prefix.)* #member }
+                })
+            })
+            .collect()
+    }
+}
+
+/// `[struct GlobalStruct; storage] struct StorageContainer { .. }`, passed to `__delegate!` by
+/// the macro generated for the sub-container.
+pub(crate) struct DelegateInput {
+    top: syn::ItemStruct,
+    /// the path of the sub-container in the top one, like `storage.blobs`
+    members: Vec<syn::Member>,
+    sub: syn::DeriveInput,
+}
+
+impl Parse for DelegateInput {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let content;
+        syn::bracketed!(content in input);
+        let top = content.parse()?;
+        let members = Punctuated::<syn::Member, Token![.]>::parse_separated_nonempty(&content)?;
+        Ok(Self {
+            top,
+            members: members.into_iter().collect(),
+            sub: input.parse()?,
+        })
+    }
+}
+
+/// Implement `AsRef` and `AsMut` of the components and the interfaces of a sub-container for the
+/// top container, forwarding through the field.
+pub(crate) fn delegate_impl(input: DelegateInput) -> syn::Result<TokenStream> {
+    let DelegateInput { top, members, sub } = &input;
+    // checked to be `#[delegatable]` when the sub-container was derived
+    let container = Container::parse(sub)?;
+
+    let ident = &top.ident;
+    let (_, ty_generics, _) = top.generics.split_for_impl();
+    // the proxies are injected with the top container, reaching the states through the field
+    let target = Target {
+        generics: top.generics.clone(),
+        self_ty: parse_quote!(#ident #ty_generics),
+        container: quote!(#(#members.)*),
+        transaction: None,
+        poison: None,
+    };
+    let as_refs = container.components.iter().map(|component| {
+        let member = &component.member;
+        impl_as_ref(
+            &target,
+            component,
+            quote!(#(#members.)* #member),
+            true,
+            None,
+        )
+    });
+    let delegates = container.delegates(&top.to_token_stream(), members)?;
+    let depends = container.check_depends(&target);
+    let forwards = container
+        .interfaces()?
+        .iter()
+        .map(|(interface, dispatch)| impl_interface(&target, interface, dispatch))
+        .collect::<Vec<_>>();
+
+    Ok(quote! {
+        #(#as_refs)*
+        #(#delegates)*
+        #(#forwards)*
+        #depends
+    })
+}
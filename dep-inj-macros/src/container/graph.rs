@@ -0,0 +1,75 @@
+use super::{proxy_type, type_name, Component, Container, Target};
+use crate::interface_name;
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+
+impl Container<'_> {
+    /// `fn dependency_graph() -> Graph`, the deps of the proxied components declared by
+    /// `Dependencies`, the remote ones depending on nothing in the container.
+    pub(super) fn dependency_graph(&self, target: &Target) -> TokenStream {
+        let Target {
+            generics, self_ty, ..
+        } = target;
+        let (impl_generics, _, where_clause) = generics.split_for_impl();
+        let vis = &self.derive_input.vis;
+        let name = self.derive_input.ident.to_string();
+        let components = self.components.iter().map(|component| {
+            let Component {
+                member,
+                ty,
+                proxy,
+                provides,
+                remote,
+                ..
+            } = component;
+            let member = member.to_token_stream().to_string();
+            let ty_name = type_name(ty);
+            let provides = provides.iter().map(interface_name);
+            let depends = match (proxy, remote) {
+                (Some(_), false) => {
+                    quote!(<#ty as ::dep_inj::graph::Dependencies>::DEPENDENCIES.to_vec())
+                }
+                _ => quote!(::std::vec::Vec::new()),
+            };
+            quote! {
+                .with_component(::dep_inj::graph::Component {
+                    name: #member,
+                    ty: #ty_name,
+                    provides: ::std::vec![#(#provides),*],
+                    depends: #depends,
+                })
+            }
+        });
+        quote! {
+            impl #impl_generics #self_ty #where_clause {
+                /// The dependencies between the components, generated by `#[derive(Container)]`.
+                #vis fn dependency_graph() -> ::dep_inj::graph::Graph {
+                    ::dep_inj::graph::Graph::new(#name)
+                        #(#components)*
+                }
+            }
+        }
+    }
+
+    /// `const _: fn() = || assert::<OddProxy<GlobalStruct>>();`, asserting that the container
+    /// implements the interfaces declared by `depend(..)` for each proxied component.
+    pub(super) fn check_depends(&self, target: &Target) -> Option<TokenStream> {
+        if !target.generics.params.is_empty() {
+            return None;
+        }
+
+        let self_ty = &target.self_ty;
+        let proxies = self
+            .components
+            .iter()
+            .filter(|component| !component.remote)
+            .filter_map(|component| component.proxy.as_ref())
+            .map(|proxy| proxy_type(proxy, self_ty.clone()));
+        Some(quote! {
+            const _: fn() = || {
+                fn assert_satisfied<__P: ?::core::marker::Sized + ::dep_inj::wiring::Satisfied>() {}
+                #(assert_satisfied::<#proxies>();)*
+            };
+        })
+    }
+}
@@ -0,0 +1,74 @@
+use super::{proxy_type, Component, Container, Target};
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use syn::parse_quote;
+
+impl Container<'_> {
+    /// `fn health(&self) -> HealthReport`, if any component is `#[component(health)]`.
+    pub(super) fn health(&self, target: &Target) -> Option<TokenStream> {
+        if !self.components.iter().any(|component| component.health) {
+            return None;
+        }
+        let Target {
+            generics,
+            self_ty,
+            poison,
+            ..
+        } = target;
+        let (impl_generics, _, where_clause) = generics.split_for_impl();
+        let vis = &self.derive_input.vis;
+        let checks = self.components.iter().map(|component| {
+            let Component {
+                member,
+                proxy,
+                health,
+                isolate,
+                actor,
+                ..
+            } = component;
+            if !health {
+                return quote!(::dep_inj::health::Health::Healthy);
+            }
+            let proxy = proxy.as_ref().unwrap();
+            let check = if actor.is_some() {
+                // inferred as the deps on the thread
+                let proxy = proxy_type(proxy, parse_quote!(_));
+                quote! {
+                    ::dep_inj::actor::Actor::call(&self.#member.actor, |__deps| {
+                        ::dep_inj::health::HealthCheck::check(<#proxy>::inj_ref(&**__deps))
+                    })
+                }
+            } else {
+                let proxy = proxy_type(proxy, parse_quote!(Self));
+                quote!(::dep_inj::health::HealthCheck::check(<#proxy>::inj_ref(self)))
+            };
+            let check = quote! {
+                ::dep_inj::health::check(::std::panic::AssertUnwindSafe(|| #check))
+            };
+            match (isolate, poison) {
+                (true, Some(_)) => {
+                    let name = member.to_token_stream().to_string();
+                    quote! {
+                        if ::dep_inj::isolation::Isolation::is_poisoned(self, #name) {
+                            ::dep_inj::health::Health::Unhealthy("poisoned".to_string())
+                        } else {
+                            #check
+                        }
+                    }
+                }
+                _ => check,
+            }
+        });
+        Some(quote! {
+            impl #impl_generics #self_ty #where_clause {
+                /// Check the health of the components, generated by `#[derive(Container)]`.
+                #vis fn health(&self) -> ::dep_inj::health::HealthReport {
+                    ::dep_inj::health::HealthReport::aggregate(
+                        &Self::dependency_graph(),
+                        ::std::vec![#(#checks),*],
+                    )
+                }
+            }
+        })
+    }
+}
@@ -0,0 +1,115 @@
+use super::{same_path, Component, Container, Target};
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use syn::{
+    parse::{Parse, ParseStream},
+    punctuated::Punctuated,
+    spanned::Spanned,
+    Token,
+};
+
+/// `#[hot_swap(IsEven => [even_state, cached_even_state])] even_slot: HotSwap`
+pub(super) struct HotSwapSlot {
+    pub(super) member: syn::Member,
+    pub(super) ty: syn::Type,
+    pub(super) interface: syn::Path,
+    pub(super) candidates: Vec<syn::Member>,
+}
+
+pub(super) struct HotSwapArgs {
+    pub(super) interface: syn::Path,
+    pub(super) candidates: Punctuated<syn::Member, Token![,]>,
+}
+
+impl Parse for HotSwapArgs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let interface = input.parse()?;
+        input.parse::<Token![=>]>()?;
+        let content;
+        syn::bracketed!(content in input);
+        Ok(Self {
+            interface,
+            candidates: content.parse_terminated(syn::Member::parse)?,
+        })
+    }
+}
+
+impl Container<'_> {
+    /// The candidate components of a hot swap slot, in the order of the slot.
+    pub(super) fn candidates(&self, slot: &HotSwapSlot) -> syn::Result<Vec<&Component>> {
+        if slot.candidates.is_empty() {
+            return Err(syn::Error::new(
+                slot.interface.span(),
+                "a hot swap slot needs at least one candidate",
+            ));
+        }
+
+        slot.candidates
+            .iter()
+            .map(|member| {
+                self.components
+                    .iter()
+                    .find(|component| component.member == *member)
+                    .filter(|component| {
+                        component
+                            .provides
+                            .iter()
+                            .any(|interface| same_path(interface, &slot.interface))
+                    })
+                    .ok_or_else(|| {
+                        syn::Error::new(
+                            member.span(),
+                            "the candidate should be a component providing the interface",
+                        )
+                    })
+            })
+            .collect()
+    }
+
+    /// Checks the capacity of a hot swap slot at compile time,
+    /// which is only possible for non generic containers.
+    pub(super) fn check_hot_swap(&self, slot: &HotSwapSlot) -> Option<TokenStream> {
+        if !self.derive_input.generics.params.is_empty() {
+            return None;
+        }
+
+        let ty = &slot.ty;
+        let len = slot.candidates.len();
+        let message = format!(
+            "the capacity of the hot swap slot `{}` should be the number of its candidates, {}",
+            slot.member.to_token_stream(),
+            len,
+        );
+        Some(quote! {
+            const _: () = ::core::assert!(<#ty>::CAPACITY == #len, #message);
+        })
+    }
+}
+
+/// The `call` of the current candidate of the slot, tracked as in flight until it finishes.
+pub(super) fn hot_swap_call(
+    target: &Target,
+    slot: &HotSwapSlot,
+    candidates: &[&Component],
+    kind: &str,
+    call: impl Fn(&Component) -> TokenStream,
+) -> TokenStream {
+    let container = &target.container;
+    let member = &slot.member;
+    // `self` is not dereferenced, and the guard doesn't borrow it
+    let slot = match kind {
+        "value" => quote!($this.#container #member),
+        _ => quote!((*$this).#container #member),
+    };
+    let indices = 0..candidates.len();
+    let calls = candidates.iter().map(|component| call(component));
+    // in-flight calls are tracked until they finish, the ones taking `self` as well
+    quote! {{
+        let __guard = ::dep_inj::hot_swap::HotSwap::enter(&#slot);
+        let __index = ::dep_inj::hot_swap::HotSwapGuard::index(&__guard);
+        match __index {
+            #(#indices => #calls,)*
+            _ => ::core::unreachable!(),
+        }
+    }}
+}
@@ -0,0 +1,97 @@
+use super::{Component, Container, Target};
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+
+impl Container<'_> {
+    /// `impl Isolation`, resetting the `#[component(isolate)]`s.
+    pub(super) fn isolation(&self, target: &Target) -> Option<TokenStream> {
+        let Target {
+            generics,
+            self_ty,
+            poison,
+            ..
+        } = target;
+        let poison = poison.as_ref()?;
+        let (impl_generics, _, where_clause) = generics.split_for_impl();
+
+        let isolated = self
+            .components
+            .iter()
+            .filter(|component| component.isolate)
+            .collect::<Vec<_>>();
+        let indices = isolated
+            .iter()
+            .map(|component| component.index)
+            .collect::<Vec<_>>();
+        let names = isolated
+            .iter()
+            .map(|component| component.member.to_token_stream().to_string())
+            .collect::<Vec<_>>();
+        let members = isolated.iter().map(|component| &component.member);
+        let tys = isolated.iter().map(|component| &component.ty);
+        Some(quote! {
+            impl #impl_generics ::dep_inj::isolation::Isolation for #self_ty #where_clause {
+                fn isolated(&self) -> &'static [&'static str] {
+                    &[#(#names),*]
+                }
+
+                fn is_poisoned(&self, component: &str) -> bool {
+                    match component {
+                        #(#names => self.#poison.is_poisoned(#indices),)*
+                        _ => false,
+                    }
+                }
+
+                fn poison(&self, component: &str) {
+                    match component {
+                        #(#names => self.#poison.poison(#indices),)*
+                        _ => {}
+                    }
+                }
+
+                fn reset(&mut self, component: &str) -> bool {
+                    match component {
+                        #(
+                            #names => {
+                                self.#members = <#tys as ::core::default::Default>::default();
+                                self.#poison.clear(#indices);
+                                true
+                            }
+                        )*
+                        _ => false,
+                    }
+                }
+            }
+        })
+    }
+}
+
+/// The `call` of an isolated component, failing fast if it is poisoned, and poisoning it if the
+/// call panics, the panic returned as the `Err` of the method.
+pub(super) fn isolate_call(
+    poison: &TokenStream,
+    component: &Component,
+    call: TokenStream,
+) -> TokenStream {
+    let index = component.index;
+    let name = component.member.to_token_stream().to_string();
+    // shared, since the call may consume `$this`
+    quote! {{
+        let __poison = ::dep_inj::isolation::Poison::share(&$this.#poison);
+        let __result = ::dep_inj::isolation::isolate(
+            &__poison,
+            #index,
+            #name,
+            ::core::panic::AssertUnwindSafe(|| #call),
+        );
+        match __result {
+            ::core::result::Result::Ok(__value) => __value,
+            ::core::result::Result::Err(__err) => {
+                // names the method in the error of a result without `Err`
+                #[allow(non_camel_case_types)]
+                struct $method;
+                ::dep_inj::isolation::Recover::<$method>::__dep_inj_recover(__err)
+            }
+        }
+    }}
+}
@@ -0,0 +1,23 @@
+use super::{inj_ident, proxy_type, Component};
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::parse_quote;
+
+/// `<Metrics<EvenProxy<Self>> as IsEven>::is_even(<Metrics<EvenProxy<Self>>>::inj_arc(..), n)`,
+/// the proxy of the component wrapped in its layers, casted from the innermost.
+pub(super) fn layered_call(
+    interface: &syn::Path,
+    component: &Component,
+    kind: &str,
+) -> TokenStream {
+    let inj = inj_ident(kind);
+    let mut proxy = proxy_type(component.proxy.as_ref().unwrap(), parse_quote!(Self));
+    let mut this = quote!(<#proxy>::#inj($this));
+    for layer in component.layers.iter().rev() {
+        proxy = proxy_type(layer, proxy);
+        this = quote!(<#proxy>::#inj(#this));
+    }
+    quote! {
+        <#proxy as #interface>::$method(#this $(, $arg)*)
+    }
+}
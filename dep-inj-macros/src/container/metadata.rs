@@ -0,0 +1,56 @@
+use super::{proxy_type, type_name, Component, Container, Target};
+use crate::interface_name;
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use syn::parse_quote;
+
+impl Container<'_> {
+    /// `fn component_metadata() -> Vec<ComponentMetadata>`, with the `Describe` of the proxies.
+    pub(super) fn component_metadata(&self, target: &Target) -> TokenStream {
+        let Target {
+            generics, self_ty, ..
+        } = target;
+        let (impl_generics, _, where_clause) = generics.split_for_impl();
+        let vis = &self.derive_input.vis;
+        let components = self.components.iter().map(|component| {
+            let Component {
+                member,
+                ty,
+                proxy,
+                provides,
+                ..
+            } = component;
+            let member = member.to_token_stream().to_string();
+            let ty_name = type_name(ty);
+            let provides = provides.iter().map(interface_name);
+            let proxy = match proxy {
+                Some(proxy) => {
+                    let proxy = proxy_type(proxy, parse_quote!(Self));
+                    quote! {
+                        ::core::option::Option::Some(
+                            <#proxy as ::dep_inj::metadata::Describe>::METADATA,
+                        )
+                    }
+                }
+                None => quote!(::core::option::Option::None),
+            };
+            quote! {
+                ::dep_inj::metadata::ComponentMetadata {
+                    name: #member,
+                    ty: #ty_name,
+                    size: ::core::mem::size_of::<#ty>(),
+                    provides: ::std::vec![#(#provides),*],
+                    proxy: #proxy,
+                }
+            }
+        });
+        quote! {
+            impl #impl_generics #self_ty #where_clause {
+                /// The metadata of the components, generated by `#[derive(Container)]`.
+                #vis fn component_metadata() -> ::std::vec::Vec<::dep_inj::metadata::ComponentMetadata> {
+                    ::std::vec![#(#components),*]
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,136 @@
+use super::{check_interface, impl_as_ref, impl_interface, same_path, Container, Dispatch, Target};
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use syn::{
+    parse::{Parse, ParseStream},
+    parse_quote,
+    punctuated::Punctuated,
+    Token,
+};
+
+/// `#[overridable(pub struct EvenStubbed: IsEven)]`
+pub(super) struct Overridable {
+    vis: syn::Visibility,
+    ident: syn::Ident,
+    interfaces: Vec<syn::Path>,
+}
+
+impl Parse for Overridable {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let vis = input.parse()?;
+        input.parse::<Token![struct]>()?;
+        let ident = input.parse()?;
+        input.parse::<Token![:]>()?;
+        let interfaces = Punctuated::<syn::Path, Token![+]>::parse_separated_nonempty(input)?;
+        for interface in &interfaces {
+            check_interface(interface)?;
+        }
+        Ok(Self {
+            vis,
+            ident,
+            interfaces: interfaces.into_iter().collect(),
+        })
+    }
+}
+
+impl Container<'_> {
+    /// The override owning the container and a stub, with the `AsRef`s and `AsMut`s and the
+    /// interfaces of the container, except the overridden interfaces forwarded to the stub.
+    pub(super) fn override_def(
+        &self,
+        overridable: &Overridable,
+        interfaces: &[(&syn::Path, Dispatch<'_>)],
+    ) -> TokenStream {
+        let ident = &self.derive_input.ident;
+        let (_, ty_generics, _) = self.derive_input.generics.split_for_impl();
+        let Overridable {
+            vis,
+            ident: override_ident,
+            interfaces: overridden,
+        } = overridable;
+
+        // the other interfaces may reach the stub through the proxies as well
+        let mut generics = self.derive_input.generics.clone();
+        generics.params.push(parse_quote!(Stub));
+        generics
+            .make_where_clause()
+            .predicates
+            .push(parse_quote!(Stub: #(#overridden)+*));
+        let (impl_generics, override_ty_generics, where_clause) = generics.split_for_impl();
+        let target = Target {
+            generics: generics.clone(),
+            self_ty: parse_quote!(#override_ident #override_ty_generics),
+            container: quote!(inner.),
+            transaction: self
+                .transaction
+                .as_ref()
+                .map(|member| quote!(inner.#member)),
+            poison: self.poison.as_ref().map(|member| quote!(inner.#member)),
+        };
+
+        let as_refs = self.components.iter().enumerate().map(|(i, component)| {
+            let member = &component.member;
+            let checkpoint = component.rollback.then_some(i);
+            impl_as_ref(&target, component, quote!(inner.#member), true, checkpoint)
+        });
+        let transactional = self.transactional(&target);
+        let forwards = interfaces
+            .iter()
+            .filter(|(interface, _)| {
+                !overridden
+                    .iter()
+                    .any(|overridden| same_path(overridden, interface))
+            })
+            .map(|(interface, dispatch)| impl_interface(&target, interface, dispatch));
+        let stubs = overridden
+            .iter()
+            .map(|interface| impl_interface(&target, interface, &Dispatch::Stub));
+
+        let doc = format!(
+            "`{}` with {} overridden by a stub.",
+            ident,
+            overridden
+                .iter()
+                .map(|interface| format!("`{}`", interface.to_token_stream()))
+                .collect::<Vec<_>>()
+                .join(", "),
+        );
+        quote! {
+            #[doc = #doc]
+            #vis struct #override_ident #generics #where_clause {
+                #vis inner: #ident #ty_generics,
+                #vis stub: ::std::sync::Arc<Stub>,
+            }
+
+            impl #impl_generics #override_ident #override_ty_generics #where_clause {
+                #vis fn new(inner: #ident #ty_generics, stub: ::std::sync::Arc<Stub>) -> Self {
+                    Self { inner, stub }
+                }
+            }
+
+            #(#as_refs)*
+            #transactional
+            #(#forwards)*
+            #(#stubs)*
+        }
+    }
+}
+
+/// `<Stub as IsEven>::is_even(Arc::clone(&(*$this).stub), n)`, or the message of the compile error
+/// for a receiver which cannot be stubbed.
+pub(super) fn stub_call(interface: &syn::Path, kind: &str) -> Result<TokenStream, String> {
+    // the stub is shared with the test through an `Arc`, so it's
+    // only lent by reference or by another `Arc`
+    let stub = match kind {
+        "ref" => quote!(&*(*$this).stub),
+        "arc" => quote!(::std::sync::Arc::clone(&(*$this).stub)),
+        _ => {
+            return Err(format!(
+                "a `{kind}` receiver cannot be stubbed, as the stub is shared in an `Arc`"
+            ))
+        }
+    };
+    Ok(quote! {
+        <Stub as #interface>::$method(#stub $(, $arg)*)
+    })
+}
@@ -0,0 +1,35 @@
+use super::Container;
+use crate::pin_guards;
+use proc_macro2::TokenStream;
+use quote::quote;
+
+impl Container<'_> {
+    /// `unsafe impl StructuralPin<FooState> for GlobalStruct` for the `#[component(pin)]`s.
+    pub(super) fn structural_pins(&self) -> TokenStream {
+        let pinned_tys = self
+            .components
+            .iter()
+            .filter(|component| component.pin)
+            .map(|component| &component.ty)
+            .collect::<Vec<_>>();
+        if pinned_tys.is_empty() {
+            return TokenStream::new();
+        }
+
+        let ident = &self.derive_input.ident;
+        let generics = &self.derive_input.generics;
+        let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+        let pin_guards = pin_guards(ident, generics, &pinned_tys);
+        quote! {
+            #(
+                // SAFETY: the field is never moved, guarded by `pin_guards`
+                unsafe impl #impl_generics ::dep_inj::pin::StructuralPin<#pinned_tys>
+                    for #ident #ty_generics #where_clause
+                {
+                }
+            )*
+
+            #pin_guards
+        }
+    }
+}
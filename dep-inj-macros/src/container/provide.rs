@@ -0,0 +1,96 @@
+use super::{tag::has_traits, Component, Target};
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::spanned::Spanned;
+
+/// A component reached by `Provide` only has no `AsRef` to pin or delegate it with.
+pub(super) fn check_access(component: &Component, attr: &syn::Attribute) -> syn::Result<()> {
+    if component.access == Some(true) && (component.pin || component.delegate) {
+        return Err(syn::Error::new(
+            attr.span(),
+            "a component provided by `Provide` only cannot be pinned or delegated",
+        ));
+    }
+    Ok(())
+}
+
+/// `impl AsRef<EvenState> for GlobalStruct` and `Provide<EvenState>` reaching `self.#field`, and
+/// `AsMut` and `ProvideMut` if `as_mut`, or only one pair of them for a component marked by
+/// `access = AsRef` or `access = Provide`, or `Has<Replica, EvenState>` and `HasMut` for a tagged
+/// component. The `checkpoint`th component is checkpointed by `as_mut` in a transaction.
+pub(super) fn impl_as_ref(
+    target: &Target,
+    component: &Component,
+    field: TokenStream,
+    as_mut: bool,
+    checkpoint: Option<usize>,
+) -> TokenStream {
+    let Target {
+        generics,
+        self_ty,
+        transaction,
+        ..
+    } = target;
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+    let ty = &component.ty;
+    let field = quote!(self.#field);
+    let mut traits = vec![];
+    match &component.tag {
+        Some(tag) => traits.push(has_traits(tag, ty)),
+        None => {
+            if component.access != Some(true) {
+                traits.push((
+                    quote!(::core::convert::AsRef<#ty>),
+                    quote!(as_ref),
+                    quote!(::core::convert::AsMut<#ty>),
+                    quote!(as_mut),
+                ));
+            }
+            if component.access != Some(false) {
+                traits.push((
+                    quote!(::dep_inj::provide::Provide<#ty>),
+                    quote!(provide),
+                    quote!(::dep_inj::provide::ProvideMut<#ty>),
+                    quote!(provide_mut),
+                ));
+            }
+        }
+    }
+    let touch = checkpoint
+        .zip(transaction.as_ref())
+        .map(|(index, transaction)| {
+            quote! {
+                ::dep_inj::transaction::Transaction::touch(&mut self.#transaction, #index, || {
+                    ::dep_inj::transaction::Checkpoint::checkpoint(&#field)
+                });
+            }
+        });
+
+    let impls = traits
+        .into_iter()
+        .map(|(as_ref_trait, as_ref, as_mut_trait, as_mut_fn)| {
+            let as_ref = quote! {
+                impl #impl_generics #as_ref_trait for #self_ty #where_clause {
+                    #[inline]
+                    fn #as_ref(&self) -> &#ty {
+                        &#field
+                    }
+                }
+            };
+            if !as_mut {
+                return as_ref;
+            }
+            quote! {
+                #as_ref
+
+                impl #impl_generics #as_mut_trait for #self_ty #where_clause {
+                    #[inline]
+                    fn #as_mut_fn(&mut self) -> &mut #ty {
+                        #touch
+                        &mut #field
+                    }
+                }
+            }
+        });
+    quote!(#(#impls)*)
+}
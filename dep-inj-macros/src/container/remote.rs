@@ -0,0 +1,35 @@
+use super::{Component, Target};
+use proc_macro2::TokenStream;
+use quote::quote;
+
+/// `<RemoteIsEven as IsEven>::is_even(Arc::new(Clone::clone(&$this.even)), n)`, the receiver made
+/// of a clone of the client sharing its connection.
+pub(super) fn remote_call(
+    target: &Target,
+    interface: &syn::Path,
+    component: &Component,
+    kind: &str,
+) -> TokenStream {
+    let container = &target.container;
+    let member = &component.member;
+    let ty = &component.ty;
+    let field = quote!((*$this).#container #member);
+    let cloned = quote!(<#ty as ::core::clone::Clone>::clone(&#field));
+    let receiver = match kind {
+        "value" => cloned,
+        "ref" => quote!(&#field),
+        "ref_mut" => quote!(&mut #field),
+        "box" => quote!(::std::boxed::Box::new(#cloned)),
+        "rc" => quote!(::std::rc::Rc::new(#cloned)),
+        "arc" => quote!(::std::sync::Arc::new(#cloned)),
+        "pin_ref" => quote!(::core::pin::Pin::new(&#field)),
+        "pin_ref_mut" => quote!(::core::pin::Pin::new(&mut #cloned)),
+        "pin_box" => quote!(::std::boxed::Box::pin(#cloned)),
+        "pin_rc" => quote!(::std::rc::Rc::pin(#cloned)),
+        "pin_arc" => quote!(::std::sync::Arc::pin(#cloned)),
+        _ => unreachable!(),
+    };
+    quote! {
+        <#ty as #interface>::$method(#receiver $(, $arg)*)
+    }
+}
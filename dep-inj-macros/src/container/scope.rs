@@ -0,0 +1,257 @@
+use super::{
+    check_interface, field_member, impl_as_ref, impl_interface, parse_component, providers,
+    same_path, same_type, static_dispatch, tag::same_tag, Component, Container, Dispatch, Target,
+};
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{
+    parse::{Parse, ParseStream},
+    parse_quote,
+    punctuated::Punctuated,
+    spanned::Spanned,
+    Token,
+};
+
+/// `#[scope(pub struct RequestScope { #[component] tx: TxState }, provide(IsOdd, IsEven => even))]`
+pub(super) struct Scope {
+    item: syn::ItemStruct,
+    components: Vec<Component>,
+    /// the interfaces of the container provided by the scope as well
+    provides: Vec<ScopeProvide>,
+}
+
+/// `IsOdd`, dispatched as the container does, or `IsOdd => odd_state`,
+/// dispatched to a container component which the container doesn't forward `IsOdd` to.
+pub(super) struct ScopeProvide {
+    interface: syn::Path,
+    component: Option<syn::Member>,
+}
+
+pub(super) struct ScopeArgs {
+    item: syn::ItemStruct,
+    provides: Punctuated<ScopeProvide, Token![,]>,
+}
+
+impl Parse for ScopeArgs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let item = input.parse()?;
+        let mut provides = Punctuated::new();
+        if !input.is_empty() {
+            input.parse::<Token![,]>()?;
+            let ident: syn::Ident = input.parse()?;
+            if ident != "provide" {
+                return Err(syn::Error::new(
+                    ident.span(),
+                    "expect `provide(Interface, ..)`",
+                ));
+            }
+            let content;
+            syn::parenthesized!(content in input);
+            provides = content.parse_terminated(ScopeProvide::parse)?;
+        }
+        Ok(Self { item, provides })
+    }
+}
+
+impl Parse for ScopeProvide {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let interface = input.parse()?;
+        check_interface(&interface)?;
+        let mut component = None;
+        if input.peek(Token![=>]) {
+            input.parse::<Token![=>]>()?;
+            component = Some(input.parse()?);
+        }
+        Ok(Self {
+            interface,
+            component,
+        })
+    }
+}
+
+impl Scope {
+    pub(super) fn parse(args: ScopeArgs) -> syn::Result<Self> {
+        let ScopeArgs { item, provides } = args;
+        if !item.generics.params.is_empty() {
+            return Err(syn::Error::new(
+                item.generics.span(),
+                "a scope takes the generics of the container, and a `'parent` lifetime",
+            ));
+        }
+
+        let mut components = vec![];
+        for (i, field) in item.fields.iter().enumerate() {
+            for attr in &field.attrs {
+                if attr.path.is_ident("component") {
+                    let component = parse_component(field_member(i, field), &field.ty, attr)?;
+                    if component.pin
+                        || component.rollback
+                        || component.isolate
+                        || component.restart
+                        || component.actor.is_some()
+                        || component.remote
+                        || component.health
+                        || component.config
+                        || component.delegate
+                        || component.default
+                    {
+                        return Err(syn::Error::new(
+                            attr.span(),
+                            "the components of a scope cannot be pinned, rolled back, isolated, \
+                            restarted, actors, remote, health checked, configured, delegated or defaulted",
+                        ));
+                    }
+                    components.push(component);
+                }
+            }
+        }
+
+        Ok(Self {
+            item,
+            components,
+            provides: provides.into_iter().collect(),
+        })
+    }
+}
+
+impl Container<'_> {
+    /// The scope struct borrowing the container, with the `AsRef`s and interfaces of its own
+    /// components, and the `AsRef`s and the listed interfaces of the container.
+    pub(super) fn scope_def(
+        &self,
+        scope: &Scope,
+        interfaces: &[(&syn::Path, Dispatch<'_>)],
+    ) -> syn::Result<TokenStream> {
+        let ident = &self.derive_input.ident;
+        let (_, ty_generics, _) = self.derive_input.generics.split_for_impl();
+        let parent_ty: syn::Type = parse_quote!(#ident #ty_generics);
+
+        let mut generics = self.derive_input.generics.clone();
+        generics.params.insert(0, parse_quote!('parent));
+        let (impl_generics, scope_ty_generics, where_clause) = generics.split_for_impl();
+
+        let mut item = scope.item.clone();
+        let scope_ident = scope.item.ident.clone();
+        let target = Target {
+            generics: generics.clone(),
+            self_ty: parse_quote!(#scope_ident #scope_ty_generics),
+            container: quote!(parent.),
+            transaction: None,
+            poison: self.poison.as_ref().map(|member| quote!(parent.#member)),
+        };
+
+        // the own components overlay the ones of the container
+        let overlaid = |overlaid: &Component| {
+            scope.components.iter().any(|component| {
+                same_type(&component.ty, &overlaid.ty) && same_tag(component, overlaid)
+            })
+        };
+        let own_as_refs = scope.components.iter().map(|component| {
+            let member = &component.member;
+            impl_as_ref(&target, component, quote!(#member), true, None)
+        });
+        let parent_as_refs = self
+            .components
+            .iter()
+            .filter(|component| !overlaid(component))
+            .map(|component| {
+                let member = &component.member;
+                impl_as_ref(&target, component, quote!(parent.#member), false, None)
+            });
+
+        let own_interfaces = providers(&scope.components)
+            .into_iter()
+            .map(|(interface, components)| {
+                Ok((interface, static_dispatch(interface, &components)?))
+            })
+            .collect::<syn::Result<Vec<_>>>()?;
+        let mut parent_interfaces = vec![];
+        for ScopeProvide {
+            interface,
+            component,
+        } in &scope.provides
+        {
+            if own_interfaces
+                .iter()
+                .any(|(provided, _)| same_path(provided, interface))
+            {
+                continue;
+            }
+            let dispatch = match component {
+                Some(member) => self
+                    .components
+                    .iter()
+                    .find(|component| component.member == *member && component.proxy.is_some())
+                    .map(Dispatch::Static)
+                    .ok_or_else(|| {
+                        syn::Error::new(
+                            member.span(),
+                            "expect a component of the container with a proxy",
+                        )
+                    })?,
+                // a hot swap slot is reached through the `parent`
+                None => interfaces
+                    .iter()
+                    .find(|(provided, _)| same_path(provided, interface))
+                    .map(|(_, dispatch)| dispatch.clone())
+                    .ok_or_else(|| {
+                        syn::Error::new(
+                            interface.span(),
+                            "the interface is not provided by the container, \
+                            try `Interface => component`",
+                        )
+                    })?,
+            };
+            parent_interfaces.push((interface, dispatch));
+        }
+        let forwards = own_interfaces
+            .iter()
+            .chain(&parent_interfaces)
+            .map(|(interface, dispatch)| impl_interface(&target, interface, dispatch));
+
+        // `pub struct RequestScope<'parent> { pub parent: &'parent GlobalStruct, tx: TxState }`
+        let syn::Fields::Named(fields) = &mut item.fields else {
+            return Err(syn::Error::new(
+                item.fields.span(),
+                "a scope should be a struct with named fields",
+            ));
+        };
+        for field in &mut fields.named {
+            field.attrs.retain(|attr| !attr.path.is_ident("component"));
+        }
+        let field_idents = fields
+            .named
+            .iter()
+            .map(|field| field.ident.clone().unwrap())
+            .collect::<Vec<_>>();
+        let field_tys = fields
+            .named
+            .iter()
+            .map(|field| field.ty.clone())
+            .collect::<Vec<_>>();
+        let vis = item.vis.clone();
+        let parent = syn::parse::Parser::parse2(
+            syn::Field::parse_named,
+            quote!(#vis parent: &'parent #parent_ty),
+        )?;
+        fields.named.insert(0, parent);
+        item.generics = generics.clone();
+
+        Ok(quote! {
+            #item
+
+            impl #impl_generics #scope_ident #scope_ty_generics #where_clause {
+                #vis fn new(parent: &'parent #parent_ty #(, #field_idents: #field_tys)*) -> Self {
+                    Self {
+                        parent,
+                        #(#field_idents,)*
+                    }
+                }
+            }
+
+            #(#own_as_refs)*
+            #(#parent_as_refs)*
+            #(#forwards)*
+        })
+    }
+}
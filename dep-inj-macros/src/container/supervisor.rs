@@ -0,0 +1,49 @@
+use super::{Component, Container};
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+
+impl Container<'_> {
+    /// `impl Supervised` for the `#[supervisor]`, restarting the isolated components and the
+    /// `#[component(restart)]` ones.
+    pub(super) fn supervised(&self) -> Option<TokenStream> {
+        let supervisor = self.supervisor.as_ref()?;
+        let ident = &self.derive_input.ident;
+        let (impl_generics, ty_generics, where_clause) =
+            self.derive_input.generics.split_for_impl();
+        let restarts = self
+            .components
+            .iter()
+            // the others are not restarted, reported by `false`
+            .filter(|component| component.isolate || component.restart)
+            .map(|component| {
+                let Component { member, ty, .. } = component;
+                let name = member.to_token_stream().to_string();
+                let restart = match component.isolate {
+                    true => quote!(::dep_inj::isolation::Isolation::reset(self, #name)),
+                    false => quote! {{
+                        self.#member = <#ty as ::core::default::Default>::default();
+                        true
+                    }},
+                };
+                quote!(#name => #restart,)
+            });
+        Some(quote! {
+            impl #impl_generics ::dep_inj::supervisor::Supervised for #ident #ty_generics #where_clause {
+                fn supervisor(&mut self) -> &mut ::dep_inj::supervisor::Supervisor {
+                    &mut self.#supervisor
+                }
+
+                fn graph(&self) -> ::dep_inj::graph::Graph {
+                    Self::dependency_graph()
+                }
+
+                fn restart(&mut self, component: &str) -> bool {
+                    match component {
+                        #(#restarts)*
+                        _ => false,
+                    }
+                }
+            }
+        })
+    }
+}
@@ -0,0 +1,46 @@
+use super::{same_path, Component};
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::spanned::Spanned;
+
+/// A tagged component is only reached by `Has<Tag, T>`, so it's neither pinned, an actor, remote,
+/// delegated nor given an `access = ..`.
+pub(super) fn check_tag(component: &Component, attr: &syn::Attribute) -> syn::Result<()> {
+    if component.tag.is_none() {
+        return Ok(());
+    }
+    if component.pin || component.actor.is_some() || component.remote || component.delegate {
+        return Err(syn::Error::new(
+            attr.span(),
+            "a tagged component cannot be pinned, an actor, remote or delegated",
+        ));
+    }
+    if component.access.is_some() {
+        return Err(syn::Error::new(
+            attr.span(),
+            "a tagged component is reached by `Has`, not by `access = ..`",
+        ));
+    }
+    Ok(())
+}
+
+/// `(Has<Replica, EvenState>, get, HasMut<Replica, EvenState>, get_mut)`, see `impl_as_ref`.
+pub(super) fn has_traits(
+    tag: &syn::Path,
+    ty: &syn::Type,
+) -> (TokenStream, TokenStream, TokenStream, TokenStream) {
+    (
+        quote!(::dep_inj::tagged::Has<#tag, #ty>),
+        quote!(get),
+        quote!(::dep_inj::tagged::HasMut<#tag, #ty>),
+        quote!(get_mut),
+    )
+}
+
+/// Two components of the same type are the same if both are untagged, or tagged alike.
+pub(super) fn same_tag(a: &Component, b: &Component) -> bool {
+    match (&a.tag, &b.tag) {
+        (Some(a), Some(b)) => same_path(a, b),
+        (a, b) => a.is_none() && b.is_none(),
+    }
+}
@@ -0,0 +1,116 @@
+use super::{Component, Container, Dispatch, Target};
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+
+impl Container<'_> {
+    /// `impl Transactional`, rolling back the `#[component(rollback)]`s.
+    pub(super) fn transactional(&self, target: &Target) -> Option<TokenStream> {
+        let Target {
+            generics,
+            self_ty,
+            container,
+            transaction,
+            ..
+        } = target;
+        let transaction = transaction.as_ref()?;
+        let (impl_generics, _, where_clause) = generics.split_for_impl();
+
+        let rollbacks = self
+            .components
+            .iter()
+            .enumerate()
+            .filter(|(_, component)| component.rollback)
+            .map(|(i, component)| {
+                let Component { member, ty, .. } = component;
+                quote! {
+                    #i => ::dep_inj::transaction::Checkpoint::rollback(
+                        &mut self.#container #member,
+                        *__saved
+                            .downcast::<<#ty as ::dep_inj::transaction::Checkpoint>::Saved>()
+                            .unwrap(),
+                    ),
+                }
+            });
+        Some(quote! {
+            impl #impl_generics ::dep_inj::transaction::Transactional for #self_ty #where_clause {
+                #[inline]
+                fn transaction(&mut self) -> &mut ::dep_inj::transaction::Transaction {
+                    &mut self.#transaction
+                }
+
+                fn rollback(&mut self, journal: ::dep_inj::transaction::Journal) {
+                    for (__index, __saved) in journal.into_saved() {
+                        match __index {
+                            #(#rollbacks)*
+                            _ => ::core::unreachable!(),
+                        }
+                    }
+                }
+            }
+        })
+    }
+
+    /// Assert that a provided interface has a `&mut self` method, the only calls run as
+    /// transactions, by the receivers listed by the interfaces.
+    pub(super) fn check_transactional(
+        &self,
+        interfaces: &[(&syn::Path, Dispatch<'_>)],
+    ) -> Option<TokenStream> {
+        let transaction = self.transaction.as_ref()?;
+        let message = format!(
+            "the `#[transaction]` field `{}` needs a provided interface with a `&mut self` method, \
+            since only the `&mut self` calls run as transactions",
+            transaction.to_token_stream(),
+        );
+        let interfaces = interfaces.iter().map(|(interface, _)| interface);
+        Some(quote! {
+            const _: () = {
+                macro_rules! __dep_inj_ref_mut {
+                    () => { false };
+                    (ref_mut $($kind:ident)*) => { true };
+                    ($other:ident $($kind:ident)*) => { __dep_inj_ref_mut! { $($kind)* } };
+                }
+
+                ::core::assert!(
+                    false #(|| #interfaces! { receivers => __dep_inj_ref_mut })*,
+                    #message
+                );
+            };
+        })
+    }
+}
+
+/// The `body` of a `&mut self` call run as a transaction, or as a part of the one in progress.
+pub(super) fn transaction_call(body: TokenStream) -> TokenStream {
+    quote! {{
+        // picks `Failed` for a `Result`, or `NotFailed` otherwise
+        #[allow(unused_imports)]
+        use ::dep_inj::transaction::{Failed as _, NotFailed as _};
+        let __transaction = ::dep_inj::transaction::Transactional::transaction($this);
+        if __transaction.is_active() {
+            // a nested call, whose panic is caught by the top-level one
+            __transaction.enter();
+            let __value = #body;
+            ::dep_inj::transaction::Transactional::transaction($this).leave();
+            __value
+        } else {
+            __transaction.enter();
+            let __result = ::std::panic::catch_unwind(
+                ::core::panic::AssertUnwindSafe(|| #body),
+            );
+            let __failed = match &__result {
+                ::core::result::Result::Ok(__value) => __value.__dep_inj_failed(),
+                ::core::result::Result::Err(_) => true,
+            };
+            let __journal =
+                ::dep_inj::transaction::Transactional::transaction($this).finish();
+            if __failed {
+                ::dep_inj::transaction::Transactional::rollback($this, __journal);
+            }
+            match __result {
+                ::core::result::Result::Ok(__value) => __value,
+                ::core::result::Result::Err(__panic) => ::std::panic::resume_unwind(__panic),
+            }
+        }
+    }}
+}
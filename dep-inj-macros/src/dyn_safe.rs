@@ -0,0 +1,496 @@
+use crate::mentions;
+use proc_macro2::{Span, TokenStream};
+use quote::{format_ident, quote, ToTokens};
+use syn::{parse_quote, spanned::Spanned};
+
+pub(crate) fn dyn_safe_impl(
+    dyn_ident: syn::Ident,
+    item_trait: syn::ItemTrait,
+) -> syn::Result<TokenStream> {
+    if let Some(item) = item_trait
+        .items
+        .iter()
+        .find(|item| !matches!(item, syn::TraitItem::Method(_)))
+    {
+        return Err(syn::Error::new(
+            item.span(),
+            "`dyn_safe` only supports traits with methods",
+        ));
+    }
+
+    let methods = item_trait
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            syn::TraitItem::Method(method) => Some(DynMethod::new(method)),
+            _ => None,
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    let dyn_trait = dyn_trait(&dyn_ident, &item_trait, &methods);
+    let to_dyn = impl_to_dyn(&dyn_ident, &item_trait, &methods);
+    let from_dyn = [
+        quote!(),
+        quote!(+ ::core::marker::Send),
+        quote!(+ ::core::marker::Send + ::core::marker::Sync),
+    ]
+    .iter()
+    .map(|auto_traits| impl_from_dyn(&dyn_ident, &item_trait, &methods, auto_traits))
+    .collect::<Vec<_>>();
+
+    Ok(quote! {
+        #item_trait
+        #dyn_trait
+        #to_dyn
+        #(#from_dyn)*
+    })
+}
+
+/// A method of the original trait, and how it maps to the dyn safe companion.
+struct DynMethod {
+    method: syn::TraitItemMethod,
+    /// only callable on sized types, which is skipped by the companion trait.
+    sized_only: bool,
+    /// the erased generics of the arguments, `None` for the arguments kept as is.
+    args: Vec<Option<ErasedFn>>,
+}
+
+/// A `F: FnOnce(usize) + Send` generic argument, erased into `Box<dyn FnOnce(usize) + Send + '_>`.
+#[derive(Clone)]
+struct ErasedFn {
+    kind: FnKind,
+    inputs: Vec<syn::Type>,
+    output: syn::ReturnType,
+    auto_traits: Vec<syn::Path>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum FnKind {
+    Fn,
+    FnMut,
+    FnOnce,
+}
+
+impl DynMethod {
+    fn new(method: &syn::TraitItemMethod) -> syn::Result<Self> {
+        let sig = &method.sig;
+        let sized_only = requires_sized(sig);
+        if sized_only {
+            return Ok(Self {
+                method: method.clone(),
+                sized_only,
+                args: vec![],
+            });
+        }
+
+        if sig.receiver().is_none() {
+            return Err(syn::Error::new(
+                sig.span(),
+                "methods without `self` cannot be dyn safe, consider adding `where Self: Sized`",
+            ));
+        }
+        if let Some(asyncness) = &sig.asyncness {
+            return Err(syn::Error::new(
+                asyncness.span(),
+                "async methods cannot be dyn safe, consider adding `where Self: Sized`",
+            ));
+        }
+
+        // the type parameters of the method and their bounds
+        let mut generics = vec![];
+        for param in &sig.generics.params {
+            match param {
+                syn::GenericParam::Type(ty) => {
+                    let mut bounds = ty.bounds.iter().cloned().collect::<Vec<_>>();
+                    for predicate in sig.generics.where_clause.iter().flat_map(|w| &w.predicates) {
+                        if let syn::WherePredicate::Type(predicate) = predicate {
+                            if is_ident(&predicate.bounded_ty, &ty.ident) {
+                                bounds.extend(predicate.bounds.iter().cloned());
+                            }
+                        }
+                    }
+                    generics.push((ty.ident.clone(), ErasedFn::new(&ty.ident, &bounds)?));
+                }
+                syn::GenericParam::Lifetime(_) => {}
+                syn::GenericParam::Const(r#const) => {
+                    return Err(syn::Error::new(
+                        r#const.span(),
+                        "const generics cannot be dyn safe, consider adding `where Self: Sized`",
+                    ))
+                }
+            }
+        }
+
+        let mut args = vec![];
+        for input in sig.inputs.iter().skip(1) {
+            let syn::FnArg::Typed(input) = input else {
+                unreachable!("the receiver is the first argument")
+            };
+
+            match &*input.ty {
+                syn::Type::ImplTrait(impl_trait) => {
+                    let bounds = impl_trait.bounds.iter().cloned().collect::<Vec<_>>();
+                    args.push(Some(ErasedFn::new(&impl_trait, &bounds)?));
+                }
+                ty => match generics.iter().position(|(ident, _)| is_ident(ty, ident)) {
+                    Some(i) => args.push(Some(generics[i].1.clone())),
+                    None => {
+                        check_no_generics(ty, &generics)?;
+                        args.push(None);
+                    }
+                },
+            }
+        }
+
+        if let syn::ReturnType::Type(_, ty) = &sig.output {
+            check_no_generics(ty, &generics)?;
+        }
+
+        Ok(Self {
+            method: method.clone(),
+            sized_only,
+            args,
+        })
+    }
+
+    /// `fn emit_count(&self, f: &mut dyn FnMut(usize))`
+    fn dyn_sig(&self) -> syn::Signature {
+        let mut sig = self.method.sig.clone();
+        sig.generics.params = sig
+            .generics
+            .params
+            .into_iter()
+            .filter(|param| matches!(param, syn::GenericParam::Lifetime(_)))
+            .collect();
+        if let Some(where_clause) = &mut sig.generics.where_clause {
+            where_clause.predicates = where_clause
+                .predicates
+                .iter()
+                .filter(|predicate| matches!(predicate, syn::WherePredicate::Lifetime(_)))
+                .cloned()
+                .collect();
+        }
+
+        for (i, (input, erased)) in sig.inputs.iter_mut().skip(1).zip(&self.args).enumerate() {
+            let syn::FnArg::Typed(input) = input else {
+                unreachable!("the receiver is the first argument")
+            };
+            *input.pat = match &*input.pat {
+                syn::Pat::Ident(pat) => {
+                    let ident = &pat.ident;
+                    parse_quote!(#ident)
+                }
+                _ => {
+                    let ident = arg_ident(i);
+                    parse_quote!(#ident)
+                }
+            };
+            if let Some(erased) = erased {
+                *input.ty = erased.dyn_type();
+            }
+        }
+
+        sig
+    }
+
+    /// `fn emit_count(&self, __arg0: ..)`
+    fn sig_with_arg_idents(sig: &syn::Signature, mutable: bool) -> syn::Signature {
+        let mut sig = sig.clone();
+        for (i, input) in sig.inputs.iter_mut().skip(1).enumerate() {
+            if let syn::FnArg::Typed(input) = input {
+                let ident = arg_ident(i);
+                *input.pat = if mutable {
+                    parse_quote!(mut #ident)
+                } else {
+                    parse_quote!(#ident)
+                };
+            }
+        }
+        sig
+    }
+
+    fn doc_attrs(&self) -> Vec<syn::Attribute> {
+        self.method
+            .attrs
+            .iter()
+            .filter(|attr| attr.path.is_ident("doc"))
+            .cloned()
+            .collect()
+    }
+}
+
+impl ErasedFn {
+    fn new(generic: &dyn ToTokens, bounds: &[syn::TypeParamBound]) -> syn::Result<Self> {
+        let mut erased = None;
+        let mut auto_traits = vec![];
+        for bound in bounds {
+            let syn::TypeParamBound::Trait(bound) = bound else {
+                return Err(syn::Error::new(
+                    bound.span(),
+                    "lifetime bounds cannot be erased by `dyn_safe`",
+                ));
+            };
+            let last = bound.path.segments.last().unwrap();
+            let kind = match &*last.ident.to_string() {
+                "Fn" => FnKind::Fn,
+                "FnMut" => FnKind::FnMut,
+                "FnOnce" => FnKind::FnOnce,
+                "Send" | "Sync" | "Unpin" => {
+                    auto_traits.push(bound.path.clone());
+                    continue;
+                }
+                _ => {
+                    return Err(syn::Error::new(
+                        bound.span(),
+                        "only `Fn`, `FnMut` and `FnOnce` generics can be erased by `dyn_safe`",
+                    ))
+                }
+            };
+            let syn::PathArguments::Parenthesized(args) = &last.arguments else {
+                return Err(syn::Error::new(last.span(), "expect `Fn(..) -> ..`"));
+            };
+            if erased.is_some() {
+                return Err(syn::Error::new(
+                    bound.span(),
+                    "expect only one of `Fn`, `FnMut` and `FnOnce` bounds",
+                ));
+            }
+            erased = Some((
+                kind,
+                args.inputs.iter().cloned().collect(),
+                args.output.clone(),
+            ));
+        }
+
+        match erased {
+            Some((kind, inputs, output)) => Ok(Self {
+                kind,
+                inputs,
+                output,
+                auto_traits,
+            }),
+            None => Err(syn::Error::new(
+                generic.span(),
+                "generics without `Fn`, `FnMut` or `FnOnce` bounds cannot be erased by `dyn_safe`",
+            )),
+        }
+    }
+
+    /// `&mut (dyn FnMut(usize) + Send)`, or `Box<dyn FnOnce(usize) + Send + '_>`
+    fn dyn_type(&self) -> syn::Type {
+        let inputs = &self.inputs;
+        let output = &self.output;
+        let auto_traits = &self.auto_traits;
+        match self.kind {
+            FnKind::Fn => {
+                parse_quote!(&(dyn ::core::ops::Fn(#(#inputs),*) #output #(+ #auto_traits)*))
+            }
+            FnKind::FnMut => {
+                parse_quote!(&mut (dyn ::core::ops::FnMut(#(#inputs),*) #output #(+ #auto_traits)*))
+            }
+            FnKind::FnOnce => parse_quote! {
+                ::std::boxed::Box<dyn ::core::ops::FnOnce(#(#inputs),*) #output #(+ #auto_traits)* + '_>
+            },
+        }
+    }
+
+    /// converts the generic argument to the erased one.
+    fn erase(&self, arg: &syn::Ident) -> TokenStream {
+        match self.kind {
+            FnKind::Fn => quote!(&#arg),
+            FnKind::FnMut => quote!(&mut #arg),
+            FnKind::FnOnce => quote!(::std::boxed::Box::new(#arg)),
+        }
+    }
+}
+
+/// `pub trait DynIsEven { .. }`
+fn dyn_trait(
+    dyn_ident: &syn::Ident,
+    item_trait: &syn::ItemTrait,
+    methods: &[DynMethod],
+) -> syn::ItemTrait {
+    let trait_ident = &item_trait.ident;
+    let doc =
+        format!(" The dyn safe companion of [`{trait_ident}`], generated by `dep_inj::dyn_safe`.");
+
+    let items = methods
+        .iter()
+        .filter(|method| !method.sized_only)
+        .map(|method| {
+            let attrs = method.doc_attrs();
+            let sig = method.dyn_sig();
+            parse_quote! {
+                #(#attrs)*
+                #sig;
+            }
+        })
+        .collect();
+
+    syn::ItemTrait {
+        attrs: vec![parse_quote!(#[doc = #doc])],
+        vis: item_trait.vis.clone(),
+        unsafety: item_trait.unsafety,
+        auto_token: None,
+        trait_token: Default::default(),
+        ident: dyn_ident.clone(),
+        generics: item_trait.generics.clone(),
+        colon_token: item_trait.colon_token,
+        supertraits: item_trait.supertraits.clone(),
+        brace_token: Default::default(),
+        items,
+    }
+}
+
+/// `impl<T: IsEven + ?Sized> DynIsEven for T`
+fn impl_to_dyn(
+    dyn_ident: &syn::Ident,
+    item_trait: &syn::ItemTrait,
+    methods: &[DynMethod],
+) -> syn::ItemImpl {
+    let trait_ident = &item_trait.ident;
+    let (_, ty_generics, _) = item_trait.generics.split_for_impl();
+
+    let mut generics = item_trait.generics.clone();
+    generics
+        .params
+        .push(parse_quote!(__T__: #trait_ident #ty_generics + ?Sized));
+
+    let items = methods
+        .iter()
+        .filter(|method| !method.sized_only)
+        .map(|method| {
+            let sig = DynMethod::sig_with_arg_idents(&method.dyn_sig(), false);
+            let ident = &sig.ident;
+            let args = (0..method.args.len()).map(arg_ident);
+            parse_quote! {
+                #[inline]
+                #sig {
+                    <__T__ as #trait_ident #ty_generics>::#ident(self #(, #args)*)
+                }
+            }
+        })
+        .collect();
+
+    syn::ItemImpl {
+        attrs: vec![],
+        defaultness: None,
+        unsafety: item_trait.unsafety,
+        impl_token: Default::default(),
+        generics,
+        trait_: Some((
+            None,
+            parse_quote!(#dyn_ident #ty_generics),
+            Default::default(),
+        )),
+        self_ty: Box::new(parse_quote!(__T__)),
+        brace_token: Default::default(),
+        items,
+    }
+}
+
+/// `impl IsEven for dyn DynIsEven + '__dyn`
+fn impl_from_dyn(
+    dyn_ident: &syn::Ident,
+    item_trait: &syn::ItemTrait,
+    methods: &[DynMethod],
+    auto_traits: &TokenStream,
+) -> syn::ItemImpl {
+    let trait_ident = &item_trait.ident;
+    let (_, ty_generics, _) = item_trait.generics.split_for_impl();
+
+    let mut generics = item_trait.generics.clone();
+    generics.params.insert(0, parse_quote!('__dyn));
+    let self_ty: syn::Type = parse_quote!(dyn #dyn_ident #ty_generics #auto_traits + '__dyn);
+
+    let items = methods
+        .iter()
+        .filter(|method| !(method.sized_only && method.method.default.is_some()))
+        .map(|method| {
+            let sig = DynMethod::sig_with_arg_idents(&method.method.sig, true);
+            if method.sized_only {
+                return parse_quote! {
+                    #[allow(dead_code)]
+                    #sig {
+                        ::core::unreachable!()
+                    }
+                };
+            }
+
+            let ident = &sig.ident;
+            let args = method.args.iter().enumerate().map(|(i, erased)| {
+                let arg = arg_ident(i);
+                match erased {
+                    Some(erased) => erased.erase(&arg),
+                    None => arg.into_token_stream(),
+                }
+            });
+            parse_quote! {
+                #[inline]
+                #[allow(unused_mut)]
+                #sig {
+                    <Self as #dyn_ident #ty_generics>::#ident(self #(, #args)*)
+                }
+            }
+        })
+        .collect();
+
+    syn::ItemImpl {
+        attrs: vec![],
+        defaultness: None,
+        unsafety: item_trait.unsafety,
+        impl_token: Default::default(),
+        generics,
+        trait_: Some((
+            None,
+            parse_quote!(#trait_ident #ty_generics),
+            Default::default(),
+        )),
+        self_ty: Box::new(self_ty),
+        brace_token: Default::default(),
+        items,
+    }
+}
+
+fn arg_ident(i: usize) -> syn::Ident {
+    format_ident!("__arg{}", i)
+}
+
+/// `where Self: Sized`
+fn requires_sized(sig: &syn::Signature) -> bool {
+    let self_ident = syn::Ident::new("Self", Span::call_site());
+    sig.generics
+        .where_clause
+        .iter()
+        .flat_map(|where_clause| &where_clause.predicates)
+        .any(|predicate| match predicate {
+            syn::WherePredicate::Type(predicate) => {
+                is_ident(&predicate.bounded_ty, &self_ident)
+                    && predicate.bounds.iter().any(|bound| {
+                        matches!(bound, syn::TypeParamBound::Trait(bound)
+                            if bound.modifier == syn::TraitBoundModifier::None
+                                && bound.path.segments.last().unwrap().ident == "Sized")
+                    })
+            }
+            _ => false,
+        })
+}
+
+fn is_ident(ty: &syn::Type, ident: &syn::Ident) -> bool {
+    matches!(ty, syn::Type::Path(ty) if ty.qself.is_none() && ty.path.is_ident(ident))
+}
+
+fn check_no_generics(ty: &syn::Type, generics: &[(syn::Ident, ErasedFn)]) -> syn::Result<()> {
+    match generics
+        .iter()
+        .find(|(ident, _)| mentions(ty.to_token_stream(), ident))
+    {
+        Some((ident, _)) => Err(syn::Error::new(
+            ty.span(),
+            format!(
+                "`{ident}` can only be used as the type of an argument to be erased by `dyn_safe`"
+            ),
+        )),
+        None => Ok(()),
+    }
+}
@@ -0,0 +1,142 @@
+use crate::{exported_macro_ident, receiver_kind};
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{parse_quote, spanned::Spanned};
+
+pub(crate) fn interface_impl(mut item_trait: syn::ItemTrait) -> syn::Result<TokenStream> {
+    if !item_trait.generics.params.is_empty() {
+        return Err(syn::Error::new(
+            item_trait.generics.span(),
+            "generic interfaces are not supported",
+        ));
+    }
+
+    let mut methods = vec![];
+    let mut kinds = vec![];
+    for item in &item_trait.items {
+        match item {
+            syn::TraitItem::Method(method) => {
+                if let Some((kind, method)) = forward_method(method)? {
+                    kinds.push(format_ident!("{}", kind));
+                    methods.push(method);
+                }
+            }
+            item => {
+                return Err(syn::Error::new(
+                    item.span(),
+                    "only the methods of an interface can be forwarded",
+                ))
+            }
+        }
+    }
+
+    // a missing interface is reported as such, rather than as an unsatisfied bound
+    let message = format!(
+        "`{{Self}}` does not provide the interface `{}`",
+        item_trait.ident
+    );
+    let note = format!(
+        "a container provides it by `#[component(proxy = .., provide({}))]`",
+        item_trait.ident
+    );
+    item_trait.attrs.push(parse_quote! {
+        #[diagnostic::on_unimplemented(message = #message, note = #note)]
+    });
+
+    let vis = &item_trait.vis;
+    let ident = &item_trait.ident;
+    let macro_ident = exported_macro_ident("interface", ident, &item_trait);
+    Ok(quote! {
+        #item_trait
+
+        #[doc(hidden)]
+        #[macro_export]
+        macro_rules! #macro_ident {
+            (
+                impl [$($generics:tt)*] [$($trait_:tt)*] for [$($self_ty:tt)*] [$($where_:tt)*]
+                => $dispatch:ident
+            ) => {
+                impl $($generics)* $($trait_)* for $($self_ty)* $($where_)* {
+                    #(#methods)*
+                }
+            };
+            // the receiver kinds of the methods, for a callback picking the interfaces it can
+            // implement
+            (receivers => $callback:ident) => {
+                $callback! { #(#kinds)* }
+            };
+        }
+
+        // used by the containers forwarding the interface, if any
+        #[doc(hidden)]
+        #[allow(unused_imports)]
+        #vis use #macro_ident as #ident;
+    })
+}
+
+/// `fn is_even(self: Arc<Self>, n: u64) -> bool;` ->
+/// `fn is_even(self: Arc<Self>, __arg0: u64) -> bool { $dispatch! { arc self is_even(__arg0) } }`
+///
+/// The methods without a receiver are left to their default bodies.
+fn forward_method(
+    method: &syn::TraitItemMethod,
+) -> syn::Result<Option<(&'static str, TokenStream)>> {
+    let mut sig = method.sig.clone();
+    let Some(kind) = sig.inputs.first().and_then(receiver_kind) else {
+        return match method.default {
+            Some(_) => Ok(None),
+            None => Err(syn::Error::new(
+                sig.span(),
+                "methods without a receiver cannot be forwarded, try giving it a default body",
+            )),
+        };
+    };
+    if sig.asyncness.is_some() {
+        return Err(syn::Error::new(
+            sig.asyncness.span(),
+            "async methods of an interface cannot be forwarded",
+        ));
+    }
+
+    let mut args = vec![];
+    for (i, input) in sig.inputs.iter_mut().enumerate() {
+        match input {
+            // `mut self` would be unused
+            syn::FnArg::Receiver(receiver) if receiver.reference.is_none() => {
+                receiver.mutability = None;
+            }
+            syn::FnArg::Receiver(_) => {}
+            syn::FnArg::Typed(input) if i == 0 => {
+                if let syn::Pat::Ident(pat) = &mut *input.pat {
+                    pat.mutability = None;
+                }
+            }
+            syn::FnArg::Typed(input) => {
+                let arg = format_ident!("__arg{}", i - 1);
+                *input.pat = parse_quote!(#arg);
+                args.push(arg);
+            }
+        }
+    }
+
+    let kind_ident = format_ident!("{}", kind);
+    let method_ident = &sig.ident;
+    let mut body = quote! {
+        $dispatch! { #kind_ident self #method_ident(#(#args),*) }
+    };
+    if sig.unsafety.is_some() {
+        body = quote! { unsafe { #body } };
+    }
+    let cfgs = method.attrs.iter().filter(|attr| attr.path.is_ident("cfg"));
+
+    Ok(Some((
+        kind,
+        quote! {
+            #(#cfgs)*
+            #[inline]
+            #sig {
+                #body
+            }
+        },
+    )))
+}
@@ -0,0 +1,2554 @@
+//! Procedural macros of `dep-inj`, use them through the `dep_inj` crate.
+
+use proc_macro2::{Span, TokenStream};
+use quote::{format_ident, quote, ToTokens};
+use std::{
+    collections::hash_map::DefaultHasher,
+    default::Default,
+    hash::{Hash, Hasher},
+};
+use syn::{parse_macro_input, parse_quote, spanned::Spanned};
+
+mod config;
+mod container;
+mod dyn_safe;
+mod interface;
+mod memoize;
+mod outline;
+mod remote;
+mod replayable;
+mod snapshot;
+
+///
+///
+/// ```
+/// # use dep_inj::DepInj;
+///
+/// #[derive(DepInj)]
+/// #[target(Foo)]
+/// struct FooState<T> {
+///     inner: T,
+/// }
+/// ```
+///
+/// will expand to
+///
+/// ```
+/// # struct FooState<T> {
+/// #    inner: T,
+/// # }
+///
+/// #[repr(transparent)]
+/// struct Foo<T, Deps: ?Sized> {
+///     _marker: core::marker::PhantomData<FooState<T>>,
+///     deps: Deps
+/// }
+///
+/// impl<T, Deps: AsRef<FooState<T>> + ?Sized> std::ops::Deref for Foo<T, Deps> {
+///     type Target = FooState<T>;
+///     #[inline]
+///     fn deref(&self) -> &Self::Target {
+///         self.deps.as_ref()
+///     }
+/// }
+///
+/// impl<T, Deps: AsMut<FooState<T>> + AsRef<FooState<T>> + ?Sized> std::ops::DerefMut for Foo<T, Deps> {
+///     #[inline]
+///     fn deref_mut(&mut self) -> &mut Self::Target {
+///         self.deps.as_mut()
+///     }
+/// }
+///
+/// impl<T, Deps: ?Sized> dep_inj::Proxy for Foo<T, Deps> {
+///     type Container = Deps;
+///     #[inline]
+///     fn container(&self) -> &Self::Container {
+///         &self.deps
+///     }
+///     #[inline]
+///     fn container_mut(&mut self) -> &mut Self::Container {
+///         &mut self.deps
+///     }
+/// }
+///
+/// impl<T, Deps: Into<FooState<T>>> From<Foo<T, Deps>> for FooState<T> {
+///     #[inline]
+///     fn from(value: Foo<T, Deps>) -> Self {
+///         value.prj().into()
+///     }
+/// }
+///
+/// impl<T, Deps: ?Sized> Foo<T, Deps> {
+///     #[inline]
+///     pub fn inj_ref(deps: &Deps) -> &Self {
+///         unsafe { &*(deps as *const Deps as *const Self) }
+///     }
+///     #[inline]
+///     pub fn prj_ref(&self) -> &Deps {
+///         unsafe { &*(self as *const Self as *const Deps) }
+///     }
+///     #[inline]
+///     pub fn inj_ref_mut(deps: &mut Deps) -> &mut Self {
+///         unsafe { &mut*(deps as *mut Deps as *mut Self) }
+///     }
+///     #[inline]
+///     pub fn prj_ref_mut(&mut self) -> &mut Deps {
+///         unsafe { &mut*(self as *mut Self as *mut Deps) }
+///     }
+///     #[inline]
+///     pub fn inj_box(deps: Box<Deps>) -> Box<Self> {
+///         unsafe { Box::from_raw(Box::into_raw(deps) as *mut Self) }
+///     }
+///     #[inline]
+///     pub fn prj_box(self: Box<Self>) -> Box<Deps> {
+///         unsafe { Box::from_raw(Box::into_raw(self) as *mut Deps) }
+///     }
+///     #[inline]
+///     pub fn inj_rc(deps: std::rc::Rc<Deps>) -> std::rc::Rc<Self> {
+///         unsafe { std::rc::Rc::from_raw(std::rc::Rc::into_raw(deps) as *const Self)}
+///     }
+///     #[inline]
+///     pub fn prj_rc(self: std::rc::Rc<Self>) -> std::rc::Rc<Deps> {
+///         unsafe { std::rc::Rc::from_raw(std::rc::Rc::into_raw(self) as *const Deps) }
+///     }
+///     #[inline]
+///     pub fn inj_arc(deps: std::sync::Arc<Deps>) -> std::sync::Arc<Self> {
+///         unsafe { std::sync::Arc::from_raw(std::sync::Arc::into_raw(deps) as *const Self)}
+///     }
+///     #[inline]
+///     pub fn prj_arc(self: std::sync::Arc<Self>) -> std::sync::Arc<Deps> {
+///         unsafe { std::sync::Arc::from_raw(std::sync::Arc::into_raw(self) as *const Deps) }
+///     }
+///
+///     // and `Pin<P>`s...
+/// }
+///
+/// impl<T, Deps> Foo<T, Deps> {
+///     #[inline]
+///     pub fn inj(deps: Deps) -> Self {
+///         Self {
+///             _marker: core::marker::PhantomData,
+///             deps
+///         }
+///     }
+///
+///     #[inline]
+///     pub fn prj(self) -> Deps {
+///         self.deps
+///     }
+/// }
+/// ```
+///
+/// With `#[target(Foo, layer)]`, `Foo` is a layer wrapping another proxy `Deps`, which `Deref`s
+/// to the state in `<Deps as dep_inj::Proxy>::Container`, see [`Container`](derive.Container.html).
+///
+/// With `#[target(Foo, provide(IsFoo, ..))]`, `Foo<Deps>` implements `dep_inj::wiring::Wired` when
+/// it implements the declared interfaces, so that `dep_inj::assert_wired!(GlobalStruct: Foo)`
+/// reports the deps of `Foo` missing in the container. `#[target(Foo, depend(IsBar, ..))]` declares
+/// the interfaces `Foo` depends on, implementing `dep_inj::graph::Dependencies` for the state, and
+/// `dep_inj::wiring::Satisfied` for `Foo<Deps>` when `Deps` implements all of them.
+/// `Foo` implements `dep_inj::metadata::Describe`, naming the state, itself and the interfaces.
+///
+/// With `#[target(Foo, tag = Replica)]`, `Foo<Deps>` reaches the state by
+/// `Deps: dep_inj::tagged::Has<Replica, FooState<T>>` instead of `AsRef`, so that a container can
+/// hold several instances of the state, see `dep_inj::tagged`. A state can have several
+/// `#[target(..)]`s, all but one tagged.
+///
+/// With `#[target(Foo, access = Provide)]`, `Foo<Deps>` reaches the state by
+/// `Deps: dep_inj::provide::Provide<FooState<T>>` instead of `AsRef`, which a container implements
+/// besides `AsRef` for its components, see `dep_inj::provide`.
+///
+/// A `#[config]` field of the state implements `dep_inj::config::Configured` for the state and
+/// `fn config(&self)`, so that the proxy reaches it by `self.config()`, see `dep_inj::config`.
+///
+/// The `#[pin]` fields of the state can be projected from `Pin<&mut Foo<T, Deps>>` by `project()`,
+/// which returns a `FooProjection` of `Pin<&mut F>`s for the `#[pin]` fields and `&mut F`s for the
+/// others. It requires the state to be structurally pinned in the deps, that is
+/// `Deps: dep_inj::pin::StructuralPin<FooState<T>>`, derived by `#[component(pin)]`.
+/// Neither the state nor the container can implement `Drop` or `Unpin` by hand then.
+///
+/// ```
+/// # use dep_inj::{Container, DepInj};
+/// # use std::marker::PhantomPinned;
+/// # use std::pin::Pin;
+/// #[derive(Default, DepInj)]
+/// #[target(TaskProxy)]
+/// pub struct TaskState {
+///     #[pin]
+///     pinned: PhantomPinned,
+///     polls: usize,
+/// }
+///
+/// #[derive(Default, Container)]
+/// pub struct GlobalStruct {
+///     #[component(pin)]
+///     task_state: TaskState,
+/// }
+///
+/// let mut global = Box::pin(GlobalStruct::default());
+/// let projection = TaskProxy::inj_pin_ref_mut(global.as_mut()).project();
+/// let _: Pin<&mut PhantomPinned> = projection.pinned;
+/// *projection.polls += 1;
+/// assert_eq!(global.task_state.polls, 1);
+/// ```
+#[proc_macro_derive(DepInj, attributes(target, inject, pin, config))]
+pub fn derive_dep_inj(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let derive_input = parse_macro_input!(input as syn::DeriveInput);
+
+    match derive_dep_inj_impl(derive_input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.into_compile_error().into(),
+    }
+}
+
+/// Derive a dyn safe companion trait from an interface which is not dyn safe,
+/// so that `Proxy<dyn Deps>` can still reach every method of the interface.
+///
+/// The generic arguments bounded by `Fn`, `FnMut` or `FnOnce` are erased into `&dyn Fn`,
+/// `&mut dyn FnMut` or `Box<dyn FnOnce>`, and the methods with `where Self: Sized` are skipped.
+///
+/// ```
+/// # use std::sync::Arc;
+/// #[dep_inj::dyn_safe(DynIsEven)]
+/// pub trait IsEven {
+///     fn is_even(self: Arc<Self>, n: u64) -> bool;
+///
+///     fn emit_count<F>(&self, f: F)
+///     where
+///         F: FnOnce(usize);
+/// }
+/// ```
+///
+/// will expand to
+///
+/// ```
+/// # use std::sync::Arc;
+/// # pub trait IsEven {
+/// #     fn is_even(self: Arc<Self>, n: u64) -> bool;
+/// #     fn emit_count<F>(&self, f: F) where F: FnOnce(usize);
+/// # }
+/// pub trait DynIsEven {
+///     fn is_even(self: Arc<Self>, n: u64) -> bool;
+///
+///     fn emit_count(&self, f: Box<dyn FnOnce(usize) + '_>);
+/// }
+///
+/// impl<T: IsEven + ?Sized> DynIsEven for T {
+///     #[inline]
+///     fn is_even(self: Arc<Self>, n: u64) -> bool {
+///         <T as IsEven>::is_even(self, n)
+///     }
+///
+///     #[inline]
+///     fn emit_count(&self, f: Box<dyn FnOnce(usize) + '_>) {
+///         <T as IsEven>::emit_count(self, f)
+///     }
+/// }
+///
+/// impl<'a> IsEven for dyn DynIsEven + 'a {
+///     #[inline]
+///     fn is_even(self: Arc<Self>, n: u64) -> bool {
+///         <Self as DynIsEven>::is_even(self, n)
+///     }
+///
+///     #[inline]
+///     fn emit_count<F>(&self, f: F)
+///     where
+///         F: FnOnce(usize),
+///     {
+///         <Self as DynIsEven>::emit_count(self, Box::new(f))
+///     }
+/// }
+///
+/// // and `impl IsEven` for `dyn DynIsEven + Send` and `dyn DynIsEven + Send + Sync`...
+/// ```
+#[proc_macro_attribute]
+pub fn dyn_safe(
+    attr: proc_macro::TokenStream,
+    item: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+    let dyn_ident = parse_macro_input!(attr as syn::Ident);
+    let item_trait = parse_macro_input!(item as syn::ItemTrait);
+
+    match dyn_safe::dyn_safe_impl(dyn_ident, item_trait) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.into_compile_error().into(),
+    }
+}
+
+/// Outline the bodies of a generic impl onto `Proxy<dyn Deps>`, so that the bodies are compiled
+/// once instead of being monomorphised for every container.
+///
+/// The `dyn Deps` can be inferred from the bound of the deps parameter, or specified like
+/// `#[outline(dyn EvenDeps)]`. The methods which cannot be called through `Proxy<dyn Deps>`
+/// (having type parameters, taking `self` by value, mentioning `Self` in the signature, ..)
+/// or marked by `#[outline(skip)]` are kept generic.
+///
+/// ```
+/// # use dep_inj::DepInj;
+/// # use std::sync::{Arc, Mutex};
+/// # pub trait IsOdd { fn is_odd(self: Arc<Self>, n: u64) -> bool; }
+/// # pub trait IsEven {
+/// #     fn is_even(self: Arc<Self>, n: u64) -> bool;
+/// #     fn emit_count<F: FnOnce(usize)>(&self, f: F);
+/// # }
+/// # #[derive(Default, DepInj)]
+/// # #[target(EvenProxy)]
+/// # pub struct EvenState { count: Mutex<usize> }
+/// pub trait EvenDeps: AsRef<EvenState> + IsOdd + Send + Sync + 'static {}
+/// # impl<T: AsRef<EvenState> + IsOdd + Send + Sync + 'static> EvenDeps for T {}
+///
+/// #[dep_inj::outline]
+/// impl<Ctx: EvenDeps> IsEven for EvenProxy<Ctx> {
+///     fn is_even(self: Arc<Self>, n: u64) -> bool {
+///         *self.count.lock().unwrap() += 1;
+///         (n == 0) || self.prj_arc().is_odd(n - 1)
+///     }
+///
+///     fn emit_count<F: FnOnce(usize)>(&self, f: F) {
+///         f(*self.count.lock().unwrap())
+///     }
+/// }
+/// ```
+///
+/// will expand to
+///
+/// ```
+/// # use dep_inj::DepInj;
+/// # use std::sync::{Arc, Mutex};
+/// # pub trait IsOdd { fn is_odd(self: Arc<Self>, n: u64) -> bool; }
+/// # pub trait IsEven {
+/// #     fn is_even(self: Arc<Self>, n: u64) -> bool;
+/// #     fn emit_count<F: FnOnce(usize)>(&self, f: F);
+/// # }
+/// # #[derive(Default, DepInj)]
+/// # #[target(EvenProxy)]
+/// # pub struct EvenState { count: Mutex<usize> }
+/// # pub trait EvenDeps: AsRef<EvenState> + IsOdd + Send + Sync + 'static {}
+/// # impl<T: AsRef<EvenState> + IsOdd + Send + Sync + 'static> EvenDeps for T {}
+/// impl<Ctx: EvenDeps> IsEven for EvenProxy<Ctx> {
+///     #[inline]
+///     fn is_even(self: Arc<Self>, n: u64) -> bool {
+///         <EvenProxy<dyn EvenDeps + '_>>::__dep_inj_outlined_is_even_is_even(
+///             <EvenProxy<dyn EvenDeps + '_>>::inj_arc(self.prj_arc()),
+///             n,
+///         )
+///     }
+///
+///     // not dyn safe, kept generic
+///     fn emit_count<F: FnOnce(usize)>(&self, f: F) {
+///         f(*self.count.lock().unwrap())
+///     }
+/// }
+///
+/// impl<'a> EvenProxy<dyn EvenDeps + 'a> {
+///     #[doc(hidden)]
+///     fn __dep_inj_outlined_is_even_is_even(self: Arc<Self>, n: u64) -> bool {
+///         *self.count.lock().unwrap() += 1;
+///         (n == 0) || self.prj_arc().is_odd(n - 1)
+///     }
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn outline(
+    attr: proc_macro::TokenStream,
+    item: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+    let dyn_deps = if attr.is_empty() {
+        None
+    } else {
+        Some(parse_macro_input!(attr as syn::Type))
+    };
+    let item_impl = parse_macro_input!(item as syn::ItemImpl);
+
+    match outline::outline_impl(dyn_deps, item_impl) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.into_compile_error().into(),
+    }
+}
+
+/// Memoize a method of a proxy in a cache field of its state, like `#[memoize(cache)]`.
+///
+/// The cache is a `dep_inj::memoize::SyncCache<Key, Value>`, or a `LocalCache` for the
+/// single-threaded proxies, where `Key` is the argument of the method, or the tuple of its
+/// arguments, and `Value` is the return type. Both the key and the value should be `Clone`.
+/// The methods taking `&self`, `&mut self`, `Rc<Self>` or `Arc<Self>` can be memoized.
+///
+/// ```
+/// # use dep_inj::DepInj;
+/// # use dep_inj::memoize::SyncCache;
+/// # use std::sync::atomic::{AtomicUsize, Ordering};
+/// # use std::sync::Arc;
+/// # pub trait IsOdd { fn is_odd(self: Arc<Self>, n: u64) -> bool; }
+/// # pub trait IsEven { fn is_even(self: Arc<Self>, n: u64) -> bool; }
+/// #[derive(Default, DepInj)]
+/// #[target(EvenProxy)]
+/// pub struct EvenState {
+///     cache: SyncCache<u64, bool>,
+///     calls: AtomicUsize,
+/// }
+///
+/// impl<Deps: AsRef<EvenState> + IsOdd> IsEven for EvenProxy<Deps> {
+///     #[dep_inj::memoize(cache)]
+///     fn is_even(self: Arc<Self>, n: u64) -> bool {
+///         self.calls.fetch_add(1, Ordering::Relaxed);
+///         n == 0 || self.prj_arc().is_odd(n - 1)
+///     }
+/// }
+/// # #[derive(Default)]
+/// # struct Global { even_state: EvenState }
+/// # impl AsRef<EvenState> for Global { fn as_ref(&self) -> &EvenState { &self.even_state } }
+/// # impl IsOdd for Global {
+/// #     fn is_odd(self: Arc<Self>, n: u64) -> bool { n != 0 && self.is_even(n - 1) }
+/// # }
+/// # impl IsEven for Global {
+/// #     fn is_even(self: Arc<Self>, n: u64) -> bool { EvenProxy::inj_arc(self).is_even(n) }
+/// # }
+///
+/// let global = Arc::new(Global::default());
+/// assert!(global.clone().is_even(10));
+/// assert!(global.clone().is_even(10));
+/// // `is_even(10)`, `is_even(8)`, .., `is_even(0)`, only computed once
+/// assert_eq!(global.even_state.calls.load(Ordering::Relaxed), 6);
+/// ```
+///
+/// The cache is checked before the body and filled after it, so the recursive calls in flight
+/// may compute the same value more than once.
+#[proc_macro_attribute]
+pub fn memoize(
+    attr: proc_macro::TokenStream,
+    item: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+    let cache = parse_macro_input!(attr as syn::Member);
+    let method = parse_macro_input!(item as syn::ImplItemMethod);
+
+    match memoize::memoize_impl(cache, method) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.into_compile_error().into(),
+    }
+}
+
+/// Generate a client calling an interface in another process over a Unix domain socket, and a
+/// server driving the real implementation, see `dep_inj::ipc`.
+///
+/// ```
+/// # use std::sync::Arc;
+/// #[dep_inj::remote(RemoteIsEven)]
+/// pub trait IsEven {
+///     fn is_even(self: Arc<Self>, n: u64) -> bool;
+/// }
+/// ```
+///
+/// will expand to
+///
+/// ```
+/// # use std::sync::Arc;
+/// # pub trait IsEven {
+/// #     fn is_even(self: Arc<Self>, n: u64) -> bool;
+/// # }
+/// #[derive(Debug, Clone)]
+/// pub struct RemoteIsEven {
+///     client: dep_inj::ipc::Client,
+/// }
+///
+/// impl RemoteIsEven {
+///     pub fn connect(path: impl AsRef<std::path::Path>) -> std::io::Result<Self> {
+///         // ...
+/// #       unimplemented!()
+///     }
+///
+///     pub fn serve_sequential<T: IsEven>(
+///         listener: &std::os::unix::net::UnixListener,
+///         target: T,
+///     ) -> std::io::Result<()> {
+///         // ...
+/// #       unimplemented!()
+///     }
+///
+///     // ...
+/// }
+///
+/// impl IsEven for RemoteIsEven {
+///     fn is_even(self: Arc<Self>, n: u64) -> bool {
+///         let mut args = Vec::new();
+///         dep_inj::ipc::Encode::encode(&n, &mut args);
+///         dep_inj::ipc::Client::call(&self.client, 0, &args)
+///             .unwrap_or_else(|err| panic!("remote `is_even` failed: {}", err))
+///     }
+/// }
+/// ```
+///
+/// Generic methods, `&mut` arguments and async methods cannot be called remotely, neither can
+/// the methods taking `self`, `Box<Self>`, `Rc<Self>` or `Pin<..>`.
+#[proc_macro_attribute]
+pub fn remote(
+    attr: proc_macro::TokenStream,
+    item: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+    let client = parse_macro_input!(attr as syn::Ident);
+    let item_trait = parse_macro_input!(item as syn::ItemTrait);
+
+    match remote::remote_impl(client, item_trait) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.into_compile_error().into(),
+    }
+}
+
+/// Generate the hooks recording the calls to an interface and replaying them, and a driver
+/// `ReplayIsEven` replaying the recorded calls against a component, see `dep_inj::replay`.
+///
+/// ```
+/// # use std::sync::Arc;
+/// #[dep_inj::replayable(ReplayIsEven)]
+/// pub trait IsEven {
+///     fn is_even(self: Arc<Self>, n: u64) -> bool;
+/// }
+/// ```
+///
+/// will expand to
+///
+/// ```
+/// # use dep_inj::replay::{Record, Recorded, Recorder, Replayer};
+/// # use dep_inj::Proxy;
+/// # use std::sync::Arc;
+/// # pub trait IsEven {
+/// #     fn is_even(self: Arc<Self>, n: u64) -> bool;
+/// # }
+/// pub struct ReplayIsEven;
+///
+/// // impl<T: IsEven> dep_inj::replay::Drive<T> for ReplayIsEven { .. }
+///
+/// impl<Inner: IsEven + Proxy> IsEven for Recorded<Inner>
+/// where
+///     Inner::Container: AsRef<Recorder>,
+/// {
+///     fn is_even(self: Arc<Self>, n: u64) -> bool {
+///         let call = Recorder::enter(&self, "IsEven", "is_even", vec![n.record()]);
+///         let value = <Inner as IsEven>::is_even(Self::prj_arc(self), n);
+///         call.finish(value.record());
+///         value
+///     }
+/// }
+///
+/// impl<S> IsEven for Replayer<S> {
+///     fn is_even(self: Arc<Self>, n: u64) -> bool {
+///         Replayer::serve(&self, "IsEven", "is_even", vec![n.record()])
+///     }
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn replayable(
+    attr: proc_macro::TokenStream,
+    item: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+    let driver = parse_macro_input!(attr as syn::Ident);
+    let item_trait = parse_macro_input!(item as syn::ItemTrait);
+
+    match replayable::replayable_impl(driver, item_trait) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.into_compile_error().into(),
+    }
+}
+
+/// Make an interface forwardable, so that a container deriving [`Container`] can implement it
+/// by forwarding to the proxy of the component providing it.
+///
+/// Besides the trait, a hidden macro of the same name is exported, which writes the forwarding
+/// impl when invoked by the derive. So the interface should be imported by its name, and the
+/// types in its signatures (like `Arc`) should also be in scope where the container is defined.
+///
+/// Generic interfaces, associated types and consts, and async methods are not supported.
+/// The methods without a receiver are left to their default bodies.
+///
+/// A type not implementing the interface is reported as "`GlobalStruct` does not provide the
+/// interface `IsEven`", see `dep_inj::assert_wired!`.
+///
+/// ```
+/// # use std::sync::Arc;
+/// #[dep_inj::interface]
+/// pub trait IsEven {
+///     fn is_even(self: Arc<Self>, n: u64) -> bool;
+///
+///     fn emit_count<F>(&self, f: F)
+///     where
+///         F: FnOnce(usize);
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn interface(
+    attr: proc_macro::TokenStream,
+    item: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+    if let Some(token) = proc_macro2::TokenStream::from(attr).into_iter().next() {
+        return syn::Error::new(token.span(), "`interface` takes no arguments")
+            .into_compile_error()
+            .into();
+    }
+    let item_trait = parse_macro_input!(item as syn::ItemTrait);
+
+    match interface::interface_impl(item_trait) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.into_compile_error().into(),
+    }
+}
+
+/// Wire the components of a container:
+///
+/// * `#[component]` implements `AsRef` and `AsMut` of the field for the container, and
+///   `dep_inj::provide::Provide` and `ProvideMut`.
+/// * `#[component(proxy = EvenProxy, provide(IsEven))]` also implements the interfaces marked
+///   by [`macro@interface`] for the container, by forwarding to `EvenProxy<Container>`.
+/// * `#[component(pin)]` pins the field structurally, so that the `#[pin]` fields of the state
+///   can be projected from a pinned proxy, see [`DepInj`](derive.DepInj.html).
+/// * `#[component(proxy = EvenProxy, layers(Metrics), provide(IsEven))]` forwards to
+///   `EvenProxy<Container>` wrapped in the layers, see below.
+/// * `#[component(actor = EvenActor, proxy = EvenProxy, provide(IsEven))]` on an
+///   `EvenActor<EvenState>` field generates the `EvenActor` client, forwarding the calls to a
+///   thread owning the state, whose proxy reaches the other components through the container,
+///   see `dep_inj::actor`.
+/// * `#[component(remote, provide(IsEven))]` on a client generated by [`macro@remote`] forwards
+///   the calls to another process, see `dep_inj::ipc`.
+/// * `fn dependency_graph()` of the container describes the components, the interfaces they
+///   provide and the ones they depend on, see `dep_inj::graph`.
+/// * `fn component_metadata()` of the container lists the components with their types, sizes and
+///   the metadata of their proxies, see `dep_inj::metadata`.
+/// * `#[component(health, proxy = EvenProxy)]` checks the component by the `HealthCheck` of the
+///   proxy in `fn health()` of the container, see `dep_inj::health`.
+/// * `#[component(config)]` loads the `#[config]` of the state in `fn configure(&mut self, source)`
+///   of the container, from the keys under the name of the component, see `dep_inj::config`.
+/// * `#[component(tag = Replica, ..)]` implements `dep_inj::tagged::Has<Replica, _>` and `HasMut`
+///   instead of `AsRef` and `AsMut`, for several instances of the same state, see
+///   `dep_inj::tagged`.
+/// * `#[component(access = Provide)]` implements only `dep_inj::provide::Provide` and `ProvideMut`
+///   of the `AsRef`, `AsMut` and them implemented for every component, and
+///   `#[component(access = AsRef)]` only `AsRef` and `AsMut`, see `dep_inj::provide`.
+/// * `#[component(delegate)]` on a field of another container marked by `#[delegatable]` forwards
+///   its components and interfaces, see below.
+/// * `#[hot_swap(IsEven => [even_state, cached_even_state])]` on a `dep_inj::hot_swap::HotSwap`
+///   field dispatches `IsEven` to the current one of the candidate components.
+/// * `#[scope(pub struct RequestScope { .. }, provide(..))]` on the container generates a
+///   scope borrowing the container, see below.
+/// * `#[overridable(pub struct EvenStubbed: IsEven)]` on the container generates an override
+///   replacing `IsEven` by a stub, see below.
+/// * `#[transaction]` on a `dep_inj::transaction::Transaction` field runs the `&mut self` calls
+///   as transactions, rolling back the `#[component(rollback)]`s on `Err` or panics, whose
+///   states implement or derive `dep_inj::transaction::Checkpoint`, see `dep_inj::transaction`.
+/// * `#[poison]` on a `dep_inj::isolation::Poison` field catches the panics of the
+///   `#[component(isolate)]`s at the interface boundaries and poisons them, see
+///   `dep_inj::isolation`.
+/// * `#[supervisor]` on a `dep_inj::supervisor::Supervisor` field restarts the poisoned
+///   components, and the `#[component(restart)]`s depending on them, and shuts the container
+///   down on a crash loop, see `dep_inj::supervisor`.
+/// * `#[snapshot]` on the container implements `dep_inj::snapshot::Snapshot` over all the
+///   components, keyed by their names.
+/// * `#[builder(pub struct GlobalBuilder)]` on the container generates a builder checking at
+///   compile time that every component is supplied, except the `#[component(default)]`s, see
+///   `dep_inj::builder`.
+///
+/// ```
+/// # use dep_inj::{Container, DepInj};
+/// # use std::sync::Arc;
+/// # #[dep_inj::interface]
+/// # pub trait IsOdd { fn is_odd(self: Arc<Self>, n: u64) -> bool; }
+/// # #[dep_inj::interface]
+/// # pub trait IsEven { fn is_even(self: Arc<Self>, n: u64) -> bool; }
+/// # #[derive(Default, DepInj)]
+/// # #[target(OddProxy)]
+/// # pub struct OddState;
+/// # impl<Deps: IsEven> IsOdd for OddProxy<Deps> {
+/// #     fn is_odd(self: Arc<Self>, n: u64) -> bool { n != 0 && self.prj_arc().is_even(n - 1) }
+/// # }
+/// # #[derive(Default, DepInj)]
+/// # #[target(EvenProxy)]
+/// # pub struct EvenState;
+/// # impl<Deps: IsOdd> IsEven for EvenProxy<Deps> {
+/// #     fn is_even(self: Arc<Self>, n: u64) -> bool { n == 0 || self.prj_arc().is_odd(n - 1) }
+/// # }
+/// #[derive(Default, Container)]
+/// pub struct GlobalStruct {
+///     #[component(proxy = OddProxy, provide(IsOdd))]
+///     odd_state: OddState,
+///     #[component(proxy = EvenProxy, provide(IsEven))]
+///     even_state: EvenState,
+/// }
+///
+/// assert!(Arc::new(GlobalStruct::default()).is_even(42));
+/// ```
+///
+/// will expand to
+///
+/// ```
+/// # use dep_inj::DepInj;
+/// # use std::sync::Arc;
+/// # pub trait IsOdd { fn is_odd(self: Arc<Self>, n: u64) -> bool; }
+/// # pub trait IsEven { fn is_even(self: Arc<Self>, n: u64) -> bool; }
+/// # #[derive(Default, DepInj)]
+/// # #[target(OddProxy)]
+/// # pub struct OddState;
+/// # #[derive(Default, DepInj)]
+/// # #[target(EvenProxy)]
+/// # pub struct EvenState;
+/// # impl<Deps> IsEven for EvenProxy<Deps> {
+/// #     fn is_even(self: Arc<Self>, n: u64) -> bool { n % 2 == 0 }
+/// # }
+/// # pub struct GlobalStruct { odd_state: OddState, even_state: EvenState }
+/// impl AsRef<OddState> for GlobalStruct {
+///     #[inline]
+///     fn as_ref(&self) -> &OddState {
+///         &self.odd_state
+///     }
+/// }
+///
+/// impl AsMut<OddState> for GlobalStruct {
+///     #[inline]
+///     fn as_mut(&mut self) -> &mut OddState {
+///         &mut self.odd_state
+///     }
+/// }
+///
+/// // and `Provide<OddState>`, `ProvideMut<OddState>`, the same for `EvenState`, `IsOdd`...
+///
+/// impl IsEven for GlobalStruct {
+///     #[inline]
+///     fn is_even(self: Arc<Self>, n: u64) -> bool {
+///         <EvenProxy<Self> as IsEven>::is_even(<EvenProxy<Self>>::inj_arc(self), n)
+///     }
+/// }
+/// ```
+///
+/// # Scopes
+///
+/// A scope holds the per-request states, like a transaction or a user identity.
+/// It borrows the container as `parent`, implements `AsRef` and `AsMut` of its own components,
+/// and `AsRef` of the components of the container unless overlaid by its own ones.
+/// The interfaces of its own components are forwarded to itself, and the ones of the container
+/// listed in `provide(..)` are forwarded to the proxies of the container components injected
+/// with the scope, so that the whole call tree sees the scope. `provide(IsOdd => odd_state)`
+/// names the providing component, for the interfaces only available in the scope.
+///
+/// ```
+/// # use dep_inj::{Container, DepInj};
+/// # #[dep_inj::interface]
+/// # pub trait IsOdd { fn is_odd(&self, n: u64) -> bool; }
+/// #[dep_inj::interface]
+/// pub trait CurrentUser {
+///     fn user(&self) -> &str;
+/// }
+///
+/// #[derive(DepInj)]
+/// #[target(UserProxy)]
+/// pub struct UserState(String);
+///
+/// impl<Deps: AsRef<UserState>> CurrentUser for UserProxy<Deps> {
+///     fn user(&self) -> &str {
+///         &self.0
+///     }
+/// }
+///
+/// #[derive(Default, DepInj)]
+/// #[target(OddProxy)]
+/// pub struct OddState;
+///
+/// impl<Deps: CurrentUser> IsOdd for OddProxy<Deps> {
+///     fn is_odd(&self, n: u64) -> bool {
+///         assert_eq!(self.prj_ref().user(), "alice");
+///         n % 2 == 1
+///     }
+/// }
+///
+/// #[derive(Default, Container)]
+/// #[scope(pub struct RequestScope {
+///     #[component(proxy = UserProxy, provide(CurrentUser))]
+///     user: UserState,
+/// }, provide(IsOdd => odd_state))]
+/// pub struct GlobalStruct {
+///     // `IsOdd` needs a `CurrentUser`, only available in the scope
+///     #[component(proxy = OddProxy)]
+///     odd_state: OddState,
+/// }
+///
+/// let global = GlobalStruct::default();
+/// let scope = RequestScope::new(&global, UserState("alice".to_string()));
+/// assert!(OddProxy::inj_ref(&scope).is_odd(7));
+/// assert!(scope.is_odd(7));
+/// ```
+///
+/// The scope takes the generics of the container after a `'parent` lifetime,
+/// `RequestScope<'parent>` here, so the proxies requiring `'static` deps cannot be injected
+/// with it.
+///
+/// # Overrides
+///
+/// An override owns the container and a stub of some interfaces, usually for tests.
+/// It implements the `AsRef`s, `AsMut`s and interfaces of the container, except that the
+/// overridden interfaces are forwarded to the stub. The proxies are injected with the override,
+/// so the calls between the components reach the stub too.
+///
+/// ```
+/// # use dep_inj::{Container, DepInj};
+/// # use std::sync::atomic::{AtomicUsize, Ordering};
+/// # use std::sync::Arc;
+/// # #[dep_inj::interface]
+/// # pub trait IsOdd { fn is_odd(self: Arc<Self>, n: u64) -> bool; }
+/// # #[dep_inj::interface]
+/// # pub trait IsEven { fn is_even(self: Arc<Self>, n: u64) -> bool; }
+/// # #[derive(Default, DepInj)]
+/// # #[target(OddProxy)]
+/// # pub struct OddState;
+/// # impl<Deps: IsEven> IsOdd for OddProxy<Deps> {
+/// #     fn is_odd(self: Arc<Self>, n: u64) -> bool { n != 0 && self.prj_arc().is_even(n - 1) }
+/// # }
+/// # #[derive(Default, DepInj)]
+/// # #[target(EvenProxy)]
+/// # pub struct EvenState;
+/// # impl<Deps: IsOdd> IsEven for EvenProxy<Deps> {
+/// #     fn is_even(self: Arc<Self>, n: u64) -> bool { n == 0 || self.prj_arc().is_odd(n - 1) }
+/// # }
+/// #[derive(Default, Container)]
+/// #[overridable(pub struct EvenStubbed: IsEven)]
+/// pub struct GlobalStruct {
+///     #[component(proxy = OddProxy, provide(IsOdd))]
+///     odd_state: OddState,
+///     #[component(proxy = EvenProxy, provide(IsEven))]
+///     even_state: EvenState,
+/// }
+///
+/// #[derive(Default)]
+/// struct EvenStub {
+///     calls: AtomicUsize,
+/// }
+///
+/// impl IsEven for EvenStub {
+///     fn is_even(self: Arc<Self>, _n: u64) -> bool {
+///         self.calls.fetch_add(1, Ordering::Relaxed);
+///         true
+///     }
+/// }
+///
+/// let stub = Arc::new(EvenStub::default());
+/// let global = Arc::new(EvenStubbed::new(GlobalStruct::default(), stub.clone()));
+/// // `OddProxy` calls the stub
+/// assert!(global.is_odd(42));
+/// assert_eq!(stub.calls.load(Ordering::Relaxed), 1);
+/// ```
+///
+/// The stub is shared with the test in an `Arc`, so it keeps its state behind atomics or locks,
+/// and only the methods taking `&self` or `Arc<Self>` can be stubbed: an overridden interface
+/// with any other receiver is a compile error.
+///
+/// # Sub-containers
+///
+/// A subsystem can be wired by its own container marked by `#[delegatable]`, and embedded into the
+/// top container by `#[component(delegate)]`. The top container implements `AsRef` and `AsMut` of
+/// the components of the sub-container, recursively through its own delegates, and the interfaces
+/// it provides.
+/// The proxies of the sub-container are injected with the top container, so they can depend on
+/// the interfaces provided by the top one.
+///
+/// ```
+/// # use dep_inj::{Container, DepInj};
+/// #[dep_inj::interface]
+/// pub trait Store {
+///     fn put(&mut self, blob: &[u8]);
+/// }
+///
+/// mod storage {
+///     use super::Store;
+///     use dep_inj::{Container, DepInj};
+///
+///     #[derive(Default, DepInj)]
+///     #[target(BlobProxy)]
+///     pub struct BlobState {
+///         pub blobs: Vec<Vec<u8>>,
+///     }
+///
+///     impl<Deps: AsRef<BlobState> + AsMut<BlobState>> Store for BlobProxy<Deps> {
+///         fn put(&mut self, blob: &[u8]) {
+///             self.blobs.push(blob.to_vec());
+///         }
+///     }
+///
+///     #[derive(Default, Container)]
+///     #[delegatable]
+///     pub struct StorageContainer {
+///         #[component(proxy = BlobProxy, provide(Store))]
+///         pub blob_state: BlobState,
+///     }
+/// }
+///
+/// use storage::{BlobProxy, BlobState, StorageContainer};
+///
+/// #[derive(Default, Container)]
+/// pub struct GlobalStruct {
+///     #[component(delegate)]
+///     storage: StorageContainer,
+/// }
+///
+/// # fn main() {
+/// let mut global = GlobalStruct::default();
+/// global.put(b"blob");
+/// let blobs: &BlobState = global.as_ref();
+/// assert_eq!(blobs.blobs.len(), 1);
+/// # }
+/// ```
+///
+/// Like the interfaces, the sub-container is reached by a macro of its name generated by
+/// `#[delegatable]`, so it, the states and proxies of its components and its interfaces must be in
+/// scope where the top container is defined. A delegatable container cannot be generic, have a
+/// `#[transaction]`, a `#[poison]` or pinned components, and an interface provided by both
+/// containers conflicts.
+///
+/// # Layers
+///
+/// A layer is a proxy derived with `#[target(Metrics, layer)]`, which wraps another proxy instead
+/// of the container, and implements the interfaces by decorating the wrapped ones. Its state is
+/// still a component of the container, reached through `dep_inj::Proxy::container`.
+/// Layers are stacked by `layers(..)`, the outermost first.
+///
+/// ```
+/// # use dep_inj::{Container, DepInj, Proxy};
+/// # use std::sync::atomic::{AtomicUsize, Ordering};
+/// # use std::sync::Arc;
+/// # #[dep_inj::interface]
+/// # pub trait IsOdd { fn is_odd(self: Arc<Self>, n: u64) -> bool; }
+/// # #[dep_inj::interface]
+/// # pub trait IsEven { fn is_even(self: Arc<Self>, n: u64) -> bool; }
+/// # #[derive(Default, DepInj)]
+/// # #[target(OddProxy)]
+/// # pub struct OddState;
+/// # impl<Deps: IsEven> IsOdd for OddProxy<Deps> {
+/// #     fn is_odd(self: Arc<Self>, n: u64) -> bool { n != 0 && self.prj_arc().is_even(n - 1) }
+/// # }
+/// # #[derive(Default, DepInj)]
+/// # #[target(EvenProxy)]
+/// # pub struct EvenState;
+/// # impl<Deps: IsOdd> IsEven for EvenProxy<Deps> {
+/// #     fn is_even(self: Arc<Self>, n: u64) -> bool { n == 0 || self.prj_arc().is_odd(n - 1) }
+/// # }
+/// #[derive(Default, DepInj)]
+/// #[target(Metrics, layer)]
+/// pub struct MetricsState {
+///     calls: AtomicUsize,
+/// }
+///
+/// impl<Inner: IsEven + Proxy> IsEven for Metrics<Inner>
+/// where
+///     Inner::Container: AsRef<MetricsState>,
+/// {
+///     fn is_even(self: Arc<Self>, n: u64) -> bool {
+///         self.calls.fetch_add(1, Ordering::Relaxed);
+///         self.prj_arc().is_even(n)
+///     }
+/// }
+///
+/// #[derive(Default, Container)]
+/// pub struct GlobalStruct {
+///     #[component(proxy = OddProxy, provide(IsOdd))]
+///     odd_state: OddState,
+///     #[component(proxy = EvenProxy, layers(Metrics), provide(IsEven))]
+///     even_state: EvenState,
+///     #[component]
+///     metrics_state: MetricsState,
+/// }
+///
+/// let global = Arc::new(GlobalStruct::default());
+/// assert!(!global.clone().is_odd(4));
+/// // `is_odd(4)` -> `is_even(3)` -> `is_odd(2)` -> `is_even(1)` -> `is_odd(0)`
+/// assert_eq!(global.metrics_state.calls.load(Ordering::Relaxed), 2);
+/// ```
+#[proc_macro_derive(
+    Container,
+    attributes(
+        component,
+        hot_swap,
+        scope,
+        overridable,
+        snapshot,
+        builder,
+        delegatable,
+        transaction,
+        poison,
+        supervisor
+    )
+)]
+pub fn derive_container(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let derive_input = parse_macro_input!(input as syn::DeriveInput);
+
+    match container::derive_container_impl(derive_input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.into_compile_error().into(),
+    }
+}
+
+/// Called back by the macro generated for a sub-container, see `#[component(delegate)]`.
+#[doc(hidden)]
+#[proc_macro]
+pub fn __delegate(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as container::DelegateInput);
+
+    match container::delegate_impl(input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.into_compile_error().into(),
+    }
+}
+
+/// Implement `dep_inj::snapshot::Snapshot` for a struct, capturing its fields into a map keyed by
+/// their names. The fields marked by `#[snapshot(skip)]` are neither captured nor restored.
+#[proc_macro_derive(Snapshot, attributes(snapshot))]
+pub fn derive_snapshot(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let derive_input = parse_macro_input!(input as syn::DeriveInput);
+
+    match snapshot::derive_snapshot_impl(derive_input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.into_compile_error().into(),
+    }
+}
+
+/// Implement `dep_inj::transaction::Checkpoint` for a `Clone` state, checkpointing it by a clone
+/// and rolling back by replacing it with the clone.
+#[proc_macro_derive(Checkpoint)]
+pub fn derive_checkpoint(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let derive_input = parse_macro_input!(input as syn::DeriveInput);
+
+    derive_checkpoint_impl(derive_input).into()
+}
+
+/// Implement `dep_inj::config::FromConfig` for a struct, reading each field by `FromStr` from the
+/// key of its name. A missing key fails, unless the field is an `Option` or marked by
+/// `#[config(default)]`.
+#[proc_macro_derive(FromConfig, attributes(config))]
+pub fn derive_from_config(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let derive_input = parse_macro_input!(input as syn::DeriveInput);
+
+    match config::derive_from_config_impl(derive_input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.into_compile_error().into(),
+    }
+}
+
+fn derive_checkpoint_impl(derive_input: syn::DeriveInput) -> TokenStream {
+    let ident = &derive_input.ident;
+    let mut generics = derive_input.generics.clone();
+    generics
+        .make_where_clause()
+        .predicates
+        .push(parse_quote!(Self: ::core::clone::Clone + Send + Sync + 'static));
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    quote! {
+        impl #impl_generics ::dep_inj::transaction::Checkpoint for #ident #ty_generics #where_clause {
+            type Saved = Self;
+
+            #[inline]
+            fn checkpoint(&self) -> Self {
+                ::core::clone::Clone::clone(self)
+            }
+
+            #[inline]
+            fn rollback(&mut self, saved: Self) {
+                *self = saved;
+            }
+        }
+    }
+}
+
+fn derive_dep_inj_impl(derive_input: syn::DeriveInput) -> syn::Result<TokenStream> {
+    let target_def = target_def(&derive_input)?;
+
+    Ok(quote! {
+        #target_def
+    })
+}
+
+fn target_def(derive_input: &syn::DeriveInput) -> syn::Result<TokenStream> {
+    let targets = target_args(derive_input)?;
+    // `FooState<T>`
+    let derive_type = derive_type(derive_input);
+    let proxies = targets
+        .iter()
+        .map(|target_args| proxy_def(derive_input, target_args, &derive_type))
+        .collect::<syn::Result<Vec<_>>>()?;
+    // `impl Dependencies for FooState<T>`
+    let dependencies = dependencies(derive_input, &derive_type, &targets);
+    // `impl Configured for FooState<T>` by the `#[config]` field
+    let state_config = state_config(derive_input, &derive_type)?;
+
+    Ok(quote! {
+        #(#proxies)*
+        #dependencies
+        #state_config
+    })
+}
+
+/// The proxy of a `#[target(..)]`.
+fn proxy_def(
+    derive_input: &syn::DeriveInput,
+    target_args: &TargetArgs,
+    derive_type: &syn::Type,
+) -> syn::Result<TokenStream> {
+    // `struct Foo<T, Deps: ?Sized> { .. }`
+    let target_struct = target_struct(derive_input, target_args, derive_type);
+    // `Foo<T, Deps>`
+    let target_type = target_type(&target_struct);
+    // `impl Deref for Foo<T, Deps>`
+    let target_deref = target_deref(&target_struct, &target_type, derive_type, target_args);
+    // `impl DerefMut for Foo<T, Deps>`
+    let target_deref_mut = target_deref_mut(&target_struct, &target_type, derive_type, target_args);
+    // `impl Proxy for Foo<T, Deps>`
+    let target_proxy = target_proxy(&target_struct, &target_type, target_args);
+    // `impl From<Foo<T, Deps>> for FooState<T>`
+    let target_from = target_from(&target_struct, &target_type, derive_type);
+    let target_clone = target_clone(&target_struct, &target_type);
+    let target_copy = target_copy(&target_struct, &target_type);
+    let target_partial_eq = target_partial_eq(&target_struct, &target_type);
+    let target_eq = target_eq(&target_struct, &target_type);
+    let target_partial_ord = target_partial_ord(&target_struct, &target_type);
+    let target_ord = target_ord(&target_struct, &target_type);
+    let target_hash = target_hash(&target_struct, &target_type);
+    let target_debug = target_debug(&target_struct, &target_type);
+    let target_ref_casting = target_impl_ref_casting(&target_struct, &target_type);
+    let target_impl_new = target_impl_new(&target_struct, &target_type);
+    // `struct FooProjection<'__pin, T>` and `Foo::project`
+    let target_projection = target_projection(derive_input, target_args, &target_struct)?;
+    // `impl Wired for Foo<T, Deps> where Self: IsFoo`
+    let target_wired = target_wired(&target_struct, &target_type, target_args);
+    // `impl Satisfied for Foo<T, Deps> where Deps: IsBar`
+    let target_satisfied = target_satisfied(&target_struct, &target_type, target_args);
+    // `impl Describe for Foo<T, Deps>`
+    let target_describe = target_describe(derive_input, &target_struct, &target_type, target_args);
+
+    Ok(quote! {
+        #target_struct
+        #target_copy
+        #target_deref
+        #target_deref_mut
+        #target_proxy
+        #target_from
+        #target_clone
+        #target_partial_eq
+        #target_eq
+        #target_partial_ord
+        #target_ord
+        #target_hash
+        #target_debug
+        #target_ref_casting
+        #target_impl_new
+        #target_projection
+        #target_wired
+        #target_satisfied
+        #target_describe
+    })
+}
+
+fn target_struct(
+    derive_input: &syn::DeriveInput,
+    target_args: &TargetArgs,
+    derive_type: &syn::Type,
+) -> syn::ItemStruct {
+    let target_ident = target_args.ident.clone();
+
+    let mut target_generics = derive_input.generics.clone();
+    target_generics
+        .params
+        .push(parse_quote! { __Deps__: ?Sized });
+
+    let target_fields = syn::Fields::Named(parse_quote! {{
+        _marker: ::core::marker::PhantomData<#derive_type>,
+        deps: __Deps__
+    }});
+
+    syn::ItemStruct {
+        attrs: vec![parse_quote!(#[repr(transparent)])],
+        vis: derive_input.vis.clone(),
+        struct_token: Default::default(),
+        ident: target_ident,
+        generics: target_generics,
+        fields: target_fields,
+        semi_token: None,
+    }
+}
+
+/// `#[target(Foo)]`, or `#[target(Foo, layer)]` for a layer wrapping another proxy,
+/// with `provide(IsFoo, ..)` declaring the interfaces implemented by the proxy,
+/// and `depend(IsBar, ..)` the ones it depends on.
+/// `tag = Replica` reaches the state by `Has<Replica, FooState>` instead of `AsRef`,
+/// and `access = Provide` by `Provide<FooState>`.
+struct TargetArgs {
+    ident: syn::Ident,
+    layer: bool,
+    tag: Option<syn::Path>,
+    via_provide: bool,
+    provides: Vec<syn::Path>,
+    depends: Vec<syn::Path>,
+}
+
+impl syn::parse::Parse for TargetArgs {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let ident = input.parse()?;
+        let mut layer = false;
+        let mut tag: Option<syn::Path> = None;
+        let mut via_provide = false;
+        let mut provides = vec![];
+        let mut depends = vec![];
+        while !input.is_empty() {
+            input.parse::<syn::Token![,]>()?;
+            if input.is_empty() {
+                break;
+            }
+            let option: syn::Ident = input.parse()?;
+            if option == "layer" {
+                layer = true;
+            } else if option == "tag" {
+                input.parse::<syn::Token![=]>()?;
+                tag = Some(input.parse()?);
+            } else if option == "access" {
+                via_provide = parse_access(input)?;
+            } else if option == "provide" {
+                let content;
+                syn::parenthesized!(content in input);
+                provides.extend(
+                    content
+                        .parse_terminated::<syn::Path, syn::Token![,]>(syn::parse::Parse::parse)?,
+                );
+            } else if option == "depend" {
+                let content;
+                syn::parenthesized!(content in input);
+                depends.extend(
+                    content
+                        .parse_terminated::<syn::Path, syn::Token![,]>(syn::parse::Parse::parse)?,
+                );
+            } else {
+                return Err(syn::Error::new(
+                    option.span(),
+                    "expect `layer`, `tag = Tag`, `access = Provide`, `provide(Interface, ..)` \
+                    or `depend(Interface, ..)`",
+                ));
+            }
+        }
+        if let (Some(tag), true) = (&tag, via_provide) {
+            return Err(syn::Error::new(
+                tag.span(),
+                "a tagged proxy reaches its state by `Has`, not `Provide`",
+            ));
+        }
+        Ok(Self {
+            ident,
+            layer,
+            tag,
+            via_provide,
+            provides,
+            depends,
+        })
+    }
+}
+
+/// `= Provide`, or `= AsRef` by default, how a proxy reaches its state in the container.
+fn parse_access(input: syn::parse::ParseStream) -> syn::Result<bool> {
+    input.parse::<syn::Token![=]>()?;
+    let access: syn::Ident = input.parse()?;
+    if access == "Provide" {
+        Ok(true)
+    } else if access == "AsRef" {
+        Ok(false)
+    } else {
+        Err(syn::Error::new(
+            access.span(),
+            "expect `access = Provide` or `access = AsRef`",
+        ))
+    }
+}
+
+/// One `#[target()]`, or several ones with at most one untagged.
+fn target_args(derive_input: &syn::DeriveInput) -> syn::Result<Vec<TargetArgs>> {
+    let mut targets = vec![];
+    for attr in derive_input
+        .attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("target"))
+    {
+        let target = attr.parse_args::<TargetArgs>()?;
+        if target.tag.is_none()
+            && targets
+                .iter()
+                .any(|target: &TargetArgs| target.tag.is_none())
+        {
+            return Err(syn::Error::new(
+                attr.span(),
+                "the other `#[target()]`s of a state need a `tag = Tag` each",
+            ));
+        }
+        targets.push(target);
+    }
+    if targets.is_empty() {
+        return Err(syn::Error::new(
+            Span::call_site(),
+            "`DepInj` requires a `#[target()]` attribute",
+        ));
+    }
+    Ok(targets)
+}
+
+fn target_type(target_struct: &syn::ItemStruct) -> syn::Type {
+    let ident = target_struct.ident.clone();
+    let mut generic = target_struct.generics.clone();
+    generic.where_clause = None;
+    for param in generic.params.iter_mut() {
+        match param {
+            syn::GenericParam::Type(ty) => {
+                ty.attrs = vec![];
+                ty.colon_token = None;
+                ty.bounds = Default::default();
+                ty.eq_token = None;
+                ty.default = None;
+            }
+            syn::GenericParam::Lifetime(lifetime) => {
+                lifetime.attrs = vec![];
+                lifetime.colon_token = None;
+                lifetime.bounds = Default::default();
+            }
+            syn::GenericParam::Const(r#const) => {
+                r#const.attrs = vec![];
+                r#const.const_token = Default::default();
+                r#const.eq_token = None;
+                r#const.default = None;
+            }
+        }
+    }
+
+    // Foo<T, Deps>
+    parse_quote! {
+        #ident #generic
+    }
+}
+
+fn target_deref(
+    target_struct: &syn::ItemStruct,
+    target_type: &syn::Type,
+    derive_type: &syn::Type,
+    target_args: &TargetArgs,
+) -> syn::ItemImpl {
+    let mut generics = target_struct.generics.clone();
+    let where_clause = generics.where_clause.get_or_insert(syn::WhereClause {
+        where_token: Default::default(),
+        predicates: Default::default(),
+    });
+    // the state tagged by `Replica` is reached by `Has<Replica, FooState>`
+    let (as_ref, get) = match &target_args.tag {
+        Some(tag) => (
+            quote!(::dep_inj::tagged::Has<#tag, #derive_type>),
+            quote!(::dep_inj::tagged::Has::<#tag, #derive_type>::get),
+        ),
+        None if target_args.via_provide => (
+            quote!(::dep_inj::provide::Provide<#derive_type>),
+            quote!(::dep_inj::provide::Provide::<#derive_type>::provide),
+        ),
+        None => (
+            quote!(AsRef<#derive_type>),
+            quote!(AsRef::<#derive_type>::as_ref),
+        ),
+    };
+    // the state of a layer is in the container under the wrapped proxy
+    let deps = if target_args.layer {
+        where_clause
+            .predicates
+            .push(parse_quote!(__Deps__: ::dep_inj::Proxy));
+        where_clause.predicates.push(parse_quote!(
+            <__Deps__ as ::dep_inj::Proxy>::Container: #as_ref
+        ));
+        quote!(::dep_inj::Proxy::container(&self.deps))
+    } else {
+        where_clause
+            .predicates
+            .push(parse_quote!(__Deps__: #as_ref));
+        quote!(&self.deps)
+    };
+
+    syn::ItemImpl {
+        attrs: vec![],
+        defaultness: None,
+        unsafety: None,
+        impl_token: Default::default(),
+        generics,
+        trait_: Some((None, parse_quote!(::core::ops::Deref), Default::default())),
+        self_ty: Box::new(target_type.clone()),
+        brace_token: Default::default(),
+        items: vec![
+            parse_quote! {
+                type Target = #derive_type;
+            },
+            parse_quote! {
+                #[inline]
+                fn deref(&self) -> &Self::Target {
+                    #get(#deps)
+                }
+            },
+        ],
+    }
+}
+
+fn target_deref_mut(
+    target_struct: &syn::ItemStruct,
+    target_type: &syn::Type,
+    derive_type: &syn::Type,
+    target_args: &TargetArgs,
+) -> syn::ItemImpl {
+    let mut generics = target_struct.generics.clone();
+    let where_clause = generics.where_clause.get_or_insert(syn::WhereClause {
+        where_token: Default::default(),
+        predicates: Default::default(),
+    });
+    let (as_mut, get_mut) = match &target_args.tag {
+        Some(tag) => (
+            quote!(::dep_inj::tagged::HasMut<#tag, #derive_type>),
+            quote!(::dep_inj::tagged::HasMut::<#tag, #derive_type>::get_mut),
+        ),
+        None if target_args.via_provide => (
+            quote!(::dep_inj::provide::ProvideMut<#derive_type>),
+            quote!(::dep_inj::provide::ProvideMut::<#derive_type>::provide_mut),
+        ),
+        None => (
+            quote!(AsRef<#derive_type> + AsMut<#derive_type>),
+            quote!(AsMut::<#derive_type>::as_mut),
+        ),
+    };
+    let deps = if target_args.layer {
+        where_clause
+            .predicates
+            .push(parse_quote!(__Deps__: ::dep_inj::Proxy));
+        where_clause.predicates.push(parse_quote!(
+            <__Deps__ as ::dep_inj::Proxy>::Container: #as_mut
+        ));
+        quote!(::dep_inj::Proxy::container_mut(&mut self.deps))
+    } else {
+        where_clause
+            .predicates
+            .push(parse_quote!(__Deps__: #as_mut));
+        quote!(&mut self.deps)
+    };
+
+    syn::ItemImpl {
+        attrs: vec![],
+        defaultness: None,
+        unsafety: None,
+        impl_token: Default::default(),
+        generics,
+        trait_: Some((
+            None,
+            parse_quote!(::core::ops::DerefMut),
+            Default::default(),
+        )),
+        self_ty: Box::new(target_type.clone()),
+        brace_token: Default::default(),
+        items: vec![parse_quote! {
+            #[inline]
+            fn deref_mut(&mut self) -> &mut Self::Target {
+                #get_mut(#deps)
+            }
+        }],
+    }
+}
+
+fn target_proxy(
+    target_struct: &syn::ItemStruct,
+    target_type: &syn::Type,
+    target_args: &TargetArgs,
+) -> syn::ItemImpl {
+    let mut generics = target_struct.generics.clone();
+    let items = if target_args.layer {
+        let where_clause = generics.where_clause.get_or_insert(syn::WhereClause {
+            where_token: Default::default(),
+            predicates: Default::default(),
+        });
+        where_clause
+            .predicates
+            .push(parse_quote!(__Deps__: ::dep_inj::Proxy));
+        vec![
+            parse_quote! {
+                type Container = <__Deps__ as ::dep_inj::Proxy>::Container;
+            },
+            parse_quote! {
+                #[inline]
+                fn container(&self) -> &Self::Container {
+                    ::dep_inj::Proxy::container(&self.deps)
+                }
+            },
+            parse_quote! {
+                #[inline]
+                fn container_mut(&mut self) -> &mut Self::Container {
+                    ::dep_inj::Proxy::container_mut(&mut self.deps)
+                }
+            },
+        ]
+    } else {
+        vec![
+            parse_quote! {
+                type Container = __Deps__;
+            },
+            parse_quote! {
+                #[inline]
+                fn container(&self) -> &Self::Container {
+                    &self.deps
+                }
+            },
+            parse_quote! {
+                #[inline]
+                fn container_mut(&mut self) -> &mut Self::Container {
+                    &mut self.deps
+                }
+            },
+        ]
+    };
+
+    syn::ItemImpl {
+        attrs: vec![],
+        defaultness: None,
+        unsafety: None,
+        impl_token: Default::default(),
+        generics,
+        trait_: Some((None, parse_quote!(::dep_inj::Proxy), Default::default())),
+        self_ty: Box::new(target_type.clone()),
+        brace_token: Default::default(),
+        items,
+    }
+}
+
+fn target_wired(
+    target_struct: &syn::ItemStruct,
+    target_type: &syn::Type,
+    target_args: &TargetArgs,
+) -> Option<syn::ItemImpl> {
+    if target_args.provides.is_empty() {
+        return None;
+    }
+    let provides = &target_args.provides;
+    let mut generics = target_struct.generics.clone();
+    let where_clause = generics.where_clause.get_or_insert(syn::WhereClause {
+        where_token: Default::default(),
+        predicates: Default::default(),
+    });
+    where_clause
+        .predicates
+        .push(parse_quote!(#target_type: #(#provides)+*));
+
+    Some(syn::ItemImpl {
+        attrs: vec![],
+        defaultness: None,
+        unsafety: None,
+        impl_token: Default::default(),
+        generics,
+        trait_: Some((
+            None,
+            parse_quote!(::dep_inj::wiring::Wired),
+            Default::default(),
+        )),
+        self_ty: Box::new(target_type.clone()),
+        brace_token: Default::default(),
+        items: vec![],
+    })
+}
+
+fn target_satisfied(
+    target_struct: &syn::ItemStruct,
+    target_type: &syn::Type,
+    target_args: &TargetArgs,
+) -> syn::ItemImpl {
+    let depends = &target_args.depends;
+    let mut generics = target_struct.generics.clone();
+    let where_clause = generics.where_clause.get_or_insert(syn::WhereClause {
+        where_token: Default::default(),
+        predicates: Default::default(),
+    });
+    where_clause.predicates.extend(
+        depends
+            .iter()
+            .map(|depend| -> syn::WherePredicate { parse_quote!(__Deps__: #depend) }),
+    );
+
+    syn::ItemImpl {
+        attrs: vec![],
+        defaultness: None,
+        unsafety: None,
+        impl_token: Default::default(),
+        generics,
+        trait_: Some((
+            None,
+            parse_quote!(::dep_inj::wiring::Satisfied),
+            Default::default(),
+        )),
+        self_ty: Box::new(target_type.clone()),
+        brace_token: Default::default(),
+        items: vec![],
+    }
+}
+
+fn state_config(
+    derive_input: &syn::DeriveInput,
+    derive_type: &syn::Type,
+) -> syn::Result<TokenStream> {
+    let syn::Data::Struct(data) = &derive_input.data else {
+        return Ok(TokenStream::new());
+    };
+    let mut configs = vec![];
+    for (i, field) in data.fields.iter().enumerate() {
+        for attr in field
+            .attrs
+            .iter()
+            .filter(|attr| attr.path.is_ident("config"))
+        {
+            if !attr.tokens.is_empty() {
+                return Err(syn::Error::new(attr.span(), "expect `#[config]`"));
+            }
+            let member = match &field.ident {
+                Some(ident) => syn::Member::Named(ident.clone()),
+                None => syn::Member::Unnamed(i.into()),
+            };
+            configs.push((member, &field.ty, attr));
+        }
+    }
+    let (member, ty) = match &*configs {
+        [] => return Ok(TokenStream::new()),
+        [(member, ty, _)] => (member, ty),
+        [_, (_, _, attr), ..] => {
+            return Err(syn::Error::new(
+                attr.span(),
+                "a state has at most one `#[config]` field",
+            ))
+        }
+    };
+
+    let vis = &derive_input.vis;
+    let (impl_generics, _, where_clause) = derive_input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::dep_inj::config::Configured for #derive_type #where_clause {
+            type Config = #ty;
+
+            #[inline]
+            fn config(&self) -> &Self::Config {
+                &self.#member
+            }
+
+            #[inline]
+            fn configure(&mut self, config: Self::Config) {
+                self.#member = config;
+            }
+        }
+
+        impl #impl_generics #derive_type #where_clause {
+            /// The config of the state, also reached from the proxy.
+            #[inline]
+            #vis fn config(&self) -> &#ty {
+                &self.#member
+            }
+        }
+    })
+}
+
+fn target_describe(
+    derive_input: &syn::DeriveInput,
+    target_struct: &syn::ItemStruct,
+    target_type: &syn::Type,
+    target_args: &TargetArgs,
+) -> TokenStream {
+    let (impl_generics, _, where_clause) = target_struct.generics.split_for_impl();
+    let state = derive_input.ident.to_string();
+    let proxy = target_args.ident.to_string();
+    let layer = target_args.layer;
+    let provides = target_args.provides.iter().map(interface_name);
+    let depends = target_args.depends.iter().map(interface_name);
+    quote! {
+        impl #impl_generics ::dep_inj::metadata::Describe for #target_type #where_clause {
+            const METADATA: ::dep_inj::metadata::Metadata = ::dep_inj::metadata::Metadata {
+                state: #state,
+                proxy: #proxy,
+                module: ::core::module_path!(),
+                layer: #layer,
+                provides: &[#(#provides),*],
+                depends: &[#(#depends),*],
+            };
+        }
+    }
+}
+
+/// `IsEven` of `even_api::IsEven`, as the interfaces are named at runtime.
+fn interface_name(interface: &syn::Path) -> String {
+    interface
+        .segments
+        .last()
+        .map(|segment| segment.ident.to_string())
+        .unwrap_or_default()
+}
+
+/// The interfaces declared by `depend(..)`, of all the proxies of the state.
+fn dependencies(
+    derive_input: &syn::DeriveInput,
+    derive_type: &syn::Type,
+    targets: &[TargetArgs],
+) -> TokenStream {
+    let (impl_generics, _, where_clause) = derive_input.generics.split_for_impl();
+    let mut depends = vec![];
+    for interface in targets.iter().flat_map(|target| &target.depends) {
+        let name = interface_name(interface);
+        if !depends.contains(&name) {
+            depends.push(name);
+        }
+    }
+    quote! {
+        impl #impl_generics ::dep_inj::graph::Dependencies for #derive_type #where_clause {
+            const DEPENDENCIES: &'static [&'static str] = &[#(#depends),*];
+        }
+    }
+}
+
+fn target_from(
+    target_struct: &syn::ItemStruct,
+    target_type: &syn::Type,
+    derive_type: &syn::Type,
+) -> syn::ItemImpl {
+    let mut generics = target_struct.generics.clone();
+    // __Deps__: ?Sized -> __Deps__
+    let dep = generics.params.last_mut().unwrap();
+    *dep = syn::GenericParam::Type(parse_quote!(__Deps__));
+
+    let where_clause = generics.where_clause.get_or_insert(syn::WhereClause {
+        where_token: Default::default(),
+        predicates: Default::default(),
+    });
+    where_clause
+        .predicates
+        .push(parse_quote!(__Deps__: Into<#derive_type>));
+
+    syn::ItemImpl {
+        attrs: vec![],
+        defaultness: None,
+        unsafety: None,
+        impl_token: Default::default(),
+        generics,
+        trait_: Some((None, parse_quote!(From<#target_type>), Default::default())),
+        self_ty: Box::new(derive_type.clone()),
+        brace_token: Default::default(),
+        items: vec![parse_quote! {
+            fn from(value: #target_type) -> Self {
+                value.prj().into()
+            }
+        }],
+    }
+}
+
+fn target_impl_ref_casting(
+    target_struct: &syn::ItemStruct,
+    target_type: &syn::Type,
+) -> syn::ItemImpl {
+    syn::ItemImpl {
+        attrs: vec![],
+        defaultness: None,
+        unsafety: None,
+        impl_token: Default::default(),
+        generics: target_struct.generics.clone(),
+        trait_: None,
+        self_ty: Box::new(target_type.clone()),
+        brace_token: Default::default(),
+        items: vec![
+            parse_quote! {
+                #[inline]
+                pub fn inj_ref(deps: &__Deps__) -> &Self {
+                     unsafe { &*(deps as *const __Deps__ as *const Self) }
+                }
+            },
+            parse_quote! {
+                #[inline]
+                pub fn prj_ref(&self) -> &__Deps__ {
+                    unsafe { &*(self as *const Self as *const __Deps__) }
+                }
+            },
+            parse_quote! {
+                #[inline]
+                pub fn inj_ref_mut(deps: &mut __Deps__) -> &mut Self {
+                    unsafe { &mut*(deps as *mut __Deps__ as *mut Self) }
+                }
+            },
+            parse_quote! {
+                #[inline]
+                pub fn prj_ref_mut(&mut self) -> &mut __Deps__ {
+                    unsafe { &mut*(self as *mut Self as *mut __Deps__) }
+                }
+            },
+            parse_quote! {
+                #[inline]
+                pub fn inj_box(deps: Box<__Deps__>) -> Box<Self> {
+                    unsafe { Box::from_raw(Box::into_raw(deps) as *mut Self) }
+                }
+            },
+            parse_quote! {
+                #[inline]
+                pub fn prj_box(self: Box<Self>) -> Box<__Deps__> {
+                    unsafe { Box::from_raw(Box::into_raw(self) as *mut __Deps__) }
+                }
+            },
+            parse_quote! {
+                #[inline]
+                pub fn inj_rc(deps: ::std::rc::Rc<__Deps__>) -> ::std::rc::Rc<Self> {
+                    unsafe { ::std::rc::Rc::from_raw(::std::rc::Rc::into_raw(deps) as *const Self)}
+                }
+            },
+            parse_quote! {
+                #[inline]
+                pub fn prj_rc(self: ::std::rc::Rc<Self>) -> ::std::rc::Rc<__Deps__> {
+                    unsafe { ::std::rc::Rc::from_raw(::std::rc::Rc::into_raw(self) as *const __Deps__) }
+                }
+            },
+            parse_quote! {
+                #[inline]
+                pub fn inj_arc(deps: ::std::sync::Arc<__Deps__>) -> ::std::sync::Arc<Self> {
+                    unsafe { ::std::sync::Arc::from_raw(::std::sync::Arc::into_raw(deps) as *const Self)}
+                }
+            },
+            parse_quote! {
+                #[inline]
+                pub fn prj_arc(self: ::std::sync::Arc<Self>) -> ::std::sync::Arc<__Deps__> {
+                    unsafe { ::std::sync::Arc::from_raw(::std::sync::Arc::into_raw(self) as *const __Deps__) }
+                }
+            },
+            parse_quote! {
+                #[inline]
+                pub fn inj_pin_ref(deps: ::core::pin::Pin<&__Deps__>) -> ::core::pin::Pin<&Self> {
+                     unsafe {
+                        ::core::pin::Pin::new_unchecked(
+                            &*(::core::pin::Pin::into_inner_unchecked(deps) as *const __Deps__ as *const Self)
+                        )
+                    }
+                }
+            },
+            parse_quote! {
+                #[inline]
+                pub fn prj_pin_ref(self: ::core::pin::Pin<&Self>) -> ::core::pin::Pin<&__Deps__> {
+                    unsafe {
+                        ::core::pin::Pin::new_unchecked(
+                            &*(::core::pin::Pin::into_inner_unchecked(self) as *const Self as *const __Deps__)
+                        )
+                    }
+                }
+            },
+            parse_quote! {
+                #[inline]
+                pub fn inj_pin_ref_mut(deps: ::core::pin::Pin<&mut __Deps__>) -> ::core::pin::Pin<&mut Self> {
+                    unsafe {
+                        ::core::pin::Pin::new_unchecked(
+                            &mut*(::core::pin::Pin::into_inner_unchecked(deps) as *mut __Deps__ as *mut Self)
+                        )
+                    }
+                }
+            },
+            parse_quote! {
+                #[inline]
+                pub fn prj_pin_ref_mut(self: ::core::pin::Pin<&mut Self>) -> ::core::pin::Pin<&mut __Deps__> {
+                    unsafe {
+                        ::core::pin::Pin::new_unchecked(
+                            &mut*(::core::pin::Pin::into_inner_unchecked(self) as *mut Self as *mut __Deps__)
+                        )
+                    }
+                }
+            },
+            parse_quote! {
+                #[inline]
+                pub fn inj_pin_box(deps: ::core::pin::Pin<Box<__Deps__>>) -> ::core::pin::Pin<Box<Self>> {
+                    unsafe {
+                        ::core::pin::Pin::new_unchecked(
+                            Box::from_raw(Box::into_raw(::core::pin::Pin::into_inner_unchecked(deps)) as *mut Self)
+                        )
+                    }
+                }
+            },
+            parse_quote! {
+                #[inline]
+                pub fn prj_pin_box(self: ::core::pin::Pin<Box<Self>>) -> ::core::pin::Pin<Box<__Deps__>> {
+                    unsafe {
+                        ::core::pin::Pin::new_unchecked(
+                            Box::from_raw(Box::into_raw(::core::pin::Pin::into_inner_unchecked(self)) as *mut __Deps__)
+                        )
+                    }
+                }
+            },
+            parse_quote! {
+                #[inline]
+                pub fn inj_pin_rc(deps: ::core::pin::Pin<::std::rc::Rc<__Deps__>>) -> ::core::pin::Pin<::std::rc::Rc<Self>> {
+                    unsafe {
+                        ::core::pin::Pin::new_unchecked(
+                            ::std::rc::Rc::from_raw(::std::rc::Rc::into_raw(::core::pin::Pin::into_inner_unchecked(deps)) as *const Self)
+                        )
+                    }
+                }
+            },
+            parse_quote! {
+                #[inline]
+                pub fn prj_pin_rc(self: ::core::pin::Pin<::std::rc::Rc<Self>>) -> ::core::pin::Pin<::std::rc::Rc<__Deps__>> {
+                    unsafe {
+                        ::core::pin::Pin::new_unchecked(
+                            ::std::rc::Rc::from_raw(::std::rc::Rc::into_raw(::core::pin::Pin::into_inner_unchecked(self)) as *const __Deps__)
+                        )
+                    }
+                }
+            },
+            parse_quote! {
+                #[inline]
+                pub fn inj_pin_arc(deps: ::core::pin::Pin<::std::sync::Arc<__Deps__>>) -> ::core::pin::Pin<::std::sync::Arc<Self>> {
+                    unsafe {
+                        ::core::pin::Pin::new_unchecked(
+                            ::std::sync::Arc::from_raw(::std::sync::Arc::into_raw(::core::pin::Pin::into_inner_unchecked(deps)) as *const Self)
+                        )
+                    }
+                }
+            },
+            parse_quote! {
+                #[inline]
+                pub fn prj_pin_arc(self: ::core::pin::Pin<::std::sync::Arc<Self>>) -> ::core::pin::Pin<::std::sync::Arc<__Deps__>> {
+                    unsafe {
+                        ::core::pin::Pin::new_unchecked(
+                            ::std::sync::Arc::from_raw(::std::sync::Arc::into_raw(::core::pin::Pin::into_inner_unchecked(self)) as *const __Deps__)
+                        )
+                    }
+                }
+            },
+        ],
+    }
+}
+
+fn target_impl_new(target_struct: &syn::ItemStruct, target_type: &syn::Type) -> syn::ItemImpl {
+    let mut generics = target_struct.generics.clone();
+    // __Deps__: ?Sized -> __Deps__
+    let dep = generics.params.last_mut().unwrap();
+    *dep = syn::GenericParam::Type(parse_quote!(__Deps__));
+
+    syn::ItemImpl {
+        attrs: vec![],
+        defaultness: None,
+        unsafety: None,
+        impl_token: Default::default(),
+        generics,
+        trait_: None,
+        self_ty: Box::new(target_type.clone()),
+        brace_token: Default::default(),
+        items: vec![
+            parse_quote! {
+                #[inline]
+                pub fn inj(deps: __Deps__) -> Self {
+                    Self {
+                        _marker: ::core::marker::PhantomData,
+                       deps
+                    }
+                }
+            },
+            parse_quote! {
+                #[inline]
+                pub fn prj(self) -> __Deps__ {
+                    self.deps
+                }
+            },
+        ],
+    }
+}
+
+/// Projects `Pin<&mut Foo<T, Deps>>` to the `#[pin]` fields of the state, as `Pin<&mut F>`,
+/// and to the other fields, as `&mut F`.
+fn target_projection(
+    derive_input: &syn::DeriveInput,
+    target_args: &TargetArgs,
+    target_struct: &syn::ItemStruct,
+) -> syn::Result<TokenStream> {
+    let syn::Data::Struct(data) = &derive_input.data else {
+        return Ok(TokenStream::new());
+    };
+    let is_pinned = |field: &syn::Field| field.attrs.iter().any(|attr| attr.path.is_ident("pin"));
+    let Some(pinned) = data.fields.iter().find(|field| is_pinned(field)) else {
+        return Ok(TokenStream::new());
+    };
+    if target_args.layer {
+        return Err(syn::Error::new(
+            pinned.span(),
+            "the `#[pin]` fields cannot be projected from a layer",
+        ));
+    }
+    if target_args.tag.is_some() {
+        return Err(syn::Error::new(
+            pinned.span(),
+            "the `#[pin]` fields cannot be projected from a tagged proxy",
+        ));
+    }
+
+    let vis = &derive_input.vis;
+    let state_ident = &derive_input.ident;
+    let projection_ident = format_ident!("{}Projection", target_args.ident);
+    let mut projection_generics = derive_input.generics.clone();
+    projection_generics.params.insert(0, parse_quote!('__pin));
+    let (_, projection_ty_generics, _) = projection_generics.split_for_impl();
+    let (_, state_ty_generics, _) = derive_input.generics.split_for_impl();
+    let (impl_generics, target_ty_generics, where_clause) = target_struct.generics.split_for_impl();
+
+    let mut fields = vec![];
+    let mut projections = vec![];
+    for (i, field) in data.fields.iter().enumerate() {
+        let member = match &field.ident {
+            Some(ident) => syn::Member::Named(ident.clone()),
+            None => syn::Member::Unnamed(i.into()),
+        };
+        let field_vis = &field.vis;
+        let ty = &field.ty;
+        let (ty, projection) = if is_pinned(field) {
+            (
+                quote!(::core::pin::Pin<&'__pin mut #ty>),
+                quote!(::core::pin::Pin::new_unchecked(&mut __state.#member)),
+            )
+        } else {
+            (quote!(&'__pin mut #ty), quote!(&mut __state.#member))
+        };
+        fields.push(match &field.ident {
+            Some(ident) => quote!(#field_vis #ident: #ty),
+            None => quote!(#field_vis #ty),
+        });
+        projections.push(quote!(#member: #projection));
+    }
+    let projection_fields = match &data.fields {
+        syn::Fields::Named(_) => quote!({ #(#fields,)* }),
+        _ => quote!((#(#fields,)*);),
+    };
+    let projection_where = &projection_generics.where_clause;
+    let projection_body = match &data.fields {
+        syn::Fields::Named(_) => quote!(#projection_where #projection_fields),
+        _ => quote!(#projection_fields #projection_where),
+    };
+    let projection_doc = format!(
+        "The projection of a pinned `{}`, see `{}::project`.",
+        target_args.ident, target_args.ident
+    );
+    let pinned_tys = data
+        .fields
+        .iter()
+        .filter(|field| is_pinned(field))
+        .map(|field| &field.ty)
+        .collect::<Vec<_>>();
+    let pin_guards = pin_guards(state_ident, &derive_input.generics, &pinned_tys);
+    let target_ident = &target_args.ident;
+    let derive_type = derive_type(derive_input);
+    let mut where_clause = where_clause.cloned().unwrap_or_else(|| parse_quote!(where));
+    where_clause
+        .predicates
+        .push(parse_quote!(__Deps__: ::dep_inj::pin::StructuralPin<#derive_type>));
+
+    Ok(quote! {
+        #[doc = #projection_doc]
+        #vis struct #projection_ident #projection_generics #projection_body
+
+        impl #impl_generics #target_ident #target_ty_generics #where_clause {
+            #[inline]
+            #vis fn project<'__pin>(
+                self: ::core::pin::Pin<&'__pin mut Self>,
+            ) -> #projection_ident #projection_ty_generics {
+                // SAFETY: the state is structurally pinned in the deps, guaranteed by
+                // `StructuralPin`, and the state neither implements `Drop` nor `Unpin` by hand.
+                unsafe {
+                    let __state: &'__pin mut #state_ident #state_ty_generics =
+                        ::core::convert::AsMut::as_mut(
+                            &mut ::core::pin::Pin::get_unchecked_mut(self).deps,
+                        );
+                    #projection_ident {
+                        #(#projections,)*
+                    }
+                }
+            }
+        }
+
+        #pin_guards
+    })
+}
+
+/// Forbids a struct structurally pinning some fields to implement `Drop`, which could move them,
+/// or `Unpin` unless the pinned fields are `Unpin`.
+fn pin_guards(
+    ident: &syn::Ident,
+    generics: &syn::Generics,
+    pinned_tys: &[&syn::Type],
+) -> TokenStream {
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let must_not_impl_drop = format_ident!("{}MustNotImplDrop", ident);
+    let always_unpin = format_ident!("__{}AlwaysUnpin", ident);
+
+    let mut unpin_generics = generics.clone();
+    unpin_generics.params.insert(0, parse_quote!('__pin));
+    let (unpin_impl_generics, unpin_ty_generics, _) = unpin_generics.split_for_impl();
+    let mut unpin_where_clause = where_clause.cloned().unwrap_or_else(|| parse_quote!(where));
+    unpin_where_clause
+        .predicates
+        .push(parse_quote!(#always_unpin #unpin_ty_generics: ::core::marker::Unpin));
+    let pinned_fields = pinned_tys.iter().enumerate().map(|(i, ty)| {
+        let field = format_ident!("__field{}", i);
+        quote!(#field: #ty)
+    });
+    // uses the generics without requiring them to be `Unpin`
+    let used_generics = generics.params.iter().map(|param| match param {
+        syn::GenericParam::Type(ty) => {
+            let ident = &ty.ident;
+            quote!(#ident)
+        }
+        syn::GenericParam::Lifetime(lifetime) => {
+            let lifetime = &lifetime.lifetime;
+            quote!(&#lifetime ())
+        }
+        syn::GenericParam::Const(param) => {
+            let ident = &param.ident;
+            quote!([(); #ident])
+        }
+    });
+
+    quote! {
+        const _: () = {
+            trait #must_not_impl_drop {}
+            #[allow(drop_bounds)]
+            impl<T: ::core::ops::Drop + ?::core::marker::Sized> #must_not_impl_drop for T {}
+            impl #impl_generics #must_not_impl_drop for #ident #ty_generics #where_clause {}
+
+            #[allow(dead_code)]
+            struct #always_unpin #unpin_generics #where_clause {
+                __pin: ::core::marker::PhantomData<&'__pin ()>,
+                __generics: ::core::marker::PhantomData<fn() -> (#(#used_generics,)*)>,
+                #(#pinned_fields,)*
+            }
+
+            impl #unpin_impl_generics ::core::marker::Unpin for #ident #ty_generics
+                #unpin_where_clause
+            {
+            }
+        };
+    }
+}
+
+// impl Clone,  PartialEq, Eq, PartialOrd, Ord, Hash, Debug
+
+fn target_clone(target_struct: &syn::ItemStruct, target_type: &syn::Type) -> syn::ItemImpl {
+    let mut generics = target_struct.generics.clone();
+    // __Deps__: ?Sized -> __Deps__
+    let dep = generics.params.last_mut().unwrap();
+    *dep = syn::GenericParam::Type(parse_quote!(__Deps__));
+
+    let where_clause = generics.where_clause.get_or_insert(syn::WhereClause {
+        where_token: Default::default(),
+        predicates: Default::default(),
+    });
+    where_clause.predicates.push(parse_quote!(__Deps__: Clone));
+
+    syn::ItemImpl {
+        attrs: vec![],
+        defaultness: None,
+        unsafety: None,
+        impl_token: Default::default(),
+        generics,
+        trait_: Some((None, parse_quote!(Clone), Default::default())),
+        self_ty: Box::new(target_type.clone()),
+        brace_token: Default::default(),
+        items: vec![parse_quote! {
+            fn clone(&self) -> Self {
+                Self {
+                    _marker: ::core::marker::PhantomData,
+                    deps: self.deps.clone(),
+                }
+            }
+        }],
+    }
+}
+
+fn target_copy(target_struct: &syn::ItemStruct, target_type: &syn::Type) -> syn::ItemImpl {
+    let mut generics = target_struct.generics.clone();
+    // __Deps__: ?Sized -> __Deps__
+    let dep = generics.params.last_mut().unwrap();
+    *dep = syn::GenericParam::Type(parse_quote!(__Deps__));
+
+    let where_clause = generics.where_clause.get_or_insert(syn::WhereClause {
+        where_token: Default::default(),
+        predicates: Default::default(),
+    });
+    where_clause.predicates.push(parse_quote!(__Deps__: Copy));
+
+    syn::ItemImpl {
+        attrs: vec![],
+        defaultness: None,
+        unsafety: None,
+        impl_token: Default::default(),
+        generics,
+        trait_: Some((None, parse_quote!(Copy), Default::default())),
+        self_ty: Box::new(target_type.clone()),
+        brace_token: Default::default(),
+        items: vec![],
+    }
+}
+
+fn target_partial_eq(target_struct: &syn::ItemStruct, target_type_: &syn::Type) -> syn::ItemImpl {
+    let mut generics = target_struct.generics.clone();
+    generics.params.push(parse_quote!(__RHS__: ?Sized));
+
+    let where_clause = generics.where_clause.get_or_insert(syn::WhereClause {
+        where_token: Default::default(),
+        predicates: Default::default(),
+    });
+    where_clause
+        .predicates
+        .push(parse_quote!(__Deps__: PartialEq<__RHS__>));
+
+    let mut rhs_struct = target_struct.clone();
+    *rhs_struct.generics.params.last_mut().unwrap() =
+        syn::GenericParam::Type(parse_quote!(__RHS__));
+    let rhs_type = target_type(&rhs_struct);
+
+    syn::ItemImpl {
+        attrs: vec![],
+        defaultness: None,
+        unsafety: None,
+        impl_token: Default::default(),
+        generics,
+        trait_: Some((None, parse_quote!(PartialEq<#rhs_type>), Default::default())),
+        self_ty: Box::new(target_type_.clone()),
+        brace_token: Default::default(),
+        items: vec![parse_quote! {
+            fn eq(&self, rhs: & #rhs_type) -> bool {
+                self.deps == rhs.deps
+            }
+        }],
+    }
+}
+
+fn target_eq(target_struct: &syn::ItemStruct, target_type: &syn::Type) -> syn::ItemImpl {
+    let mut generics = target_struct.generics.clone();
+    // __Deps__: ?Sized -> __Deps__
+    let dep = generics.params.last_mut().unwrap();
+    *dep = syn::GenericParam::Type(parse_quote!(__Deps__));
+
+    let where_clause = generics.where_clause.get_or_insert(syn::WhereClause {
+        where_token: Default::default(),
+        predicates: Default::default(),
+    });
+    where_clause.predicates.push(parse_quote!(__Deps__: Eq));
+
+    syn::ItemImpl {
+        attrs: vec![],
+        defaultness: None,
+        unsafety: None,
+        impl_token: Default::default(),
+        generics,
+        trait_: Some((None, parse_quote!(Eq), Default::default())),
+        self_ty: Box::new(target_type.clone()),
+        brace_token: Default::default(),
+        items: vec![],
+    }
+}
+
+fn target_partial_ord(target_struct: &syn::ItemStruct, target_type_: &syn::Type) -> syn::ItemImpl {
+    let mut generics = target_struct.generics.clone();
+    generics.params.push(parse_quote!(__RHS__: ?Sized));
+
+    let where_clause = generics.where_clause.get_or_insert(syn::WhereClause {
+        where_token: Default::default(),
+        predicates: Default::default(),
+    });
+    where_clause
+        .predicates
+        .push(parse_quote!(__Deps__: PartialOrd<__RHS__>));
+
+    let mut rhs_struct = target_struct.clone();
+    *rhs_struct.generics.params.last_mut().unwrap() =
+        syn::GenericParam::Type(parse_quote!(__RHS__));
+    let rhs_type = target_type(&rhs_struct);
+
+    syn::ItemImpl {
+        attrs: vec![],
+        defaultness: None,
+        unsafety: None,
+        impl_token: Default::default(),
+        generics,
+        trait_: Some((
+            None,
+            parse_quote!(PartialOrd<#rhs_type>),
+            Default::default(),
+        )),
+        self_ty: Box::new(target_type_.clone()),
+        brace_token: Default::default(),
+        items: vec![parse_quote! {
+            fn partial_cmp(&self, rhs: & #rhs_type) -> Option<::core::cmp::Ordering> {
+                self.deps.partial_cmp(&rhs.deps)
+            }
+        }],
+    }
+}
+
+fn target_ord(target_struct: &syn::ItemStruct, target_type: &syn::Type) -> syn::ItemImpl {
+    let mut generics = target_struct.generics.clone();
+    // __Deps__: ?Sized -> __Deps__
+    let dep = generics.params.last_mut().unwrap();
+    *dep = syn::GenericParam::Type(parse_quote!(__Deps__));
+
+    let where_clause = generics.where_clause.get_or_insert(syn::WhereClause {
+        where_token: Default::default(),
+        predicates: Default::default(),
+    });
+    where_clause.predicates.push(parse_quote!(__Deps__: Ord));
+
+    syn::ItemImpl {
+        attrs: vec![],
+        defaultness: None,
+        unsafety: None,
+        impl_token: Default::default(),
+        generics,
+        trait_: Some((None, parse_quote!(Ord), Default::default())),
+        self_ty: Box::new(target_type.clone()),
+        brace_token: Default::default(),
+        items: vec![parse_quote! {
+            fn cmp(&self, rhs: &Self) -> ::core::cmp::Ordering {
+                self.deps.cmp(&rhs.deps)
+            }
+        }],
+    }
+}
+
+fn target_hash(target_struct: &syn::ItemStruct, target_type: &syn::Type) -> syn::ItemImpl {
+    let mut generics = target_struct.generics.clone();
+    // __Deps__: ?Sized -> __Deps__
+    let dep = generics.params.last_mut().unwrap();
+    *dep = syn::GenericParam::Type(parse_quote!(__Deps__));
+
+    let where_clause = generics.where_clause.get_or_insert(syn::WhereClause {
+        where_token: Default::default(),
+        predicates: Default::default(),
+    });
+    where_clause
+        .predicates
+        .push(parse_quote!(__Deps__: ::core::hash::Hash));
+
+    syn::ItemImpl {
+        attrs: vec![],
+        defaultness: None,
+        unsafety: None,
+        impl_token: Default::default(),
+        generics,
+        trait_: Some((None, parse_quote!(::core::hash::Hash), Default::default())),
+        self_ty: Box::new(target_type.clone()),
+        brace_token: Default::default(),
+        items: vec![parse_quote! {
+            fn hash<__H__: ::core::hash::Hasher>(&self, hasher: &mut __H__) {
+                self.deps.hash(hasher)
+            }
+        }],
+    }
+}
+
+fn target_debug(target_struct: &syn::ItemStruct, target_type: &syn::Type) -> syn::ItemImpl {
+    let mut generics = target_struct.generics.clone();
+    // __Deps__: ?Sized -> __Deps__
+    let dep = generics.params.last_mut().unwrap();
+    *dep = syn::GenericParam::Type(parse_quote!(__Deps__));
+
+    let where_clause = generics.where_clause.get_or_insert(syn::WhereClause {
+        where_token: Default::default(),
+        predicates: Default::default(),
+    });
+    where_clause
+        .predicates
+        .push(parse_quote!(__Deps__: ::core::fmt::Debug));
+
+    let ident = target_struct.ident.to_string();
+
+    syn::ItemImpl {
+        attrs: vec![],
+        defaultness: None,
+        unsafety: None,
+        impl_token: Default::default(),
+        generics,
+        trait_: Some((None, parse_quote!(::core::fmt::Debug), Default::default())),
+        self_ty: Box::new(target_type.clone()),
+        brace_token: Default::default(),
+        items: vec![parse_quote! {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                f.debug_struct(#ident)
+                 .field("deps", &self.deps)
+                 .finish()
+            }
+        }],
+    }
+}
+
+fn derive_type(derive_input: &syn::DeriveInput) -> syn::Type {
+    let ident = derive_input.ident.clone();
+    let mut generic = derive_input.generics.clone();
+    generic.where_clause = None;
+    for param in generic.params.iter_mut() {
+        match param {
+            syn::GenericParam::Type(ty) => {
+                ty.attrs = vec![];
+                ty.colon_token = None;
+                ty.bounds = Default::default();
+                ty.eq_token = None;
+                ty.default = None;
+            }
+            syn::GenericParam::Lifetime(lifetime) => {
+                lifetime.attrs = vec![];
+                lifetime.colon_token = None;
+                lifetime.bounds = Default::default();
+            }
+            syn::GenericParam::Const(r#const) => {
+                r#const.attrs = vec![];
+                r#const.const_token = Default::default();
+                r#const.eq_token = None;
+                r#const.default = None;
+            }
+        }
+    }
+
+    // FooState<T>
+    parse_quote! {
+        #ident #generic
+    }
+}
+
+/// Whether the `tokens` mention the `ident`.
+fn mentions(tokens: TokenStream, ident: &syn::Ident) -> bool {
+    tokens.into_iter().any(|token| match token {
+        proc_macro2::TokenTree::Ident(i) => i == *ident,
+        proc_macro2::TokenTree::Group(group) => mentions(group.stream(), ident),
+        _ => false,
+    })
+}
+
+/// `__dep_inj_interface_IsEven_0123456789abcdef`, the name of a `#[macro_export]` macro generated
+/// for `item`. The macros share the namespace of the crate root, so the name is made unique by a
+/// hash of the item and its location, as the items of the same name in other modules need their
+/// own ones. It is reached through a `use` re-export under the name of the item instead.
+fn exported_macro_ident(kind: &str, ident: &syn::Ident, item: &impl ToTokens) -> syn::Ident {
+    let mut hasher = DefaultHasher::new();
+    item.to_token_stream().to_string().hash(&mut hasher);
+    format!("{:?}", ident.span()).hash(&mut hasher);
+    format_ident!("__dep_inj_{}_{}_{:016x}", kind, ident, hasher.finish())
+}
+
+/// `self` -> `value`, `Arc<Self>` -> `arc`, `Pin<&mut Self>` -> `pin_ref_mut`,
+/// or `None` if the method has no receiver which a proxy can be casted from.
+fn receiver_kind(arg: &syn::FnArg) -> Option<&'static str> {
+    fn is_self(ty: &syn::Type) -> bool {
+        matches!(ty, syn::Type::Path(ty) if ty.qself.is_none() && ty.path.is_ident("Self"))
+    }
+
+    fn generic_arg(ty: &syn::Type) -> Option<(String, &syn::Type)> {
+        let syn::Type::Path(ty) = ty else {
+            return None;
+        };
+        let last = ty.path.segments.last()?;
+        let syn::PathArguments::AngleBracketed(args) = &last.arguments else {
+            return None;
+        };
+        match args.args.iter().collect::<Vec<_>>()[..] {
+            [syn::GenericArgument::Type(arg)] => Some((last.ident.to_string(), arg)),
+            _ => None,
+        }
+    }
+
+    fn pointer_kind(ty: &syn::Type) -> Option<&'static str> {
+        match ty {
+            syn::Type::Reference(reference) if is_self(&reference.elem) => {
+                Some(match reference.mutability {
+                    Some(_) => "ref_mut",
+                    None => "ref",
+                })
+            }
+            _ => match generic_arg(ty)? {
+                (ident, arg) if is_self(arg) => match &*ident {
+                    "Box" => Some("box"),
+                    "Rc" => Some("rc"),
+                    "Arc" => Some("arc"),
+                    _ => None,
+                },
+                _ => None,
+            },
+        }
+    }
+
+    let ty = match arg {
+        syn::FnArg::Receiver(receiver) => {
+            return Some(match (&receiver.reference, &receiver.mutability) {
+                (None, _) => "value",
+                (Some(_), None) => "ref",
+                (Some(_), Some(_)) => "ref_mut",
+            })
+        }
+        syn::FnArg::Typed(receiver) => match &*receiver.pat {
+            syn::Pat::Ident(pat) if pat.ident == "self" && is_self(&receiver.ty) => {
+                return Some("value")
+            }
+            syn::Pat::Ident(pat) if pat.ident == "self" => &*receiver.ty,
+            _ => return None,
+        },
+    };
+
+    match generic_arg(ty) {
+        Some((ident, arg)) if ident == "Pin" => match pointer_kind(arg)? {
+            "ref" => Some("pin_ref"),
+            "ref_mut" => Some("pin_ref_mut"),
+            "box" => Some("pin_box"),
+            "rc" => Some("pin_rc"),
+            "arc" => Some("pin_arc"),
+            _ => None,
+        },
+        _ => pointer_kind(ty),
+    }
+}
+
+//
+// struct InjectTrait {
+//     trait_token: Token![trait],
+//     ident: syn::Ident,
+//     generics: syn::Generics,
+// }
+//
+// struct InjectType {
+//     pub type_token: Token![type],
+//     pub ident: syn::Ident,
+//     pub generics: syn::Generics,
+// }
+//
+// enum Injection {
+//     Trait(InjectTrait),
+//     Type(InjectType),
+// }
+//
+// impl Parse for InjectTrait {
+//     fn parse(input: ParseStream) -> syn::Result<Self> {
+//         Ok(Self {
+//             trait_token: input.parse()?,
+//             ident: input.parse()?,
+//             generics: input.parse()?,
+//         })
+//     }
+// }
+
+// impl Parse for InjectType {
+//     fn parse(input: ParseStream) -> syn::Result<Self> {
+//         Ok(Self {
+//             type_token: input.parse()?,
+//             ident: input.parse()?,
+//             generics: input.parse()?
+//         })
+//     }
+// }
+//
+// impl Parse for Injection {
+//     fn parse(input: ParseStream) -> syn::Result<Self> {
+//         let lookahead = input.fork().lookahead1();
+//         if lookahead.peek(Token![trait]) {
+//             Ok(Self::Trait(input.parse()?))
+//         } else {
+//             Ok(Self::Type(input.parse()?))
+//         }
+//     }
+// }
+
+// fn injections(derive_input: &syn::DeriveInput) -> syn::Result<Vec<Injection>> {
+//     derive_input
+//         .attrs
+//         .iter()
+//         .filter(|attr| attr.path.is_ident("inj"))
+//         .map(|attr: &syn::Attribute| attr.parse_args::<Injection>())
+//         .collect()
+// }
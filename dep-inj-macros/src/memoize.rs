@@ -0,0 +1,84 @@
+use crate::receiver_kind;
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::spanned::Spanned;
+
+pub(crate) fn memoize_impl(
+    cache: syn::Member,
+    mut method: syn::ImplItemMethod,
+) -> syn::Result<TokenStream> {
+    let sig = &method.sig;
+    if sig.asyncness.is_some() {
+        return Err(syn::Error::new(
+            sig.asyncness.span(),
+            "async methods cannot be memoized",
+        ));
+    }
+    let syn::ReturnType::Type(_, output) = &sig.output else {
+        return Err(syn::Error::new(
+            sig.span(),
+            "methods returning nothing cannot be memoized",
+        ));
+    };
+    let kind = sig.inputs.first().and_then(receiver_kind);
+
+    // the arguments make the key, `n` or `(a, b)`
+    let mut args = vec![];
+    for input in sig.inputs.iter().skip(1) {
+        match input {
+            syn::FnArg::Typed(syn::PatType { pat, .. }) => match &**pat {
+                syn::Pat::Ident(pat) if pat.by_ref.is_none() && pat.subpat.is_none() => {
+                    args.push(pat.ident.clone())
+                }
+                pat => {
+                    return Err(syn::Error::new(
+                        pat.span(),
+                        "the arguments of a memoized method should be identifiers",
+                    ))
+                }
+            },
+            syn::FnArg::Receiver(_) => unreachable!(),
+        }
+    }
+    let key = match &*args {
+        [arg] => quote!(::core::clone::Clone::clone(&#arg)),
+        args => quote!((#(::core::clone::Clone::clone(&#args),)*)),
+    };
+
+    let block = &method.block;
+    // the cache is reached again after the body, which may consume `self`
+    let (prelude, this, body) = match kind {
+        Some("ref" | "ref_mut") => (quote!(), quote!(self), quote!((|| -> #output #block)())),
+        Some("rc" | "arc") => (
+            quote!(let __this = ::core::clone::Clone::clone(&self);),
+            quote!(__this),
+            quote!((move || -> #output #block)()),
+        ),
+        _ => {
+            return Err(syn::Error::new(
+                sig.span(),
+                "only the methods taking `&self`, `&mut self`, `Rc<Self>` or `Arc<Self>` \
+                can be memoized",
+            ))
+        }
+    };
+
+    method.block = syn::parse_quote! {{
+        let __key = #key;
+        if let ::core::option::Option::Some(__value) =
+            ::dep_inj::memoize::Cache::get(&self.#cache, &__key)
+        {
+            return __value;
+        }
+        #prelude
+        let __value: #output = #body;
+        ::dep_inj::memoize::Cache::insert(
+            &#this.#cache,
+            __key,
+            ::core::clone::Clone::clone(&__value),
+        );
+        __value
+    }};
+
+    Ok(quote!(#method))
+}
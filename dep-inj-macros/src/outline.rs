@@ -0,0 +1,271 @@
+use crate::{mentions, receiver_kind};
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote, ToTokens};
+use syn::{parse_quote, spanned::Spanned};
+
+pub(crate) fn outline_impl(
+    dyn_deps: Option<syn::Type>,
+    mut item_impl: syn::ItemImpl,
+) -> syn::Result<TokenStream> {
+    // `EvenProxy<Ctx>`
+    let syn::Type::Path(self_ty) = &*item_impl.self_ty else {
+        return Err(syn::Error::new(
+            item_impl.self_ty.span(),
+            "`outline` expects the self type to be a proxy like `Proxy<Deps>`",
+        ));
+    };
+    let proxy_path = self_ty.path.clone();
+    // `Ctx`
+    let deps_ident = deps_ident(&proxy_path)?;
+    let deps_param = item_impl
+        .generics
+        .type_params()
+        .find(|param| param.ident == deps_ident)
+        .ok_or_else(|| {
+            syn::Error::new(
+                self_ty.span(),
+                format!("`{deps_ident}` should be a generic parameter of the impl"),
+            )
+        })?;
+    // `dyn EvenDeps`
+    let dyn_deps = match dyn_deps {
+        Some(dyn_deps) => dyn_deps,
+        None => infer_dyn_deps(&item_impl.generics, deps_param)?,
+    };
+
+    // the other generics of the impl, `impl<T> FooProxy<T, dyn FooDeps>`
+    let mut dyn_generics = dyn_generics(&item_impl.generics, &deps_ident);
+    // `EvenProxy<dyn EvenDeps + 'a>` for the outlined bodies,
+    // and `EvenProxy<dyn EvenDeps + '_>` for the shims,
+    // so that the deps are not required to be `'static`
+    let (dyn_proxy, shim_dyn_proxy) = match &dyn_deps {
+        syn::Type::TraitObject(trait_object)
+            if !trait_object
+                .bounds
+                .iter()
+                .any(|bound| matches!(bound, syn::TypeParamBound::Lifetime(_))) =>
+        {
+            dyn_generics.params.insert(0, parse_quote!('__dep_inj));
+            let mut dyn_deps = trait_object.clone();
+            dyn_deps.bounds.push(parse_quote!('__dep_inj));
+            let mut shim_dyn_deps = trait_object.clone();
+            shim_dyn_deps.bounds.push(parse_quote!('_));
+            (
+                replace_deps(&proxy_path, syn::Type::TraitObject(dyn_deps)),
+                replace_deps(&proxy_path, syn::Type::TraitObject(shim_dyn_deps)),
+            )
+        }
+        _ => (
+            replace_deps(&proxy_path, dyn_deps.clone()),
+            replace_deps(&proxy_path, dyn_deps),
+        ),
+    };
+
+    let prefix = match &item_impl.trait_ {
+        Some((_, path, _)) => snake_case(&path.segments.last().unwrap().ident.to_string()),
+        None => "inherent".to_string(),
+    };
+
+    let mut outlined = vec![];
+    for item in &mut item_impl.items {
+        let syn::ImplItem::Method(method) = item else {
+            continue;
+        };
+
+        let skip = take_skip_attr(&mut method.attrs)?;
+        let Some(conversion) = outlinable(method, &deps_ident).filter(|_| !skip) else {
+            continue;
+        };
+
+        let outlined_ident = format_ident!("__dep_inj_outlined_{}_{}", prefix, method.sig.ident);
+        let mut outlined_method = method.clone();
+        outlined_method.vis = syn::Visibility::Inherited;
+        outlined_method.sig.ident = outlined_ident.clone();
+        outlined_method
+            .attrs
+            .retain(|attr| attr.path.is_ident("allow"));
+        outlined_method
+            .attrs
+            .insert(0, parse_quote!(#[doc(hidden)]));
+        outlined.push(outlined_method);
+
+        let mut args = vec![];
+        for (i, input) in method.sig.inputs.iter_mut().skip(1).enumerate() {
+            if let syn::FnArg::Typed(input) = input {
+                let arg = format_ident!("__arg{}", i);
+                *input.pat = parse_quote!(#arg);
+                args.push(arg);
+            }
+        }
+        let (inj, prj) = conversion;
+        method.block = parse_quote! {{
+            <#shim_dyn_proxy>::#outlined_ident(<#shim_dyn_proxy>::#inj(self.#prj()) #(, #args)*)
+        }};
+        if !method.attrs.iter().any(|attr| attr.path.is_ident("inline")) {
+            method.attrs.push(parse_quote!(#[inline]));
+        }
+    }
+
+    let (impl_generics, _, where_clause) = dyn_generics.split_for_impl();
+    Ok(quote! {
+        #item_impl
+
+        impl #impl_generics #dyn_proxy #where_clause {
+            #(#outlined)*
+        }
+    })
+}
+
+/// `EvenProxy<Ctx>` -> `EvenProxy<dyn EvenDeps>`
+fn replace_deps(proxy_path: &syn::Path, dyn_deps: syn::Type) -> syn::Type {
+    let mut dyn_proxy_path = proxy_path.clone();
+    if let syn::PathArguments::AngleBracketed(args) =
+        &mut dyn_proxy_path.segments.last_mut().unwrap().arguments
+    {
+        *args.args.last_mut().unwrap() = syn::GenericArgument::Type(dyn_deps);
+    }
+    parse_quote!(#dyn_proxy_path)
+}
+
+/// `IsEven` -> `is_even`
+fn snake_case(ident: &str) -> String {
+    let mut snake = String::new();
+    for (i, ch) in ident.char_indices() {
+        if ch.is_uppercase() {
+            if i != 0 {
+                snake.push('_');
+            }
+            snake.extend(ch.to_lowercase());
+        } else {
+            snake.push(ch);
+        }
+    }
+    snake
+}
+
+/// `Ctx` in `EvenProxy<Ctx>`
+fn deps_ident(proxy_path: &syn::Path) -> syn::Result<syn::Ident> {
+    let last = proxy_path.segments.last().unwrap();
+    if let syn::PathArguments::AngleBracketed(args) = &last.arguments {
+        if let Some(syn::GenericArgument::Type(syn::Type::Path(deps))) = args.args.last() {
+            if let Some(ident) = deps.path.get_ident() {
+                return Ok(ident.clone());
+            }
+        }
+    }
+
+    Err(syn::Error::new(
+        proxy_path.span(),
+        "`outline` expects the self type to be a proxy like `Proxy<Deps>`",
+    ))
+}
+
+/// `impl<Ctx: EvenDeps>` -> `dyn EvenDeps`
+fn infer_dyn_deps(generics: &syn::Generics, deps_param: &syn::TypeParam) -> syn::Result<syn::Type> {
+    let mut bounds = deps_param.bounds.iter().collect::<Vec<_>>();
+    for predicate in generics.where_clause.iter().flat_map(|w| &w.predicates) {
+        if let syn::WherePredicate::Type(predicate) = predicate {
+            if matches!(&predicate.bounded_ty, syn::Type::Path(ty) if ty.path.is_ident(&deps_param.ident))
+            {
+                bounds.extend(predicate.bounds.iter());
+            }
+        }
+    }
+
+    let traits = bounds
+        .into_iter()
+        .filter(|bound| {
+            matches!(bound, syn::TypeParamBound::Trait(bound) if bound.modifier == syn::TraitBoundModifier::None)
+        })
+        .collect::<Vec<_>>();
+
+    match &*traits {
+        [deps] => Ok(parse_quote!(dyn #deps)),
+        _ => Err(syn::Error::new(
+            deps_param.span(),
+            "cannot infer the dyn deps, please specify it like `#[outline(dyn Deps)]`",
+        )),
+    }
+}
+
+/// removes the deps parameter and the predicates mentioning it.
+fn dyn_generics(generics: &syn::Generics, deps_ident: &syn::Ident) -> syn::Generics {
+    let mut generics = generics.clone();
+    generics.params = generics
+        .params
+        .into_iter()
+        .filter(|param| !matches!(param, syn::GenericParam::Type(ty) if ty.ident == *deps_ident))
+        .collect();
+    if let Some(where_clause) = &mut generics.where_clause {
+        where_clause.predicates = where_clause
+            .predicates
+            .iter()
+            .filter(|predicate| !mentions(predicate.to_token_stream(), deps_ident))
+            .cloned()
+            .collect();
+    }
+    generics
+}
+
+/// `#[outline(skip)]` keeps the method generic.
+fn take_skip_attr(attrs: &mut Vec<syn::Attribute>) -> syn::Result<bool> {
+    let mut skip = false;
+    let mut result = Ok(());
+    attrs.retain(|attr| {
+        if !attr.path.is_ident("outline") {
+            return true;
+        }
+        match attr.parse_args::<syn::Ident>() {
+            Ok(ident) if ident == "skip" => skip = true,
+            _ => result = Err(syn::Error::new(attr.span(), "expect `#[outline(skip)]`")),
+        }
+        false
+    });
+    result.map(|_| skip)
+}
+
+/// Returns the `(inj, prj)` conversions of the receiver if the method can be outlined,
+/// or `None` to fall back to full generics.
+fn outlinable(
+    method: &syn::ImplItemMethod,
+    deps_ident: &syn::Ident,
+) -> Option<(syn::Ident, syn::Ident)> {
+    let sig = &method.sig;
+    if sig.asyncness.is_some()
+        || sig.generics.type_params().next().is_some()
+        || sig.generics.const_params().next().is_some()
+    {
+        return None;
+    }
+
+    let self_ident = syn::Ident::new("Self", sig.span());
+    let conversion = match receiver_kind(sig.inputs.first()?)? {
+        "value" => return None,
+        conversion => conversion,
+    };
+
+    // `Self` and the deps would be `Proxy<dyn Deps>` and `dyn Deps` in the outlined body
+    let others = sig
+        .inputs
+        .iter()
+        .skip(1)
+        .map(|input| input.to_token_stream());
+    let output = sig.output.to_token_stream();
+    let impl_ident = syn::Ident::new("impl", sig.span());
+    for tokens in others.chain([output]) {
+        if mentions(tokens.clone(), &self_ident)
+            || mentions(tokens.clone(), deps_ident)
+            || mentions(tokens, &impl_ident)
+        {
+            return None;
+        }
+    }
+    if mentions(method.block.to_token_stream(), deps_ident) {
+        return None;
+    }
+
+    Some((
+        format_ident!("inj_{}", conversion),
+        format_ident!("prj_{}", conversion),
+    ))
+}
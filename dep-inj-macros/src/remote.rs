@@ -0,0 +1,265 @@
+use crate::receiver_kind;
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{parse_quote, spanned::Spanned};
+
+pub(crate) fn remote_impl(
+    client: syn::Ident,
+    item_trait: syn::ItemTrait,
+) -> syn::Result<TokenStream> {
+    if !item_trait.generics.params.is_empty() {
+        return Err(syn::Error::new(
+            item_trait.generics.span(),
+            "generic interfaces cannot be called remotely",
+        ));
+    }
+
+    let mut methods = vec![];
+    for item in &item_trait.items {
+        match item {
+            syn::TraitItem::Method(method) => {
+                if let Some(method) = RemoteMethod::new(method)? {
+                    methods.push(method);
+                }
+            }
+            item => {
+                return Err(syn::Error::new(
+                    item.span(),
+                    "only the methods of an interface can be called remotely",
+                ))
+            }
+        }
+    }
+    if methods.len() > u16::MAX as usize {
+        return Err(syn::Error::new(
+            item_trait.ident.span(),
+            "too many methods to be called remotely",
+        ));
+    }
+
+    let vis = &item_trait.vis;
+    let ident = &item_trait.ident;
+    let doc = format!("A client of a remote [`{ident}`], generated by `#[dep_inj::remote]`.");
+    let client_methods = methods
+        .iter()
+        .enumerate()
+        .map(|(index, method)| method.client_method(index as u16));
+    let server_arms = methods
+        .iter()
+        .enumerate()
+        .map(|(index, method)| method.server_arm(ident, index as u16));
+
+    Ok(quote! {
+        #item_trait
+
+        #[doc = #doc]
+        #[derive(Debug, Clone)]
+        #vis struct #client {
+            client: ::dep_inj::ipc::Client,
+        }
+
+        impl #client {
+            /// Connect to the server listening at `path`.
+            pub fn connect(path: impl ::core::convert::AsRef<::std::path::Path>) -> ::std::io::Result<Self> {
+                ::core::result::Result::Ok(Self {
+                    client: ::dep_inj::ipc::Client::connect(path)?,
+                })
+            }
+
+            pub fn new(client: ::dep_inj::ipc::Client) -> Self {
+                Self { client }
+            }
+
+            /// Serve the clients connecting to `listener` by `target`, one connection at a time.
+            pub fn serve_sequential<__T: #ident>(
+                listener: &::std::os::unix::net::UnixListener,
+                target: __T,
+            ) -> ::std::io::Result<()> {
+                ::dep_inj::ipc::serve_sequential(listener, target, Self::dispatch::<__T>)
+            }
+
+            /// Dispatch a request to `target`, returning the encoded result.
+            #[allow(unused_mut, unused_variables)]
+            pub fn dispatch<__T: #ident>(
+                target: &mut ::std::sync::Arc<__T>,
+                method: u16,
+                mut input: &[u8],
+            ) -> ::core::result::Result<::std::vec::Vec<u8>, ::dep_inj::ipc::IpcError> {
+                let mut __output = ::std::vec::Vec::new();
+                match method {
+                    #(#server_arms)*
+                    _ => {
+                        return ::core::result::Result::Err(
+                            ::dep_inj::ipc::IpcError::UnknownMethod(method),
+                        )
+                    }
+                }
+                ::core::result::Result::Ok(__output)
+            }
+        }
+
+        impl #ident for #client {
+            #(#client_methods)*
+        }
+    })
+}
+
+/// A method of the interface called remotely.
+struct RemoteMethod {
+    method: syn::TraitItemMethod,
+    kind: &'static str,
+    /// `__arg0`, ..
+    args: Vec<syn::Ident>,
+    /// `T` sent as `T`, or `&T` sent as `T::Owned`
+    arg_tys: Vec<(syn::Type, bool)>,
+}
+
+impl RemoteMethod {
+    /// `None` for the methods without a receiver, left to their default bodies.
+    fn new(method: &syn::TraitItemMethod) -> syn::Result<Option<Self>> {
+        let mut method = method.clone();
+        let sig = &mut method.sig;
+        let Some(kind) = sig.inputs.first().and_then(receiver_kind) else {
+            return match method.default {
+                Some(_) => Ok(None),
+                None => Err(syn::Error::new(
+                    sig.span(),
+                    "methods without a receiver cannot be called remotely, \
+                    try giving it a default body",
+                )),
+            };
+        };
+        if sig.asyncness.is_some() {
+            return Err(syn::Error::new(
+                sig.asyncness.span(),
+                "async methods cannot be called remotely",
+            ));
+        }
+        if let Some(param) = sig
+            .generics
+            .params
+            .iter()
+            .find(|param| !matches!(param, syn::GenericParam::Lifetime(_)))
+        {
+            return Err(syn::Error::new(
+                param.span(),
+                "generic methods cannot be called remotely",
+            ));
+        }
+
+        if !matches!(kind, "ref" | "ref_mut" | "arc") {
+            return Err(syn::Error::new(
+                sig.inputs[0].span(),
+                "only the methods taking `&self`, `&mut self` or `Arc<Self>` can be called remotely",
+            ));
+        }
+
+        let mut args = vec![];
+        let mut arg_tys = vec![];
+        for (i, input) in sig.inputs.iter_mut().enumerate() {
+            match input {
+                syn::FnArg::Receiver(_) => {}
+                syn::FnArg::Typed(input) if i == 0 => {
+                    if let syn::Pat::Ident(pat) = &mut *input.pat {
+                        pat.mutability = None;
+                    }
+                }
+                syn::FnArg::Typed(input) => {
+                    let ty = match &*input.ty {
+                        syn::Type::Reference(reference) if reference.mutability.is_some() => {
+                            return Err(syn::Error::new(
+                                input.ty.span(),
+                                "`&mut` arguments cannot be sent remotely",
+                            ));
+                        }
+                        syn::Type::Reference(reference) => ((*reference.elem).clone(), true),
+                        syn::Type::ImplTrait(_) => {
+                            return Err(syn::Error::new(
+                                input.ty.span(),
+                                "generic methods cannot be called remotely",
+                            ));
+                        }
+                        ty => (ty.clone(), false),
+                    };
+                    let arg = format_ident!("__arg{}", i - 1);
+                    *input.pat = parse_quote!(#arg);
+                    args.push(arg);
+                    arg_tys.push(ty);
+                }
+            }
+        }
+
+        Ok(Some(Self {
+            method,
+            kind,
+            args,
+            arg_tys,
+        }))
+    }
+
+    /// `fn is_even(self: Arc<Self>, __arg0: u64) -> bool { self.client.call(0, ..) }`
+    fn client_method(&self, index: u16) -> TokenStream {
+        let sig = &self.method.sig;
+        let args = &self.args;
+        let message = format!("remote `{}` failed: {{}}", sig.ident);
+        let cfgs = self
+            .method
+            .attrs
+            .iter()
+            .filter(|attr| attr.path.is_ident("cfg"));
+        quote! {
+            #(#cfgs)*
+            #sig {
+                let mut __args = ::std::vec::Vec::new();
+                #(::dep_inj::ipc::Encode::encode(&#args, &mut __args);)*
+                ::dep_inj::ipc::Client::call(&self.client, #index, &__args)
+                    .unwrap_or_else(|__err| ::core::panic!(#message, __err))
+            }
+        }
+    }
+
+    /// `0 => { let __arg0 = decode(..)?; encode(&<__T as IsEven>::is_even(target.clone(), __arg0)) }`
+    fn server_arm(&self, interface: &syn::Ident, index: u16) -> TokenStream {
+        let method_ident = &self.method.sig.ident;
+        let receiver = match self.kind {
+            "ref" => quote!(&**target),
+            "ref_mut" => quote!(::dep_inj::ipc::target_mut(target)?),
+            "arc" => quote!(::std::sync::Arc::clone(target)),
+            // rejected by `RemoteMethod::new`
+            kind => unreachable!("a `{kind}` receiver cannot be served remotely"),
+        };
+        let args = &self.args;
+        let decodes = self.arg_tys.iter().map(|(ty, borrowed)| match borrowed {
+            true => quote!(<#ty as ::std::borrow::ToOwned>::Owned),
+            false => quote!(#ty),
+        });
+        let passes =
+            self.args
+                .iter()
+                .zip(&self.arg_tys)
+                .map(|(arg, (_, borrowed))| match borrowed {
+                    true => quote!(::core::borrow::Borrow::borrow(&#arg)),
+                    false => quote!(#arg),
+                });
+        let call = quote! {
+            <__T as #interface>::#method_ident(#receiver #(, #passes)*)
+        };
+        let call = match self.method.sig.unsafety {
+            Some(_) => quote!(unsafe { #call }),
+            None => call,
+        };
+        let cfgs = self
+            .method
+            .attrs
+            .iter()
+            .filter(|attr| attr.path.is_ident("cfg"));
+        quote! {
+            #(#cfgs)*
+            #index => {
+                #(let #args = <#decodes as ::dep_inj::ipc::Decode>::decode(&mut input)?;)*
+                let __value = #call;
+                ::dep_inj::ipc::Encode::encode(&__value, &mut __output);
+            }
+        }
+    }
+}
@@ -0,0 +1,315 @@
+use crate::receiver_kind;
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{parse_quote, spanned::Spanned};
+
+pub(crate) fn replayable_impl(
+    driver: syn::Ident,
+    item_trait: syn::ItemTrait,
+) -> syn::Result<TokenStream> {
+    if !item_trait.generics.params.is_empty() {
+        return Err(syn::Error::new(
+            item_trait.generics.span(),
+            "generic interfaces cannot be replayed",
+        ));
+    }
+
+    let mut methods = vec![];
+    for item in &item_trait.items {
+        match item {
+            syn::TraitItem::Method(method) => {
+                if let Some(method) = ReplayMethod::new(method)? {
+                    methods.push(method);
+                }
+            }
+            item => {
+                return Err(syn::Error::new(
+                    item.span(),
+                    "only the methods of an interface can be replayed",
+                ))
+            }
+        }
+    }
+
+    let vis = &item_trait.vis;
+    let ident = &item_trait.ident;
+    let interface = ident.to_string();
+    let doc = format!(
+        "Drives a component under replay with the recorded calls to [`{ident}`], \
+        generated by `#[dep_inj::replayable]`."
+    );
+    let recorded = methods
+        .iter()
+        .map(|method| method.recorded(ident, &interface));
+    let served = methods.iter().map(|method| method.served(&interface));
+    let driven = methods
+        .iter()
+        .filter(|method| method.replayable)
+        .map(|method| method.driven(ident, &interface));
+
+    Ok(quote! {
+        #item_trait
+
+        #[doc = #doc]
+        #vis struct #driver;
+
+        impl<__T: #ident> ::dep_inj::replay::Drive<__T> for #driver {
+            const INTERFACE: &'static str = #interface;
+
+            #[allow(unused_variables)]
+            fn call(
+                target: &mut ::std::sync::Arc<__T>,
+                method: &str,
+                args: &[::dep_inj::snapshot::Value],
+            ) -> ::core::result::Result<::dep_inj::snapshot::Value, ::dep_inj::replay::ReplayError> {
+                match method {
+                    #(#driven)*
+                    _ => ::core::result::Result::Err(::dep_inj::replay::ReplayError::UnknownMethod {
+                        interface: #interface.to_string(),
+                        method: method.to_string(),
+                    }),
+                }
+            }
+        }
+
+        impl<__Inner> #ident for ::dep_inj::replay::Recorded<__Inner>
+        where
+            __Inner: #ident + ::dep_inj::Proxy,
+            __Inner::Container: ::core::convert::AsRef<::dep_inj::replay::Recorder>,
+        {
+            #(#recorded)*
+        }
+
+        impl<__S> #ident for ::dep_inj::replay::Replayer<__S> {
+            #(#served)*
+        }
+    })
+}
+
+/// A method of the interface to be recorded and replayed.
+struct ReplayMethod {
+    /// the signature with the arguments renamed to `__arg0`, ..
+    sig: syn::Signature,
+    cfgs: Vec<syn::Attribute>,
+    kind: &'static str,
+    args: Vec<syn::Ident>,
+    arg_tys: Vec<ArgTy>,
+    /// not generic, and returning no references
+    replayable: bool,
+}
+
+/// How an argument is rebuilt from the recording.
+enum ArgTy {
+    /// `T`, replayed as `T`
+    Owned(syn::Type),
+    /// `&T`, replayed as `T::Owned`
+    Ref(syn::Type),
+    /// `&mut T`, replayed as `T`
+    Mut(syn::Type),
+}
+
+impl ReplayMethod {
+    /// `None` for the methods without a receiver, left to their default bodies.
+    fn new(method: &syn::TraitItemMethod) -> syn::Result<Option<Self>> {
+        let mut sig = method.sig.clone();
+        let Some(kind) = sig.inputs.first().and_then(receiver_kind) else {
+            return match method.default {
+                Some(_) => Ok(None),
+                None => Err(syn::Error::new(
+                    sig.span(),
+                    "methods without a receiver cannot be replayed, try giving it a default body",
+                )),
+            };
+        };
+        if sig.asyncness.is_some() {
+            return Err(syn::Error::new(
+                sig.asyncness.span(),
+                "async methods cannot be replayed",
+            ));
+        }
+
+        let mut replayable = sig
+            .generics
+            .params
+            .iter()
+            .all(|param| matches!(param, syn::GenericParam::Lifetime(_)))
+            && !matches!(&sig.output, syn::ReturnType::Type(_, ty) if matches!(**ty, syn::Type::Reference(_)));
+        let mut args = vec![];
+        let mut arg_tys = vec![];
+        for (i, input) in sig.inputs.iter_mut().enumerate() {
+            match input {
+                // `mut self` would be unused
+                syn::FnArg::Receiver(receiver) if receiver.reference.is_none() => {
+                    receiver.mutability = None;
+                }
+                syn::FnArg::Receiver(_) => {}
+                syn::FnArg::Typed(input) if i == 0 => {
+                    if let syn::Pat::Ident(pat) = &mut *input.pat {
+                        pat.mutability = None;
+                    }
+                }
+                syn::FnArg::Typed(input) => {
+                    let ty = match &*input.ty {
+                        syn::Type::Reference(reference) if reference.mutability.is_some() => {
+                            ArgTy::Mut((*reference.elem).clone())
+                        }
+                        syn::Type::Reference(reference) => ArgTy::Ref((*reference.elem).clone()),
+                        ty => {
+                            if let syn::Type::ImplTrait(_) = ty {
+                                replayable = false;
+                            }
+                            ArgTy::Owned(ty.clone())
+                        }
+                    };
+                    let arg = format_ident!("__arg{}", i - 1);
+                    *input.pat = parse_quote!(#arg);
+                    args.push(arg);
+                    arg_tys.push(ty);
+                }
+            }
+        }
+
+        Ok(Some(Self {
+            sig,
+            cfgs: method
+                .attrs
+                .iter()
+                .filter(|attr| attr.path.is_ident("cfg"))
+                .cloned()
+                .collect(),
+            kind,
+            args,
+            arg_tys,
+            replayable,
+        }))
+    }
+
+    /// `fn is_even(self: Arc<Self>, __arg0: u64) -> bool { record(is_even(self.prj_arc(), __arg0)) }`
+    fn recorded(&self, interface: &syn::Ident, name: &str) -> TokenStream {
+        let Self { sig, cfgs, .. } = self;
+        let method_ident = &sig.ident;
+        let method = method_ident.to_string();
+        let args = &self.args;
+        let prj = match self.kind {
+            "value" => format_ident!("prj"),
+            kind => format_ident!("prj_{}", kind),
+        };
+        let call = quote! {
+            <__Inner as #interface>::#method_ident(Self::#prj(self) #(, #args)*)
+        };
+        let call = match sig.unsafety {
+            Some(_) => quote!(unsafe { #call }),
+            None => call,
+        };
+        if !self.replayable {
+            return quote! {
+                #(#cfgs)*
+                #[inline]
+                #sig {
+                    #call
+                }
+            };
+        }
+        quote! {
+            #(#cfgs)*
+            #sig {
+                let __call = ::dep_inj::replay::Recorder::enter(
+                    &self,
+                    #name,
+                    #method,
+                    ::std::vec![#(::dep_inj::replay::Record::record(&#args)),*],
+                );
+                let __value = #call;
+                __call.finish(::dep_inj::replay::Record::record(&__value));
+                __value
+            }
+        }
+    }
+
+    /// `fn is_even(self: Arc<Self>, __arg0: u64) -> bool { self.serve("IsEven", "is_even", ..) }`
+    fn served(&self, name: &str) -> TokenStream {
+        let Self { sig, cfgs, .. } = self;
+        let method = sig.ident.to_string();
+        let args = &self.args;
+        let body = if self.replayable {
+            quote! {
+                ::dep_inj::replay::Replayer::serve(
+                    &self,
+                    #name,
+                    #method,
+                    ::std::vec![#(::dep_inj::replay::Record::record(&#args)),*],
+                )
+            }
+        } else {
+            quote! {
+                #(let _ = #args;)*
+                ::std::panic::panic_any(::dep_inj::replay::ReplayError::UnknownMethod {
+                    interface: #name.to_string(),
+                    method: #method.to_string(),
+                })
+            }
+        };
+        quote! {
+            #(#cfgs)*
+            #sig {
+                #body
+            }
+        }
+    }
+
+    /// `"is_even" => { let __arg0 = replay_arg(..)?; Ok(record(is_even(target.clone(), __arg0))) }`
+    fn driven(&self, interface: &syn::Ident, name: &str) -> TokenStream {
+        let Self { sig, cfgs, .. } = self;
+        let method_ident = &sig.ident;
+        let method = method_ident.to_string();
+        let receiver = match self.kind {
+            "ref" => quote!(&**target),
+            "ref_mut" => quote! {
+                ::std::sync::Arc::get_mut(target)
+                    .expect("the state under replay is shared by an `Arc<Self>` call")
+            },
+            "arc" => quote!(::std::sync::Arc::clone(target)),
+            kind => {
+                let message = format!("a `{kind}` receiver cannot be replayed");
+                return quote! {
+                    #(#cfgs)*
+                    #method => ::core::panic!(#message),
+                };
+            }
+        };
+        let args = &self.args;
+        let indices = 0..args.len();
+        let replayed = self.arg_tys.iter().map(|ty| match ty {
+            ArgTy::Owned(ty) | ArgTy::Mut(ty) => quote!(#ty),
+            ArgTy::Ref(ty) => quote!(<#ty as ::std::borrow::ToOwned>::Owned),
+        });
+        let passes = args.iter().zip(&self.arg_tys).map(|(arg, ty)| match ty {
+            ArgTy::Owned(_) => quote!(#arg),
+            ArgTy::Ref(_) => quote!(::core::borrow::Borrow::borrow(&#arg)),
+            ArgTy::Mut(_) => quote!(&mut #arg),
+        });
+        let call = quote! {
+            <__T as #interface>::#method_ident(#receiver #(, #passes)*)
+        };
+        let call = match sig.unsafety {
+            Some(_) => quote!(unsafe { #call }),
+            None => call,
+        };
+        quote! {
+            #(#cfgs)*
+            #method => {
+                #(
+                    #[allow(unused_mut)]
+                    let mut #args = ::dep_inj::replay::replay_arg::<#replayed>(
+                        #name,
+                        #method,
+                        args,
+                        #indices,
+                    )?;
+                )*
+                let __value = #call;
+                ::core::result::Result::Ok(::dep_inj::replay::Record::record(&__value))
+            }
+        }
+    }
+}
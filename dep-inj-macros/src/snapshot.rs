@@ -0,0 +1,90 @@
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use syn::spanned::Spanned;
+
+pub(crate) fn derive_snapshot_impl(derive_input: syn::DeriveInput) -> syn::Result<TokenStream> {
+    let syn::Data::Struct(data) = &derive_input.data else {
+        return Err(syn::Error::new(
+            derive_input.ident.span(),
+            "`Snapshot` can only be derived for structs",
+        ));
+    };
+
+    let mut fields = vec![];
+    for (i, field) in data.fields.iter().enumerate() {
+        if skipped(field)? {
+            continue;
+        }
+        let member = match &field.ident {
+            Some(ident) => syn::Member::Named(ident.clone()),
+            None => syn::Member::Unnamed(i.into()),
+        };
+        fields.push((member, &field.ty));
+    }
+    Ok(impl_snapshot(&derive_input, &fields))
+}
+
+/// `#[snapshot(skip)]`
+fn skipped(field: &syn::Field) -> syn::Result<bool> {
+    let mut skip = false;
+    for attr in field
+        .attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("snapshot"))
+    {
+        match attr.parse_args::<syn::Ident>() {
+            Ok(ident) if ident == "skip" => skip = true,
+            _ => return Err(syn::Error::new(attr.span(), "expect `#[snapshot(skip)]`")),
+        }
+    }
+    Ok(skip)
+}
+
+/// `impl Snapshot` capturing the `fields` into a map keyed by their names.
+pub(crate) fn impl_snapshot(
+    derive_input: &syn::DeriveInput,
+    fields: &[(syn::Member, &syn::Type)],
+) -> TokenStream {
+    let ident = &derive_input.ident;
+    let mut generics = derive_input.generics.clone();
+    // bounds the field types mentioning the generics only
+    if generics.type_params().next().is_some() {
+        let where_clause = generics.make_where_clause();
+        for (_, ty) in fields {
+            where_clause
+                .predicates
+                .push(syn::parse_quote!(#ty: ::dep_inj::snapshot::Snapshot));
+        }
+    }
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let members = fields.iter().map(|(member, _)| member).collect::<Vec<_>>();
+    let keys = members
+        .iter()
+        .map(|member| member.to_token_stream().to_string())
+        .collect::<Vec<_>>();
+
+    quote! {
+        impl #impl_generics ::dep_inj::snapshot::Snapshot for #ident #ty_generics #where_clause {
+            fn snapshot(&self) -> ::dep_inj::snapshot::Value {
+                ::dep_inj::snapshot::Value::Map(::std::vec![
+                    #((
+                        ::std::string::String::from(#keys),
+                        ::dep_inj::snapshot::Snapshot::snapshot(&self.#members),
+                    ),)*
+                ])
+            }
+
+            fn restore(
+                &mut self,
+                value: &::dep_inj::snapshot::Value,
+            ) -> ::core::result::Result<(), ::dep_inj::snapshot::SnapshotError> {
+                let __fields = ::dep_inj::snapshot::Value::fields(value)?;
+                #(
+                    ::dep_inj::snapshot::restore_field(__fields, #keys, &mut self.#members)?;
+                )*
+                ::core::result::Result::Ok(())
+            }
+        }
+    }
+}
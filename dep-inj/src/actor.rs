@@ -0,0 +1,361 @@
+//! Components running on their own threads.
+//!
+//! A component marked by `#[component(actor = CounterActor, proxy = CounterProxy, provide(Count))]`
+//! is a `CounterActor<CounterState>` field of the container, a client generated by the container
+//! implementing the provided interfaces. The client sends the calls over a `std::sync::mpsc`
+//! channel to a thread owning the state, where they run one by one on the proxy, so the state
+//! needs no lock to be shared across threads. The client can be cloned and handed out, sharing the
+//! thread.
+//!
+//! The deps of the proxy on the thread are the state, and a [`Handle`] of the container
+//! implementing the other interfaces of the container, so that the proxy can call the other
+//! components. The handle is bound by the first `Arc<Self>` call to the container, or by
+//! `CounterActor::bind`:
+//!
+//! ```
+//! use dep_inj::{Container, DepInj};
+//! use std::sync::Arc;
+//!
+//! #[dep_inj::interface]
+//! pub trait Parity {
+//!     fn is_odd(&self, n: u64) -> bool;
+//! }
+//!
+//! #[dep_inj::interface]
+//! pub trait Count {
+//!     fn incr(&mut self, n: u64) -> usize;
+//!     fn count(self: Arc<Self>) -> usize;
+//! }
+//!
+//! #[derive(Default, DepInj)]
+//! #[target(ParityProxy)]
+//! pub struct ParityState;
+//!
+//! impl<Deps: AsRef<ParityState>> Parity for ParityProxy<Deps> {
+//!     fn is_odd(&self, n: u64) -> bool {
+//!         n % 2 == 1
+//!     }
+//! }
+//!
+//! // counts the odd numbers, asking the parity component
+//! #[derive(Default, DepInj)]
+//! #[target(CounterProxy, depend(Parity))]
+//! pub struct CounterState {
+//!     count: usize,
+//! }
+//!
+//! impl<Deps> Count for CounterProxy<Deps>
+//! where
+//!     Deps: AsRef<CounterState> + AsMut<CounterState> + Parity,
+//! {
+//!     fn incr(&mut self, n: u64) -> usize {
+//!         if self.prj_ref().is_odd(n) {
+//!             self.count += 1;
+//!         }
+//!         self.count
+//!     }
+//!
+//!     fn count(self: Arc<Self>) -> usize {
+//!         self.count
+//!     }
+//! }
+//!
+//! #[derive(Default, Container)]
+//! pub struct GlobalStruct {
+//!     #[component(proxy = ParityProxy, provide(Parity))]
+//!     parity_state: ParityState,
+//!     #[component(actor = CounterActor, proxy = CounterProxy, provide(Count))]
+//!     counter: CounterActor<CounterState>,
+//! }
+//!
+//! let global = Arc::new(GlobalStruct::default());
+//! global.counter.bind(&global);
+//!
+//! // the client implements the interface
+//! let mut counter = global.counter.clone();
+//! assert_eq!(counter.incr(3), 1);
+//! assert_eq!(counter.incr(4), 1);
+//!
+//! let handles = (0..4)
+//!     .map(|_| {
+//!         let global = global.clone();
+//!         std::thread::spawn(move || global.count())
+//!     })
+//!     .collect::<Vec<_>>();
+//! for handle in handles {
+//!     assert_eq!(handle.join().unwrap(), 1);
+//! }
+//! ```
+//!
+//! The arguments and the results of the calls are sent across the threads, so they must be
+//! `Send + 'static`, and only the `&self`, `&mut self` and `Arc<Self>` receivers are forwarded to
+//! the thread. The deps on the thread implement the other interfaces of the container whose
+//! methods all take `&self` or `Arc<Self>`, and whose results cannot borrow the container, so a
+//! proxy depending on an interface with another receiver fails to compile. A panic of a call is
+//! resumed on the caller, and the thread keeps serving the later calls.
+//!
+//! A call back into the actor from its own thread panics rather than deadlocks. So components
+//! calling each other recursively, like `is_odd(n)` calling `is_even(n - 1)` and the other way
+//! around, panic as soon as one of them is an actor: the actor calls the other one through the
+//! container, which calls the actor back on its own thread.
+//!
+//! The actors of a delegated sub-container are bound by `bind` only, to the sub-container.
+
+use crate::config::{ConfigError, Configured, FromConfig, Section};
+use std::{
+    any::Any,
+    fmt::{self, Debug},
+    panic::{catch_unwind, resume_unwind, AssertUnwindSafe},
+    sync::{mpsc, Arc, OnceLock, Weak},
+    thread::{self, JoinHandle, ThreadId},
+};
+
+/// The container of an actor, through which the proxy on its thread calls the other components.
+///
+/// Shared by the client and the deps on the thread, and bound once.
+pub struct Handle<C> {
+    container: Arc<OnceLock<Weak<C>>>,
+}
+
+impl<C> Handle<C> {
+    pub fn new() -> Self {
+        Self {
+            container: Arc::new(OnceLock::new()),
+        }
+    }
+
+    /// Bind the actor to `container`, if it is not bound yet.
+    pub fn bind(&self, container: &Arc<C>) {
+        if self.container.get().is_none() {
+            let _ = self.container.set(Arc::downgrade(container));
+        }
+    }
+
+    /// The container the actor is bound to.
+    ///
+    /// # Panics
+    ///
+    /// If the actor is not bound, or the container is dropped.
+    pub fn get(&self) -> Arc<C> {
+        self.container
+            .get()
+            .expect("the actor is not bound to its container, by `bind` or an `Arc<Self>` call")
+            .upgrade()
+            .expect("the container of the actor is dropped")
+    }
+}
+
+impl<C> Clone for Handle<C> {
+    fn clone(&self) -> Self {
+        Self {
+            container: self.container.clone(),
+        }
+    }
+}
+
+impl<C> Default for Handle<C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C> Debug for Handle<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Handle")
+            .field("bound", &self.container.get().is_some())
+            .finish()
+    }
+}
+
+type Job<D> = Box<dyn FnOnce(&mut Arc<D>) + Send>;
+
+/// The thread owning the deps `D` of a proxy, the state and the [`Handle`] of the container,
+/// shared by the clones of a client.
+pub struct Actor<D> {
+    mailbox: mpsc::Sender<Job<D>>,
+    thread: Arc<Joined>,
+}
+
+/// Joins the thread when the last client is dropped, after the mailboxes are closed.
+struct Joined(Option<JoinHandle<()>>);
+
+impl<D: Send + 'static> Actor<D> {
+    /// Spawn a thread owning `deps`, which stops when the last clone of the actor is dropped.
+    pub fn spawn(deps: D) -> Self {
+        let (mailbox, jobs) = mpsc::channel::<Job<D>>();
+        let thread = thread::spawn(move || {
+            let mut deps = Arc::new(deps);
+            for job in jobs {
+                job(&mut deps);
+            }
+        });
+        Self {
+            mailbox,
+            thread: Arc::new(Joined(Some(thread))),
+        }
+    }
+
+    /// Run `f` on the thread of the actor, and wait for its result.
+    ///
+    /// # Panics
+    ///
+    /// Resumes the panic of `f`, and panics if called from the thread of the actor itself.
+    pub fn call<R: Send + 'static>(&self, f: impl FnOnce(&mut Arc<D>) -> R + Send + 'static) -> R {
+        if self.thread_id() == Some(thread::current().id()) {
+            panic!("the actor is called back from its own thread, which would deadlock");
+        }
+        let (reply, result) = mpsc::sync_channel::<Result<R, Box<dyn Any + Send>>>(1);
+        let job: Job<D> = Box::new(move |deps| {
+            // the caller may have gone by a panic of its own
+            let _ = reply.send(catch_unwind(AssertUnwindSafe(|| f(deps))));
+        });
+        self.mailbox
+            .send(job)
+            .unwrap_or_else(|_| panic!("the thread of the actor is gone"));
+        match result.recv() {
+            Ok(Ok(value)) => value,
+            Ok(Err(panic)) => resume_unwind(panic),
+            Err(_) => panic!("the thread of the actor is gone"),
+        }
+    }
+}
+
+impl<D> Actor<D> {
+    fn thread_id(&self) -> Option<ThreadId> {
+        self.thread.0.as_ref().map(|thread| thread.thread().id())
+    }
+}
+
+/// The `&mut self` receiver of a call run on the thread of an actor.
+#[doc(hidden)]
+pub fn deps_mut<D>(deps: &mut Arc<D>) -> &mut D {
+    Arc::get_mut(deps).expect("the state of the actor is shared by an `Arc<Self>` call")
+}
+
+/// Load the config of the state of an actor, sent to its thread.
+#[doc(hidden)]
+pub fn configure<S, D>(actor: &Actor<D>, section: &Section<'_>) -> Result<(), ConfigError>
+where
+    S: Configured,
+    S::Config: FromConfig + Send + 'static,
+    D: AsMut<S> + Send + 'static,
+{
+    let config = S::Config::from_config(section)?;
+    actor.call(move |deps| deps_mut(deps).as_mut().configure(config));
+    Ok(())
+}
+
+impl<D> Clone for Actor<D> {
+    fn clone(&self) -> Self {
+        Self {
+            mailbox: self.mailbox.clone(),
+            thread: self.thread.clone(),
+        }
+    }
+}
+
+impl<D> Debug for Actor<D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Actor")
+            .field("thread", &self.thread_id())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for Joined {
+    fn drop(&mut self) {
+        // dropped after the mailboxes of all the clients, which stops the thread after the
+        // queued calls
+        if let Some(thread) = self.0.take() {
+            if thread.thread().id() != thread::current().id() {
+                let _ = thread.join();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Container, DepInj};
+    use std::{
+        panic::{catch_unwind, AssertUnwindSafe},
+        sync::Arc,
+    };
+
+    #[crate::interface]
+    pub trait IsOdd {
+        fn is_odd(&self, n: u64) -> bool;
+    }
+
+    #[crate::interface]
+    pub trait IsEven {
+        fn is_even(self: Arc<Self>, n: u64) -> bool;
+        fn calls(&mut self) -> usize;
+    }
+
+    #[derive(Default, DepInj)]
+    #[target(OddProxy)]
+    pub struct OddState;
+
+    impl<Deps: AsRef<OddState>> IsOdd for OddProxy<Deps> {
+        fn is_odd(&self, n: u64) -> bool {
+            n % 2 == 1
+        }
+    }
+
+    #[derive(Default, DepInj)]
+    #[target(EvenProxy, depend(IsOdd))]
+    pub struct EvenState {
+        calls: usize,
+    }
+
+    impl<Deps> IsEven for EvenProxy<Deps>
+    where
+        Deps: AsRef<EvenState> + AsMut<EvenState> + IsOdd + Send + Sync,
+    {
+        fn is_even(self: Arc<Self>, n: u64) -> bool {
+            // the sibling, reached through the container on the thread of the actor
+            !self.prj_ref().is_odd(n)
+        }
+
+        fn calls(&mut self) -> usize {
+            self.calls += 1;
+            self.calls
+        }
+    }
+
+    #[derive(Default, Container)]
+    pub struct GlobalStruct {
+        #[component(proxy = OddProxy, provide(IsOdd))]
+        odd_state: OddState,
+        #[component(actor = EvenActor, proxy = EvenProxy, provide(IsEven))]
+        even_state: EvenActor<EvenState>,
+    }
+
+    #[test]
+    fn sibling_call() {
+        // bound by the `Arc<Self>` call
+        let global = Arc::new(GlobalStruct::default());
+        assert!(global.clone().is_even(4));
+        assert!(!global.clone().is_even(3));
+
+        let mut client = global.even_state.clone();
+        assert_eq!(client.calls(), 1);
+        assert_eq!(client.calls(), 2);
+    }
+
+    #[test]
+    fn unbound() {
+        let global = Arc::new(GlobalStruct::default());
+        let client = Arc::new(global.even_state.clone());
+        // panics on the thread of the actor, resumed on the caller
+        let panic = catch_unwind(AssertUnwindSafe(|| client.clone().is_even(4))).unwrap_err();
+        assert!(panic
+            .downcast_ref::<String>()
+            .is_some_and(|message| message.contains("not bound")));
+
+        // the thread keeps serving the calls
+        global.even_state.bind(&global);
+        assert!(client.clone().is_even(4));
+    }
+}
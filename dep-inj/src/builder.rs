@@ -0,0 +1,79 @@
+//! Building a container whose components are not all `Default`.
+//!
+//! `#[builder(pub struct GlobalBuilder)]` on a container generates the builder and
+//! `GlobalStruct::builder()`. Each `with_pool_state(..)` supplying a component moves the builder to
+//! a new type-state, so `build()` only compiles once every component is supplied. The components
+//! marked by `#[component(default, ..)]` and the fields which are not components, like a
+//! `#[transaction]`, can be supplied as well, or fall back to `Default`:
+//!
+//! ```
+//! use dep_inj::{Container, DepInj};
+//!
+//! #[derive(DepInj)]
+//! #[target(PoolProxy)]
+//! pub struct PoolState {
+//!     url: String,
+//! }
+//!
+//! #[derive(Default, DepInj)]
+//! #[target(CacheProxy)]
+//! pub struct CacheState {
+//!     capacity: usize,
+//! }
+//!
+//! #[derive(Container)]
+//! #[builder(pub struct GlobalBuilder)]
+//! pub struct GlobalStruct {
+//!     #[component(proxy = PoolProxy)]
+//!     pool_state: PoolState,
+//!     #[component(default, proxy = CacheProxy)]
+//!     cache_state: CacheState,
+//! }
+//!
+//! let global = GlobalStruct::builder()
+//!     .with_pool_state(PoolState {
+//!         url: "postgres://localhost/app".to_string(),
+//!     })
+//!     .build();
+//! assert_eq!(global.pool_state.url, "postgres://localhost/app");
+//! assert_eq!(global.cache_state.capacity, 0);
+//! ```
+//!
+//! A missing component fails to compile, naming the field:
+//!
+//! ```compile_fail
+//! # use dep_inj::{Container, DepInj};
+//! # #[derive(DepInj)]
+//! # #[target(PoolProxy)]
+//! # pub struct PoolState {
+//! #     url: String,
+//! # }
+//! # #[derive(Container)]
+//! # #[builder(pub struct GlobalBuilder)]
+//! # pub struct GlobalStruct {
+//! #     #[component(proxy = PoolProxy)]
+//! #     pool_state: PoolState,
+//! # }
+//! // error: the component `pool_state` is not supplied to the builder
+//! let global = GlobalStruct::builder().build();
+//! ```
+
+/// Implemented by the type-state of a supplied component, the component itself.
+///
+/// The type-state of a missing component is a marker named after the field, which does not
+/// implement it.
+#[diagnostic::on_unimplemented(
+    message = "the component `{Self}` is not supplied to the builder",
+    label = "missing `{Self}`",
+    note = "supply it by `with_{Self}(..)`, or fall back to `Default` by `#[component(default, ..)]`"
+)]
+pub trait Supplied<T> {
+    fn supplied(self) -> T;
+}
+
+impl<T> Supplied<T> for T {
+    #[inline]
+    fn supplied(self) -> T {
+        self
+    }
+}
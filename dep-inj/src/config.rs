@@ -0,0 +1,303 @@
+//! Typed configuration of the components.
+//!
+//! A state declares its config by a `#[config]` field of a type implementing [`FromConfig`],
+//! usually derived, which implements [`Configured`] for the state and `fn config(&self)` reaching
+//! the config, also from the proxy. A container deriving `Container` generates
+//! `fn configure(&mut self, source: &Source)` loading the configs of its `#[component(config)]`s,
+//! each from the keys under the name of the component.
+//!
+//! A [`Source`] is layered, the later layers overriding the earlier ones, like a key=value file
+//! overridden by the environment variables:
+//!
+//! ```
+//! use dep_inj::config::Source;
+//! use dep_inj::{Container, DepInj, FromConfig};
+//! use std::time::Duration;
+//!
+//! #[derive(Default, FromConfig)]
+//! pub struct PoolConfig {
+//!     url: String,
+//!     max_connections: usize,
+//!     #[config(default)]
+//!     verbose: bool,
+//!     idle_secs: Option<u64>,
+//! }
+//!
+//! #[derive(Default, DepInj)]
+//! #[target(PoolProxy)]
+//! pub struct PoolState {
+//!     #[config]
+//!     config: PoolConfig,
+//! }
+//!
+//! impl<Deps: AsRef<PoolState>> PoolProxy<Deps> {
+//!     fn idle_timeout(&self) -> Option<Duration> {
+//!         self.config().idle_secs.map(Duration::from_secs)
+//!     }
+//! }
+//!
+//! #[derive(Default, Container)]
+//! pub struct GlobalStruct {
+//!     #[component(config, proxy = PoolProxy)]
+//!     pool_state: PoolState,
+//! }
+//!
+//! let file = "
+//!     pool_state.url = postgres://localhost/app
+//!     pool_state.max_connections = 8
+//! ";
+//! std::env::set_var("APP_POOL_STATE_MAX_CONNECTIONS", "16");
+//! let source = Source::new().with_str(file).unwrap().with_env("APP");
+//!
+//! let mut global = GlobalStruct::default();
+//! global.configure(&source).unwrap();
+//! assert_eq!(global.pool_state.config().url, "postgres://localhost/app");
+//! assert_eq!(global.pool_state.config().max_connections, 16);
+//! assert_eq!(PoolProxy::inj_ref(&global).idle_timeout(), None);
+//!
+//! std::env::set_var("APP_POOL_STATE_MAX_CONNECTIONS", "many");
+//! assert_eq!(
+//!     global.configure(&source).unwrap_err().to_string(),
+//!     "invalid config `pool_state.max_connections`: invalid digit found in string"
+//! );
+//! ```
+
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    fmt::{self, Display, Formatter},
+    fs, io,
+    path::Path,
+    str::FromStr,
+};
+
+/// A config loaded from the keys of a component in a [`Source`], derived by `FromConfig`.
+///
+/// The derive reads each field from the key of its name by `FromStr`, a missing key failing
+/// unless the field is an `Option` or marked by `#[config(default)]`. More validation can be
+/// done by implementing it by hand, failing by [`Section::invalid`]:
+///
+/// ```
+/// use dep_inj::config::{ConfigError, FromConfig, Section, Source};
+///
+/// pub struct QueueConfig {
+///     limit: usize,
+/// }
+///
+/// impl FromConfig for QueueConfig {
+///     fn from_config(section: &Section<'_>) -> Result<Self, ConfigError> {
+///         let limit = section.require("limit")?;
+///         if limit == 0 {
+///             return Err(section.invalid("limit", "must be positive"));
+///         }
+///         Ok(Self { limit })
+///     }
+/// }
+///
+/// let source = Source::new().with_str("queue_state.limit = 0").unwrap();
+/// let err = QueueConfig::from_config(&source.section("queue_state")).err().unwrap();
+/// assert_eq!(err.to_string(), "invalid config `queue_state.limit`: must be positive");
+/// ```
+pub trait FromConfig: Sized {
+    fn from_config(section: &Section<'_>) -> Result<Self, ConfigError>;
+}
+
+/// Implemented for a state by the `#[config]` field, see [`config`](crate::config).
+pub trait Configured {
+    type Config;
+
+    fn config(&self) -> &Self::Config;
+
+    fn configure(&mut self, config: Self::Config);
+}
+
+/// The layers of key-values to load the configs from, the later ones overriding the earlier ones.
+#[derive(Debug, Clone, Default)]
+pub struct Source {
+    layers: Vec<Layer>,
+}
+
+#[derive(Debug, Clone)]
+enum Layer {
+    /// `component.key = value`
+    Values(BTreeMap<String, String>),
+    /// `PREFIX_COMPONENT_KEY`
+    Env(String),
+}
+
+impl Source {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a layer of `component.key = value`s, one per line, skipping the blank lines and the
+    /// comments starting with `#`.
+    pub fn with_str(mut self, text: &str) -> Result<Self, ConfigError> {
+        let mut values = BTreeMap::new();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                return Err(ConfigError::Parse {
+                    line: i + 1,
+                    message: "expect `component.key = value`".to_string(),
+                });
+            };
+            let key = key.trim();
+            if !key.contains('.') {
+                return Err(ConfigError::Parse {
+                    line: i + 1,
+                    message: format!("expect `component.key`, found `{key}`"),
+                });
+            }
+            values.insert(key.to_string(), value.trim().to_string());
+        }
+        self.layers.push(Layer::Values(values));
+        Ok(self)
+    }
+
+    /// Add a layer of the file at `path`, see [`Source::with_str`].
+    pub fn with_file(self, path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        let text = fs::read_to_string(path).map_err(ConfigError::Io)?;
+        self.with_str(&text)
+    }
+
+    /// Add a layer of the environment variables, `component.key` read from
+    /// `PREFIX_COMPONENT_KEY`, the `.` and `-` replaced by `_`.
+    pub fn with_env(mut self, prefix: impl Into<String>) -> Self {
+        self.layers.push(Layer::Env(prefix.into()));
+        self
+    }
+
+    /// Add a layer of `(component.key, value)`s.
+    pub fn with_values<K: Into<String>, V: Into<String>>(
+        mut self,
+        values: impl IntoIterator<Item = (K, V)>,
+    ) -> Self {
+        let values = values
+            .into_iter()
+            .map(|(key, value)| (key.into(), value.into()))
+            .collect();
+        self.layers.push(Layer::Values(values));
+        self
+    }
+
+    /// The value of `component.key` in the last layer having it.
+    pub fn get(&self, component: &str, key: &str) -> Option<String> {
+        self.layers.iter().rev().find_map(|layer| match layer {
+            Layer::Values(values) => values.get(&format!("{component}.{key}")).cloned(),
+            Layer::Env(prefix) => {
+                let name = format!("{prefix}_{component}_{key}")
+                    .replace(['.', '-'], "_")
+                    .to_uppercase();
+                std::env::var(name).ok()
+            }
+        })
+    }
+
+    /// The keys of `component`.
+    pub fn section<'a>(&'a self, component: &'a str) -> Section<'a> {
+        Section {
+            source: self,
+            component,
+        }
+    }
+}
+
+/// The keys of a component in a [`Source`].
+#[derive(Debug, Clone, Copy)]
+pub struct Section<'a> {
+    source: &'a Source,
+    component: &'a str,
+}
+
+impl Section<'_> {
+    pub fn component(&self) -> &str {
+        self.component
+    }
+
+    /// The value of `key` parsed, `None` if missing.
+    pub fn get<T>(&self, key: &str) -> Result<Option<T>, ConfigError>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        match self.source.get(self.component, key) {
+            Some(value) => value
+                .parse()
+                .map(Some)
+                .map_err(|err| self.invalid(key, err)),
+            None => Ok(None),
+        }
+    }
+
+    /// The value of `key` parsed, failing if missing.
+    pub fn require<T>(&self, key: &str) -> Result<T, ConfigError>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        self.get(key)?.ok_or_else(|| ConfigError::Missing {
+            component: self.component.to_string(),
+            key: key.to_string(),
+        })
+    }
+
+    /// An error of the value of `key`.
+    pub fn invalid(&self, key: &str, message: impl Display) -> ConfigError {
+        ConfigError::Invalid {
+            component: self.component.to_string(),
+            key: key.to_string(),
+            message: message.to_string(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(io::Error),
+    /// A malformed line of a key=value file.
+    Parse {
+        line: usize,
+        message: String,
+    },
+    Missing {
+        component: String,
+        key: String,
+    },
+    Invalid {
+        component: String,
+        key: String,
+        message: String,
+    },
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io(err) => write!(f, "failed to read the config: {err}"),
+            ConfigError::Parse { line, message } => {
+                write!(f, "malformed config at line {line}: {message}")
+            }
+            ConfigError::Missing { component, key } => {
+                write!(f, "missing config `{component}.{key}`")
+            }
+            ConfigError::Invalid {
+                component,
+                key,
+                message,
+            } => write!(f, "invalid config `{component}.{key}`: {message}"),
+        }
+    }
+}
+
+impl Error for ConfigError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ConfigError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
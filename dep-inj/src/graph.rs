@@ -0,0 +1,404 @@
+//! Introspecting the dependencies between the components of a container.
+//!
+//! A state declares the interfaces its proxy depends on by `#[target(OddProxy, depend(IsEven))]`,
+//! implementing [`Dependencies`]. A container deriving `Container` generates
+//! `fn dependency_graph() -> Graph`, with a node per component and an edge from a component to
+//! the components providing its deps by `#[component(provide(..))]`, several ones for the
+//! candidates of a `#[hot_swap]` slot.
+//!
+//! Only the declared deps are in the graph: a bound of the impls of a proxy, like `Deps: IsEven`,
+//! which is missing in `depend(..)` is invisible to it, though the container still has to meet it.
+//! The container asserts that it implements the declared ones, see
+//! [`Satisfied`](crate::wiring::Satisfied).
+//!
+//! The graph renders to Graphviz DOT by [`Graph::to_dot`], and to a plain text adjacency list
+//! by `Display`. [`Graph::cycles`] reports the components depending on each other:
+//!
+//! ```
+//! use dep_inj::{Container, DepInj};
+//! use std::sync::Arc;
+//!
+//! #[dep_inj::interface]
+//! pub trait IsOdd {
+//!     fn is_odd(self: Arc<Self>, n: u64) -> bool;
+//! }
+//!
+//! #[dep_inj::interface]
+//! pub trait IsEven {
+//!     fn is_even(self: Arc<Self>, n: u64) -> bool;
+//! }
+//!
+//! #[derive(Default, DepInj)]
+//! #[target(OddProxy, depend(IsEven))]
+//! pub struct OddState;
+//!
+//! impl<Deps: IsEven + AsRef<OddState>> IsOdd for OddProxy<Deps> {
+//!     fn is_odd(self: Arc<Self>, n: u64) -> bool {
+//!         n != 0 && self.prj_arc().is_even(n - 1)
+//!     }
+//! }
+//!
+//! #[derive(Default, DepInj)]
+//! #[target(EvenProxy, depend(IsOdd))]
+//! pub struct EvenState;
+//!
+//! impl<Deps: IsOdd + AsRef<EvenState>> IsEven for EvenProxy<Deps> {
+//!     fn is_even(self: Arc<Self>, n: u64) -> bool {
+//!         n == 0 || self.prj_arc().is_odd(n - 1)
+//!     }
+//! }
+//!
+//! #[derive(Default)]
+//! pub struct Config;
+//!
+//! #[derive(Default, Container)]
+//! pub struct GlobalStruct {
+//!     #[component(proxy = OddProxy, provide(IsOdd))]
+//!     odd_state: OddState,
+//!     #[component(proxy = EvenProxy, provide(IsEven))]
+//!     even_state: EvenState,
+//!     #[component]
+//!     config: Config,
+//! }
+//!
+//! let graph = GlobalStruct::dependency_graph();
+//! assert_eq!(graph.cycles(), [["odd_state", "even_state"]]);
+//! assert_eq!(
+//!     graph.to_string(),
+//!     "odd_state: OddState\n  \
+//!         IsEven -> even_state\n\
+//!     even_state: EvenState\n  \
+//!         IsOdd -> odd_state\n\
+//!     config: Config\n"
+//! );
+//! assert!(graph
+//!     .to_dot()
+//!     .contains("\"odd_state\" -> \"even_state\" [label=\"IsEven\"];"));
+//! ```
+
+use std::fmt::{self, Display, Formatter, Write};
+
+/// The interfaces the proxy of a state depends on, declared by `#[target(Foo, depend(IsBar))]`.
+pub trait Dependencies {
+    const DEPENDENCIES: &'static [&'static str];
+}
+
+/// The dependencies between the components of a container.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Graph {
+    name: &'static str,
+    components: Vec<Component>,
+}
+
+/// A component in a [`Graph`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Component {
+    /// the field of the container
+    pub name: &'static str,
+    pub ty: &'static str,
+    /// the interfaces provided by the component
+    pub provides: Vec<&'static str>,
+    /// the interfaces the component depends on
+    pub depends: Vec<&'static str>,
+}
+
+/// A dep of a component, provided by another one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Edge {
+    pub from: &'static str,
+    pub interface: &'static str,
+    /// `None` if no component provides the interface
+    pub to: Option<&'static str>,
+}
+
+impl Graph {
+    /// An empty graph of the container `name`.
+    pub fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            components: vec![],
+        }
+    }
+
+    pub fn with_component(mut self, component: Component) -> Self {
+        self.components.push(component);
+        self
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// The components in the order of declaration.
+    pub fn components(&self) -> &[Component] {
+        &self.components
+    }
+
+    pub fn component(&self, name: &str) -> Option<&Component> {
+        self.components
+            .iter()
+            .find(|component| component.name == name)
+    }
+
+    /// The components providing `interface`, several ones for a `#[hot_swap]` slot.
+    pub fn providers<'a>(&'a self, interface: &'a str) -> impl Iterator<Item = &'a Component> {
+        self.components
+            .iter()
+            .filter(move |component| component.provides.contains(&interface))
+    }
+
+    /// An edge to each provider of each dep, or to `None` for the deps provided by no component.
+    pub fn edges(&self) -> Vec<Edge> {
+        let mut edges = vec![];
+        for component in &self.components {
+            for &interface in &component.depends {
+                let len = edges.len();
+                edges.extend(self.providers(interface).map(|provider| Edge {
+                    from: component.name,
+                    interface,
+                    to: Some(provider.name),
+                }));
+                if edges.len() == len {
+                    edges.push(Edge {
+                        from: component.name,
+                        interface,
+                        to: None,
+                    });
+                }
+            }
+        }
+        edges
+    }
+
+    /// The deps provided by no component.
+    pub fn missing(&self) -> Vec<Edge> {
+        self.edges()
+            .into_iter()
+            .filter(|edge| edge.to.is_none())
+            .collect()
+    }
+
+    /// The components in `names` and the ones depending on them, directly or not, each after the
+    /// ones it depends on, and in the order of declaration otherwise or within a cycle.
+    pub fn dependents(&self, names: &[&str]) -> Vec<&'static str> {
+        let successors = self.successors();
+        let mut included = self
+            .components
+            .iter()
+            .map(|component| names.contains(&component.name))
+            .collect::<Vec<_>>();
+        // the dependents of the included ones until a fixpoint
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for node in 0..self.components.len() {
+                if !included[node] && successors[node].iter().any(|&to| included[to]) {
+                    included[node] = true;
+                    changed = true;
+                }
+            }
+        }
+
+        let mut ordered = vec![];
+        let mut done = vec![false; self.components.len()];
+        while let Some(next) = (0..self.components.len())
+            .filter(|&node| included[node] && !done[node])
+            .find(|&node| {
+                successors[node]
+                    .iter()
+                    .all(|&to| to == node || !included[to] || done[to])
+            })
+            // a cycle, broken at its first component
+            .or_else(|| (0..self.components.len()).find(|&node| included[node] && !done[node]))
+        {
+            done[next] = true;
+            ordered.push(self.components[next].name);
+        }
+        ordered
+    }
+
+    /// The indices of the providers of the deps of each component.
+    fn successors(&self) -> Vec<Vec<usize>> {
+        let index = |name| {
+            self.components
+                .iter()
+                .position(|component| component.name == name)
+                .unwrap()
+        };
+        let mut successors = vec![vec![]; self.components.len()];
+        for edge in self.edges() {
+            if let Some(to) = edge.to {
+                successors[index(edge.from)].push(index(to));
+            }
+        }
+        successors
+    }
+
+    /// The groups of components depending on each other, directly or not, each in the order of
+    /// declaration, including a component depending on itself.
+    pub fn cycles(&self) -> Vec<Vec<&'static str>> {
+        let successors = self.successors();
+
+        let mut tarjan = Tarjan {
+            successors: &successors,
+            indices: vec![None; self.components.len()],
+            low_links: vec![0; self.components.len()],
+            stack: vec![],
+            on_stack: vec![false; self.components.len()],
+            next: 0,
+            components: vec![],
+        };
+        for node in 0..self.components.len() {
+            if tarjan.indices[node].is_none() {
+                tarjan.connect(node);
+            }
+        }
+
+        let mut cycles = tarjan
+            .components
+            .into_iter()
+            .filter(|nodes| nodes.len() > 1 || successors[nodes[0]].contains(&nodes[0]))
+            .map(|mut nodes| {
+                nodes.sort_unstable();
+                nodes
+            })
+            .collect::<Vec<_>>();
+        cycles.sort_unstable();
+        cycles
+            .into_iter()
+            .map(|nodes| {
+                nodes
+                    .into_iter()
+                    .map(|node| self.components[node].name)
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Render to Graphviz DOT, the components being nodes and the deps being edges labelled by
+    /// the interfaces, the deps provided by no component pointing to dashed interface nodes.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::new();
+        let _ = writeln!(dot, "digraph {} {{", Quoted(self.name));
+        for component in &self.components {
+            let label = format!("{}: {}", component.name, component.ty);
+            let _ = writeln!(
+                dot,
+                "    {} [label={}];",
+                Quoted(component.name),
+                Quoted(&label)
+            );
+        }
+        for edge in self.edges() {
+            match edge.to {
+                Some(to) => {
+                    let _ = writeln!(
+                        dot,
+                        "    {} -> {} [label={}];",
+                        Quoted(edge.from),
+                        Quoted(to),
+                        Quoted(edge.interface)
+                    );
+                }
+                None => {
+                    let _ = writeln!(
+                        dot,
+                        "    {} [shape=box, style=dashed];",
+                        Quoted(edge.interface)
+                    );
+                    let _ = writeln!(
+                        dot,
+                        "    {} -> {} [style=dashed];",
+                        Quoted(edge.from),
+                        Quoted(edge.interface)
+                    );
+                }
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+/// The adjacency list, a line per component followed by a line per dep:
+///
+/// ```text
+/// odd_state: OddState
+///   IsEven -> even_state
+/// ```
+///
+/// with `IsEven -> ?` for a dep provided by no component.
+impl Display for Graph {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let edges = self.edges();
+        for component in &self.components {
+            writeln!(f, "{}: {}", component.name, component.ty)?;
+            for edge in edges.iter().filter(|edge| edge.from == component.name) {
+                writeln!(f, "  {} -> {}", edge.interface, edge.to.unwrap_or("?"))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A quoted DOT id.
+struct Quoted<'a>(&'a str);
+
+impl Display for Quoted<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_char('"')?;
+        for c in self.0.chars() {
+            if c == '"' || c == '\\' {
+                f.write_char('\\')?;
+            }
+            f.write_char(c)?;
+        }
+        f.write_char('"')
+    }
+}
+
+/// Tarjan's strongly connected components.
+struct Tarjan<'a> {
+    successors: &'a [Vec<usize>],
+    indices: Vec<Option<usize>>,
+    low_links: Vec<usize>,
+    stack: Vec<usize>,
+    on_stack: Vec<bool>,
+    next: usize,
+    components: Vec<Vec<usize>>,
+}
+
+impl Tarjan<'_> {
+    fn connect(&mut self, node: usize) {
+        self.indices[node] = Some(self.next);
+        self.low_links[node] = self.next;
+        self.next += 1;
+        self.stack.push(node);
+        self.on_stack[node] = true;
+
+        for &successor in &self.successors[node] {
+            match self.indices[successor] {
+                None => {
+                    self.connect(successor);
+                    self.low_links[node] = self.low_links[node].min(self.low_links[successor]);
+                }
+                Some(index) if self.on_stack[successor] => {
+                    self.low_links[node] = self.low_links[node].min(index);
+                }
+                Some(_) => {}
+            }
+        }
+
+        if Some(self.low_links[node]) == self.indices[node] {
+            let mut component = vec![];
+            while let Some(top) = self.stack.pop() {
+                self.on_stack[top] = false;
+                component.push(top);
+                if top == node {
+                    break;
+                }
+            }
+            self.components.push(component);
+        }
+    }
+}
@@ -0,0 +1,246 @@
+//! Checking the health of the components of a container.
+//!
+//! A proxy implements [`HealthCheck`] with access to its deps, and the component opts in by
+//! `#[component(health, proxy = ConnProxy)]`. The container deriving `Container` generates
+//! `fn health(&self) -> HealthReport`, which checks the components in the order of declaration:
+//!
+//! * a component is [`Status::Unhealthy`] if its check says so, panics, or it is poisoned
+//!   (see [`isolation`](crate::isolation)),
+//! * a component is [`Status::Degraded`] if its check says so, or one of the components providing
+//!   its deps is not healthy, directly or not, following the
+//!   [`dependency_graph`](crate::graph) of the container,
+//! * the components without a check are taken as healthy.
+//!
+//! ```
+//! use dep_inj::health::{Health, HealthCheck, Status};
+//! use dep_inj::{Container, DepInj};
+//!
+//! #[dep_inj::interface]
+//! pub trait Query {
+//!     fn query(&self, sql: &str) -> usize;
+//! }
+//!
+//! #[derive(Default, DepInj)]
+//! #[target(ConnProxy)]
+//! pub struct ConnState {
+//!     closed: bool,
+//! }
+//!
+//! impl<Deps: AsRef<ConnState>> Query for ConnProxy<Deps> {
+//!     fn query(&self, sql: &str) -> usize {
+//!         sql.len()
+//!     }
+//! }
+//!
+//! impl<Deps: AsRef<ConnState>> HealthCheck for ConnProxy<Deps> {
+//!     fn check(&self) -> Health {
+//!         match self.closed {
+//!             true => Health::Unhealthy("the connection is closed".to_string()),
+//!             false => Health::Healthy,
+//!         }
+//!     }
+//! }
+//!
+//! #[derive(Default, DepInj)]
+//! #[target(ReportProxy, depend(Query))]
+//! pub struct ReportState {
+//!     queued: usize,
+//! }
+//!
+//! impl<Deps: Query + AsRef<ReportState>> HealthCheck for ReportProxy<Deps> {
+//!     fn check(&self) -> Health {
+//!         match self.queued {
+//!             0..=100 => Health::Healthy,
+//!             _ => Health::Degraded(format!("{} reports queued", self.queued)),
+//!         }
+//!     }
+//! }
+//!
+//! #[derive(Default, Container)]
+//! pub struct GlobalStruct {
+//!     #[component(health, proxy = ConnProxy, provide(Query))]
+//!     conn_state: ConnState,
+//!     #[component(health, proxy = ReportProxy)]
+//!     report_state: ReportState,
+//! }
+//!
+//! let mut global = GlobalStruct::default();
+//! assert_eq!(global.health().status(), Status::Healthy);
+//!
+//! global.conn_state.closed = true;
+//! let report = global.health();
+//! assert_eq!(report.status(), Status::Unhealthy);
+//! assert_eq!(report.component("report_state").unwrap().status, Status::Degraded);
+//! assert_eq!(
+//!     report.to_string(),
+//!     "conn_state: unhealthy, the connection is closed\n\
+//!     report_state: degraded, depends on `Query` of `conn_state`\n"
+//! );
+//! ```
+
+use crate::{graph::Graph, isolation::panic_message};
+use std::{
+    fmt::{self, Display, Formatter},
+    panic::{catch_unwind, UnwindSafe},
+};
+
+/// Implemented by a proxy checking the health of its component.
+pub trait HealthCheck {
+    fn check(&self) -> Health;
+}
+
+/// The health of a component checked by itself.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Health {
+    Healthy,
+    /// working, but not as well as expected
+    Degraded(String),
+    Unhealthy(String),
+}
+
+impl Health {
+    pub fn status(&self) -> Status {
+        match self {
+            Health::Healthy => Status::Healthy,
+            Health::Degraded(_) => Status::Degraded,
+            Health::Unhealthy(_) => Status::Unhealthy,
+        }
+    }
+}
+
+/// The status of a component, ordered from the best to the worst.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Status {
+    Healthy,
+    Degraded,
+    Unhealthy,
+}
+
+impl Display for Status {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Status::Healthy => "healthy",
+            Status::Degraded => "degraded",
+            Status::Unhealthy => "unhealthy",
+        })
+    }
+}
+
+/// The health of a component in a [`HealthReport`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ComponentHealth {
+    /// the field of the container
+    pub name: &'static str,
+    pub status: Status,
+    /// the reasons of the status, the one given by the check first
+    pub reasons: Vec<String>,
+}
+
+/// The health of the components of a container, generated by `fn health()` of the container.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HealthReport {
+    components: Vec<ComponentHealth>,
+}
+
+impl HealthReport {
+    /// Aggregate the health checked by the components of `graph`, one per component in the same
+    /// order, degrading the components depending on the ones not healthy.
+    pub fn aggregate(graph: &Graph, checks: Vec<Health>) -> Self {
+        assert_eq!(graph.components().len(), checks.len());
+        let mut components = graph
+            .components()
+            .iter()
+            .zip(checks)
+            .map(|(component, health)| ComponentHealth {
+                name: component.name,
+                status: health.status(),
+                reasons: match health {
+                    Health::Healthy => vec![],
+                    Health::Degraded(reason) | Health::Unhealthy(reason) => vec![reason],
+                },
+            })
+            .collect::<Vec<_>>();
+
+        // until every component depending on a one not healthy is degraded, through the cycles
+        let edges = graph.edges();
+        loop {
+            let mut changed = false;
+            for edge in &edges {
+                let Some(to) = edge.to else {
+                    continue;
+                };
+                let status = |name| {
+                    components
+                        .iter()
+                        .find(|component: &&ComponentHealth| component.name == name)
+                        .unwrap()
+                        .status
+                };
+                if status(to) == Status::Healthy || status(edge.from) != Status::Healthy {
+                    continue;
+                }
+                let from = components
+                    .iter_mut()
+                    .find(|component| component.name == edge.from)
+                    .unwrap();
+                from.status = Status::Degraded;
+                from.reasons
+                    .push(format!("depends on `{}` of `{to}`", edge.interface));
+                changed = true;
+            }
+            if !changed {
+                break;
+            }
+        }
+        Self { components }
+    }
+
+    /// The worst status of the components.
+    pub fn status(&self) -> Status {
+        self.components
+            .iter()
+            .map(|component| component.status)
+            .max()
+            .unwrap_or(Status::Healthy)
+    }
+
+    pub fn is_healthy(&self) -> bool {
+        self.status() == Status::Healthy
+    }
+
+    /// The components in the order of declaration.
+    pub fn components(&self) -> &[ComponentHealth] {
+        &self.components
+    }
+
+    pub fn component(&self, name: &str) -> Option<&ComponentHealth> {
+        self.components
+            .iter()
+            .find(|component| component.name == name)
+    }
+}
+
+/// A line per component, `name: status, reason, ..`.
+impl Display for HealthReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for component in &self.components {
+            write!(f, "{}: {}", component.name, component.status)?;
+            for reason in &component.reasons {
+                write!(f, ", {reason}")?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// Run a health check, a panic taken as unhealthy.
+#[doc(hidden)]
+pub fn check(f: impl FnOnce() -> Health + UnwindSafe) -> Health {
+    catch_unwind(f).unwrap_or_else(|payload| {
+        Health::Unhealthy(format!(
+            "the health check panicked: {}",
+            panic_message(&*payload)
+        ))
+    })
+}
@@ -0,0 +1,332 @@
+//! Hot-swappable implementations of an interface.
+//!
+//! A [`HotSwap`] slot in a container holds the index of the current implementation among
+//! several candidate components, and the `Container` derive dispatches the methods of the
+//! interface to the current one:
+//!
+//! ```
+//! use dep_inj::hot_swap::HotSwap;
+//! use dep_inj::{Container, DepInj};
+//! use std::sync::Arc;
+//!
+//! #[dep_inj::interface]
+//! pub trait IsEven {
+//!     fn is_even(self: Arc<Self>, n: u64) -> bool;
+//! }
+//!
+//! #[derive(Default, DepInj)]
+//! #[target(EvenProxy)]
+//! pub struct EvenState;
+//!
+//! impl<Deps> IsEven for EvenProxy<Deps> {
+//!     fn is_even(self: Arc<Self>, n: u64) -> bool {
+//!         n % 2 == 0
+//!     }
+//! }
+//!
+//! #[derive(Default, DepInj)]
+//! #[target(BitEvenProxy)]
+//! pub struct BitEvenState;
+//!
+//! impl<Deps> IsEven for BitEvenProxy<Deps> {
+//!     fn is_even(self: Arc<Self>, n: u64) -> bool {
+//!         n & 1 == 0
+//!     }
+//! }
+//!
+//! #[derive(Default, Container)]
+//! pub struct GlobalStruct {
+//!     #[component(proxy = EvenProxy, provide(IsEven))]
+//!     even_state: EvenState,
+//!     #[component(proxy = BitEvenProxy, provide(IsEven))]
+//!     bit_even_state: BitEvenState,
+//!     #[hot_swap(IsEven => [even_state, bit_even_state])]
+//!     even_slot: HotSwap,
+//! }
+//!
+//! let global = Arc::new(GlobalStruct::default());
+//! assert!(global.clone().is_even(42));
+//!
+//! // the calls entered before the switch still finish on `EvenProxy`
+//! let previous = global.even_slot.switch(1);
+//! global.even_slot.wait_idle(previous);
+//! assert!(global.clone().is_even(42));
+//! ```
+//!
+//! The capacity of a slot is the number of its candidates, `HotSwap` is `HotSwap<2>`.
+
+use std::{
+    fmt::{self, Debug},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+/// A slot holding the index of the current implementation among `N` candidates.
+pub struct HotSwap<const N: usize = 2> {
+    // shared with the guards, so that a guard doesn't borrow the container.
+    inner: Arc<Inner<N>>,
+}
+
+struct Inner<const N: usize> {
+    current: AtomicUsize,
+    /// The number of the calls running on each candidate.
+    in_flight: [AtomicUsize; N],
+}
+
+impl<const N: usize> HotSwap<N> {
+    /// The number of the candidates.
+    pub const CAPACITY: usize = N;
+
+    /// A slot using the first candidate.
+    pub fn new() -> Self {
+        Self::with_current(0)
+    }
+
+    /// A slot using the `index`th candidate, panics if it is out of the capacity.
+    pub fn with_current(index: usize) -> Self {
+        assert!(
+            index < N,
+            "the candidate {index} is out of the capacity {N}"
+        );
+        Self {
+            inner: Arc::new(Inner {
+                current: AtomicUsize::new(index),
+                in_flight: std::array::from_fn(|_| AtomicUsize::new(0)),
+            }),
+        }
+    }
+
+    /// The index of the current candidate.
+    pub fn current(&self) -> usize {
+        self.inner.current.load(Ordering::SeqCst)
+    }
+
+    /// Switch to the `index`th candidate and return the previous one,
+    /// panics if it is out of the capacity.
+    ///
+    /// The calls entered before the switch keep running on the previous candidate,
+    /// use [`HotSwap::wait_idle`] to wait for them.
+    pub fn switch(&self, index: usize) -> usize {
+        assert!(
+            index < N,
+            "the candidate {index} is out of the capacity {N}"
+        );
+        self.inner.current.swap(index, Ordering::SeqCst)
+    }
+
+    /// The number of the calls running on the `index`th candidate.
+    pub fn in_flight(&self, index: usize) -> usize {
+        self.inner.in_flight[index].load(Ordering::SeqCst)
+    }
+
+    /// Block until no call is running on the `index`th candidate.
+    ///
+    /// It never returns if the `index`th candidate is still the current one and keeps being
+    /// called, or if it is called from a call running on the `index`th candidate.
+    pub fn wait_idle(&self, index: usize) {
+        while self.in_flight(index) != 0 {
+            std::thread::yield_now();
+        }
+    }
+
+    /// Enter a call on the current candidate, which is tracked until the guard is dropped.
+    pub fn enter(&self) -> HotSwapGuard<N> {
+        let inner = &self.inner;
+        loop {
+            let index = inner.current.load(Ordering::SeqCst);
+            inner.in_flight[index].fetch_add(1, Ordering::SeqCst);
+            // check again, otherwise the call could start on the previous candidate
+            // after `wait_idle` has returned.
+            if inner.current.load(Ordering::SeqCst) == index {
+                return HotSwapGuard {
+                    inner: inner.clone(),
+                    index,
+                };
+            }
+            inner.in_flight[index].fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+}
+
+impl<const N: usize> Default for HotSwap<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> Debug for HotSwap<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let in_flight = (0..N)
+            .map(|index| self.in_flight(index))
+            .collect::<Vec<_>>();
+        f.debug_struct("HotSwap")
+            .field("current", &self.current())
+            .field("in_flight", &in_flight)
+            .finish()
+    }
+}
+
+/// A call running on a candidate of a [`HotSwap`] slot.
+pub struct HotSwapGuard<const N: usize = 2> {
+    inner: Arc<Inner<N>>,
+    index: usize,
+}
+
+impl<const N: usize> HotSwapGuard<N> {
+    /// The index of the candidate the call is running on.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+}
+
+impl<const N: usize> Drop for HotSwapGuard<N> {
+    fn drop(&mut self) {
+        self.inner.in_flight[self.index].fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Container, DepInj};
+    use std::{
+        sync::atomic::AtomicBool,
+        thread::{self, yield_now},
+    };
+
+    #[crate::interface]
+    pub trait Work {
+        fn work(&self);
+    }
+
+    #[derive(Default)]
+    pub struct Calls {
+        running: AtomicUsize,
+        total: AtomicUsize,
+        /// set once the slot has switched away and the calls have drained
+        retired: AtomicBool,
+        late: AtomicUsize,
+    }
+
+    impl Calls {
+        fn run(&self) {
+            if self.retired.load(Ordering::SeqCst) {
+                self.late.fetch_add(1, Ordering::SeqCst);
+            }
+            self.running.fetch_add(1, Ordering::SeqCst);
+            self.total.fetch_add(1, Ordering::SeqCst);
+            yield_now();
+            self.running.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+
+    #[derive(Default, DepInj)]
+    #[target(OldProxy)]
+    pub struct OldState {
+        calls: Calls,
+    }
+
+    impl<Deps: AsRef<OldState>> Work for OldProxy<Deps> {
+        fn work(&self) {
+            self.calls.run();
+        }
+    }
+
+    #[derive(Default, DepInj)]
+    #[target(NewProxy)]
+    pub struct NewState {
+        calls: Calls,
+    }
+
+    impl<Deps: AsRef<NewState>> Work for NewProxy<Deps> {
+        fn work(&self) {
+            self.calls.run();
+        }
+    }
+
+    #[derive(Default, Container)]
+    pub struct GlobalStruct {
+        #[component(proxy = OldProxy, provide(Work))]
+        old_state: OldState,
+        #[component(proxy = NewProxy, provide(Work))]
+        new_state: NewState,
+        #[hot_swap(Work => [old_state, new_state])]
+        work_slot: HotSwap,
+    }
+
+    #[crate::interface]
+    pub trait Take {
+        fn take(self) -> usize;
+    }
+
+    #[derive(Default, Container)]
+    pub struct TakeStruct {
+        #[component(proxy = OldProxy, provide(Take))]
+        old_state: OldState,
+        #[component(proxy = NewProxy, provide(Take))]
+        new_state: NewState,
+        #[hot_swap(Take => [old_state, new_state])]
+        take_slot: HotSwap,
+    }
+
+    // the calls in flight, seen from a call taking the container by value
+    impl Take for OldProxy<TakeStruct> {
+        fn take(self) -> usize {
+            self.prj().take_slot.in_flight(0)
+        }
+    }
+
+    impl Take for NewProxy<TakeStruct> {
+        fn take(self) -> usize {
+            self.prj().take_slot.in_flight(1)
+        }
+    }
+
+    #[test]
+    fn by_value_in_flight() {
+        assert_eq!(TakeStruct::default().take(), 1);
+        let swapped = TakeStruct::default();
+        swapped.take_slot.switch(1);
+        assert_eq!(swapped.take(), 1);
+    }
+
+    #[test]
+    fn wait_idle_concurrently() {
+        let global = Arc::new(GlobalStruct::default());
+        let stop = Arc::new(AtomicBool::new(false));
+        let workers = (0..4)
+            .map(|_| {
+                let (global, stop) = (global.clone(), stop.clone());
+                thread::spawn(move || {
+                    while !stop.load(Ordering::SeqCst) {
+                        global.work();
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let (old, new) = (&global.old_state.calls, &global.new_state.calls);
+        while old.total.load(Ordering::SeqCst) < 100 {
+            yield_now();
+        }
+        let previous = global.work_slot.switch(1);
+        global.work_slot.wait_idle(previous);
+        // no call is left on the previous candidate, and none enters it any more
+        assert_eq!(old.running.load(Ordering::SeqCst), 0);
+        old.retired.store(true, Ordering::SeqCst);
+
+        let total = new.total.load(Ordering::SeqCst);
+        while new.total.load(Ordering::SeqCst) < total + 100 {
+            yield_now();
+        }
+        stop.store(true, Ordering::SeqCst);
+        for worker in workers {
+            worker.join().unwrap();
+        }
+        assert_eq!(old.late.load(Ordering::SeqCst), 0);
+        assert_eq!(global.work_slot.in_flight(0), 0);
+        assert_eq!(global.work_slot.in_flight(1), 0);
+    }
+}
@@ -0,0 +1,625 @@
+//! Calling the interfaces across processes over Unix domain sockets.
+//!
+//! `#[dep_inj::remote(RemoteIsEven)]` on an interface generates a client `RemoteIsEven`
+//! implementing the interface by sending the calls to a server, and
+//! `RemoteIsEven::serve_sequential` driving the real implementation, usually a container, in the
+//! other process. A container can take the client as a component by
+//! `#[component(remote, provide(IsEven))]`, so the components can be moved out of process by the
+//! integrator only:
+//!
+//! ```
+//! use dep_inj::{Container, DepInj};
+//! use std::os::unix::net::UnixListener;
+//! use std::sync::Arc;
+//!
+//! #[dep_inj::remote(RemoteIsEven)]
+//! #[dep_inj::interface]
+//! pub trait IsEven {
+//!     fn is_even(self: Arc<Self>, n: u64) -> bool;
+//!     fn describe(&self, name: &str) -> String;
+//! }
+//!
+//! #[derive(Default, DepInj)]
+//! #[target(EvenProxy)]
+//! pub struct EvenState;
+//!
+//! impl<Deps: AsRef<EvenState>> IsEven for EvenProxy<Deps> {
+//!     fn is_even(self: Arc<Self>, n: u64) -> bool {
+//!         n % 2 == 0
+//!     }
+//!
+//!     fn describe(&self, name: &str) -> String {
+//!         format!("{name} in {}", std::process::id())
+//!     }
+//! }
+//!
+//! // in the server process
+//! #[derive(Default, Container)]
+//! pub struct ServerStruct {
+//!     #[component(proxy = EvenProxy, provide(IsEven))]
+//!     even_state: EvenState,
+//! }
+//!
+//! // in the client process
+//! #[derive(Container)]
+//! pub struct GlobalStruct {
+//!     #[component(remote, provide(IsEven))]
+//!     even: RemoteIsEven,
+//! }
+//!
+//! let path = std::env::temp_dir().join(format!("dep-inj-doc-{}.sock", std::process::id()));
+//! let _ = std::fs::remove_file(&path);
+//! let listener = UnixListener::bind(&path).unwrap();
+//! std::thread::spawn(move || RemoteIsEven::serve_sequential(&listener, ServerStruct::default()));
+//!
+//! let global = Arc::new(GlobalStruct {
+//!     even: RemoteIsEven::connect(&path).unwrap(),
+//! });
+//! assert!(global.clone().is_even(4));
+//! assert!(!global.clone().is_even(7));
+//! assert_eq!(global.describe("even"), format!("even in {}", std::process::id()));
+//! # std::fs::remove_file(&path).unwrap();
+//! ```
+//!
+//! A frame is a little-endian `u32` length followed by the payload, of at most [`MAX_FRAME`]
+//! bytes. A request is the `u16` index of the method followed by the [`Encode`]d arguments, and a
+//! response is a status byte, `0` followed by the [`Encode`]d result, or `1` followed by the error
+//! message. A client panics if a call fails, since the interfaces have no room for the errors of
+//! the transport.
+//!
+//! The server takes the calls of one connection at a time, so that the `&mut self` receivers can
+//! borrow the target as well as the `&self` and `Arc<Self>` ones; another client waits until the
+//! connection before it is closed. A `&mut self` call fails while an `Arc<Self>` call left the
+//! target shared, by a thread spawned by it for example. The arguments and the results are
+//! [`Encode`] and [`Decode`], where `&T` is sent as `T::Owned`. The generic methods, and the ones
+//! taking `self`, `Box<Self>`, `Rc<Self>` or `Pin<..>`, cannot be called remotely.
+
+use std::{
+    any::Any,
+    collections::{BTreeMap, HashMap},
+    error::Error,
+    fmt::{self, Display},
+    hash::Hash,
+    io::{self, Read, Write},
+    os::unix::net::{UnixListener, UnixStream},
+    panic::{catch_unwind, AssertUnwindSafe},
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+/// Writes a value into a frame.
+pub trait Encode {
+    fn encode(&self, buf: &mut Vec<u8>);
+}
+
+/// Reads a value from a frame, advancing the input.
+pub trait Decode: Sized {
+    fn decode(input: &mut &[u8]) -> Result<Self, IpcError>;
+}
+
+/// The failures of a remote call.
+#[derive(Debug)]
+pub enum IpcError {
+    Io(io::Error),
+    /// A malformed frame.
+    Decode(String),
+    /// The method index is not known by the server.
+    UnknownMethod(u16),
+    /// The call failed in the server, or the server panicked.
+    Remote(String),
+}
+
+impl Display for IpcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "{err}"),
+            Self::Decode(message) => write!(f, "malformed frame: {message}"),
+            Self::UnknownMethod(method) => write!(f, "unknown method #{method}"),
+            Self::Remote(message) => write!(f, "{message}"),
+        }
+    }
+}
+
+impl Error for IpcError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for IpcError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+fn take<'a>(input: &mut &'a [u8], len: usize) -> Result<&'a [u8], IpcError> {
+    if input.len() < len {
+        return Err(IpcError::Decode(format!(
+            "expect {len} bytes, found {}",
+            input.len()
+        )));
+    }
+    let (head, rest) = input.split_at(len);
+    *input = rest;
+    Ok(head)
+}
+
+macro_rules! impl_num {
+    ($($ty:ty),*) => {
+        $(
+            impl Encode for $ty {
+                fn encode(&self, buf: &mut Vec<u8>) {
+                    buf.extend_from_slice(&self.to_le_bytes());
+                }
+            }
+
+            impl Decode for $ty {
+                fn decode(input: &mut &[u8]) -> Result<Self, IpcError> {
+                    let bytes = take(input, std::mem::size_of::<$ty>())?;
+                    Ok(<$ty>::from_le_bytes(bytes.try_into().unwrap()))
+                }
+            }
+        )*
+    };
+}
+
+impl_num!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128, f32, f64);
+
+macro_rules! impl_size {
+    ($($ty:ty => $wire:ty),*) => {
+        $(
+            impl Encode for $ty {
+                fn encode(&self, buf: &mut Vec<u8>) {
+                    (*self as $wire).encode(buf);
+                }
+            }
+
+            impl Decode for $ty {
+                fn decode(input: &mut &[u8]) -> Result<Self, IpcError> {
+                    <$ty>::try_from(<$wire>::decode(input)?)
+                        .map_err(|err| IpcError::Decode(err.to_string()))
+                }
+            }
+        )*
+    };
+}
+
+impl_size!(usize => u64, isize => i64);
+
+impl Encode for bool {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        buf.push(*self as u8);
+    }
+}
+
+impl Decode for bool {
+    fn decode(input: &mut &[u8]) -> Result<Self, IpcError> {
+        match u8::decode(input)? {
+            0 => Ok(false),
+            1 => Ok(true),
+            byte => Err(IpcError::Decode(format!("invalid bool {byte}"))),
+        }
+    }
+}
+
+impl Encode for char {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        (*self as u32).encode(buf);
+    }
+}
+
+impl Decode for char {
+    fn decode(input: &mut &[u8]) -> Result<Self, IpcError> {
+        let code = u32::decode(input)?;
+        char::from_u32(code).ok_or_else(|| IpcError::Decode(format!("invalid char {code:#x}")))
+    }
+}
+
+impl Encode for () {
+    fn encode(&self, _: &mut Vec<u8>) {}
+}
+
+impl Decode for () {
+    fn decode(_: &mut &[u8]) -> Result<Self, IpcError> {
+        Ok(())
+    }
+}
+
+impl Encode for str {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        self.as_bytes().encode(buf);
+    }
+}
+
+impl Encode for String {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        self.as_str().encode(buf);
+    }
+}
+
+impl Decode for String {
+    fn decode(input: &mut &[u8]) -> Result<Self, IpcError> {
+        String::from_utf8(Vec::decode(input)?).map_err(|err| IpcError::Decode(err.to_string()))
+    }
+}
+
+impl<T: Encode> Encode for [T] {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        self.len().encode(buf);
+        for item in self {
+            item.encode(buf);
+        }
+    }
+}
+
+impl<T: Encode> Encode for Vec<T> {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        self.as_slice().encode(buf);
+    }
+}
+
+impl<T: Decode> Decode for Vec<T> {
+    fn decode(input: &mut &[u8]) -> Result<Self, IpcError> {
+        let len = usize::decode(input)?;
+        // not trusting the length for the capacity
+        let mut items = Vec::with_capacity(len.min(input.len()));
+        for _ in 0..len {
+            items.push(T::decode(input)?);
+        }
+        Ok(items)
+    }
+}
+
+impl<T: Encode> Encode for Option<T> {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        match self {
+            None => buf.push(0),
+            Some(value) => {
+                buf.push(1);
+                value.encode(buf);
+            }
+        }
+    }
+}
+
+impl<T: Decode> Decode for Option<T> {
+    fn decode(input: &mut &[u8]) -> Result<Self, IpcError> {
+        match bool::decode(input)? {
+            false => Ok(None),
+            true => T::decode(input).map(Some),
+        }
+    }
+}
+
+impl<T: Encode, E: Encode> Encode for Result<T, E> {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        match self {
+            Ok(value) => {
+                buf.push(0);
+                value.encode(buf);
+            }
+            Err(err) => {
+                buf.push(1);
+                err.encode(buf);
+            }
+        }
+    }
+}
+
+impl<T: Decode, E: Decode> Decode for Result<T, E> {
+    fn decode(input: &mut &[u8]) -> Result<Self, IpcError> {
+        match bool::decode(input)? {
+            false => T::decode(input).map(Ok),
+            true => E::decode(input).map(Err),
+        }
+    }
+}
+
+impl<T: Encode + ?Sized> Encode for &T {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        (**self).encode(buf);
+    }
+}
+
+impl<T: Encode + ?Sized> Encode for Box<T> {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        (**self).encode(buf);
+    }
+}
+
+impl<T: Decode> Decode for Box<T> {
+    fn decode(input: &mut &[u8]) -> Result<Self, IpcError> {
+        T::decode(input).map(Box::new)
+    }
+}
+
+impl<K: Encode, V: Encode> Encode for BTreeMap<K, V> {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        self.len().encode(buf);
+        for (key, value) in self {
+            key.encode(buf);
+            value.encode(buf);
+        }
+    }
+}
+
+impl<K: Decode + Ord, V: Decode> Decode for BTreeMap<K, V> {
+    fn decode(input: &mut &[u8]) -> Result<Self, IpcError> {
+        Vec::<(K, V)>::decode(input).map(|entries| entries.into_iter().collect())
+    }
+}
+
+impl<K: Encode, V: Encode, S> Encode for HashMap<K, V, S> {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        self.len().encode(buf);
+        for (key, value) in self {
+            key.encode(buf);
+            value.encode(buf);
+        }
+    }
+}
+
+impl<K: Decode + Eq + Hash, V: Decode> Decode for HashMap<K, V> {
+    fn decode(input: &mut &[u8]) -> Result<Self, IpcError> {
+        Vec::<(K, V)>::decode(input).map(|entries| entries.into_iter().collect())
+    }
+}
+
+macro_rules! impl_tuple {
+    ($(($($ty:ident),+))*) => {
+        $(
+            #[allow(non_snake_case)]
+            impl<$($ty: Encode),+> Encode for ($($ty,)+) {
+                fn encode(&self, buf: &mut Vec<u8>) {
+                    let ($($ty,)+) = self;
+                    $($ty.encode(buf);)+
+                }
+            }
+
+            impl<$($ty: Decode),+> Decode for ($($ty,)+) {
+                fn decode(input: &mut &[u8]) -> Result<Self, IpcError> {
+                    Ok(($($ty::decode(input)?,)+))
+                }
+            }
+        )*
+    };
+}
+
+impl_tuple!((A)(A, B)(A, B, C)(A, B, C, D));
+
+/// The largest payload of a frame, bounding the memory allocated for the length sent by a peer.
+pub const MAX_FRAME: u32 = 64 << 20;
+
+/// Write `payload` as a length-prefixed frame.
+pub fn write_frame(writer: &mut impl Write, payload: &[u8]) -> io::Result<()> {
+    let len = u32::try_from(payload.len())
+        .ok()
+        .filter(|len| *len <= MAX_FRAME)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "the frame is too large"))?;
+    writer.write_all(&len.to_le_bytes())?;
+    writer.write_all(payload)?;
+    writer.flush()
+}
+
+/// Read a length-prefixed frame, `None` if the stream is closed before it.
+pub fn read_frame(reader: &mut impl Read) -> io::Result<Option<Vec<u8>>> {
+    let mut len = [0; 4];
+    if let Err(err) = reader.read_exact(&mut len) {
+        return match err.kind() {
+            io::ErrorKind::UnexpectedEof => Ok(None),
+            _ => Err(err),
+        };
+    }
+    let len = u32::from_le_bytes(len);
+    if len > MAX_FRAME {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("a frame of {len} bytes exceeds the limit of {MAX_FRAME}"),
+        ));
+    }
+    let mut payload = vec![0; len as usize];
+    reader.read_exact(&mut payload)?;
+    Ok(Some(payload))
+}
+
+/// A connection to a server, shared by the clones.
+#[derive(Debug, Clone)]
+pub struct Client {
+    stream: Arc<Mutex<UnixStream>>,
+}
+
+impl Client {
+    pub fn connect(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(Self::new(UnixStream::connect(path)?))
+    }
+
+    pub fn new(stream: UnixStream) -> Self {
+        Self {
+            stream: Arc::new(Mutex::new(stream)),
+        }
+    }
+
+    /// Call the `method`th method with the encoded `args`, and decode the result.
+    pub fn call<R: Decode>(&self, method: u16, args: &[u8]) -> Result<R, IpcError> {
+        let mut request = Vec::with_capacity(2 + args.len());
+        method.encode(&mut request);
+        request.extend_from_slice(args);
+
+        // a poisoned stream may be in the middle of a frame
+        let mut stream = self
+            .stream
+            .lock()
+            .map_err(|_| IpcError::Remote("the connection is poisoned".to_string()))?;
+        write_frame(&mut *stream, &request)?;
+        let response = read_frame(&mut *stream)?.ok_or_else(|| {
+            IpcError::Io(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "the server closed the connection",
+            ))
+        })?;
+        drop(stream);
+
+        let mut input = &response[..];
+        match u8::decode(&mut input)? {
+            0 => R::decode(&mut input),
+            1 => Err(IpcError::Remote(String::decode(&mut input)?)),
+            status => Err(IpcError::Decode(format!("invalid status {status}"))),
+        }
+    }
+}
+
+/// Dispatches a request to the target, generated by `#[dep_inj::remote]`.
+pub type Dispatch<T> = fn(&mut Arc<T>, u16, &[u8]) -> Result<Vec<u8>, IpcError>;
+
+/// Serve the connections to `listener` one at a time, by dispatching the calls to `target`.
+pub fn serve_sequential<T>(
+    listener: &UnixListener,
+    target: T,
+    dispatch: Dispatch<T>,
+) -> io::Result<()> {
+    let mut target = Arc::new(target);
+    for stream in listener.incoming() {
+        // a broken connection doesn't stop the server
+        let _ = serve_connection(stream?, &mut target, dispatch);
+    }
+    Ok(())
+}
+
+/// Serve the calls on `stream` until it is closed.
+pub fn serve_connection<T>(
+    mut stream: UnixStream,
+    target: &mut Arc<T>,
+    dispatch: Dispatch<T>,
+) -> io::Result<()> {
+    while let Some(request) = read_frame(&mut stream)? {
+        let mut input = &request[..];
+        let result = match u16::decode(&mut input) {
+            Ok(method) => catch_unwind(AssertUnwindSafe(|| dispatch(target, method, input)))
+                .unwrap_or_else(|payload| Err(IpcError::Remote(panic_message(&*payload)))),
+            Err(err) => Err(err),
+        };
+
+        let mut response = vec![];
+        match result {
+            Ok(output) => {
+                response.push(0);
+                response.extend_from_slice(&output);
+            }
+            Err(IpcError::Remote(message)) => {
+                response.push(1);
+                message.encode(&mut response);
+            }
+            Err(err) => {
+                response.push(1);
+                err.to_string().encode(&mut response);
+            }
+        }
+        write_frame(&mut stream, &response)?;
+    }
+    Ok(())
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    match payload.downcast_ref::<&str>() {
+        Some(message) => format!("panicked: {message}"),
+        None => match payload.downcast_ref::<String>() {
+            Some(message) => format!("panicked: {message}"),
+            None => "panicked".to_string(),
+        },
+    }
+}
+
+/// The `&mut self` receiver of a served call, failing if an `Arc<Self>` call still shares it.
+#[doc(hidden)]
+pub fn target_mut<T>(target: &mut Arc<T>) -> Result<&mut T, IpcError> {
+    Arc::get_mut(target)
+        .ok_or_else(|| IpcError::Remote("the target is shared by an `Arc<Self>` call".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn frames() {
+        let mut stream = vec![];
+        write_frame(&mut stream, b"even").unwrap();
+        write_frame(&mut stream, b"").unwrap();
+
+        let mut input = &stream[..];
+        assert_eq!(read_frame(&mut input).unwrap().unwrap(), b"even");
+        assert_eq!(read_frame(&mut input).unwrap().unwrap(), b"");
+        // closed between the frames
+        assert!(read_frame(&mut input).unwrap().is_none());
+    }
+
+    #[test]
+    fn frame_errors() {
+        let err = write_frame(&mut vec![], &vec![0; MAX_FRAME as usize + 1]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+
+        // rejected before the payload is allocated
+        let len = (MAX_FRAME + 1).to_le_bytes();
+        let err = read_frame(&mut &len[..]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        // closed in the middle of a frame
+        let mut truncated = 4u32.to_le_bytes().to_vec();
+        truncated.extend_from_slice(b"ev");
+        let err = read_frame(&mut &truncated[..]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    fn dispatch(target: &mut Arc<u64>, method: u16, input: &[u8]) -> Result<Vec<u8>, IpcError> {
+        let mut output = vec![];
+        match method {
+            0 => (**target).encode(&mut output),
+            1 => {
+                *target_mut(target)? += u64::decode(&mut &input[..])?;
+            }
+            2 => panic!("odd"),
+            method => return Err(IpcError::UnknownMethod(method)),
+        }
+        Ok(output)
+    }
+
+    #[test]
+    fn error_frames() {
+        let (client, server) = UnixStream::pair().unwrap();
+        let mut target = Arc::new(1);
+        let shared = target.clone();
+        let server = thread::spawn(move || {
+            serve_connection(server, &mut target, dispatch).unwrap();
+            target
+        });
+        let client = Client::new(client);
+
+        assert_eq!(client.call::<u64>(0, &[]).unwrap(), 1);
+        let mut args = vec![];
+        2u64.encode(&mut args);
+        match client.call::<()>(1, &args) {
+            Err(IpcError::Remote(message)) => assert!(message.contains("shared")),
+            result => panic!("unexpected {result:?}"),
+        }
+        match client.call::<()>(2, &[]) {
+            Err(IpcError::Remote(message)) => assert_eq!(message, "panicked: odd"),
+            result => panic!("unexpected {result:?}"),
+        }
+        match client.call::<()>(7, &[]) {
+            Err(IpcError::Remote(message)) => assert_eq!(message, "unknown method #7"),
+            result => panic!("unexpected {result:?}"),
+        }
+
+        // the connection survives the failed calls
+        drop(shared);
+        client.call::<()>(1, &args).unwrap();
+        assert_eq!(client.call::<u64>(0, &[]).unwrap(), 3);
+
+        drop(client);
+        assert_eq!(*server.join().unwrap(), 3);
+    }
+}
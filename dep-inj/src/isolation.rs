@@ -0,0 +1,306 @@
+//! Panic isolation at the interface boundaries of the components.
+//!
+//! The calls forwarded by a container to a component marked by `#[component(isolate)]` catch
+//! the panics of the component, which poisons it. Then the later calls fail fast until the
+//! component is reset. A caught panic becomes the `Err` of the result of the method, so every
+//! method of the interfaces an isolated component provides returns a `Result` whose error
+//! implements `From<IsolationError>`, or the container fails to compile, naming the method.
+//! The poison is kept in a [`Poison`] field marked by `#[poison]`:
+//!
+//! ```
+//! use dep_inj::isolation::{Isolation, IsolationError, Poison};
+//! use dep_inj::{Container, DepInj};
+//! use std::sync::Mutex;
+//!
+//! #[derive(Debug)]
+//! pub struct DivError(String);
+//!
+//! impl From<IsolationError> for DivError {
+//!     fn from(err: IsolationError) -> Self {
+//!         DivError(err.to_string())
+//!     }
+//! }
+//!
+//! #[dep_inj::interface]
+//! pub trait Div {
+//!     fn div(&self, a: u64, b: u64) -> Result<u64, DivError>;
+//! }
+//!
+//! #[derive(Default, DepInj)]
+//! #[target(DivProxy)]
+//! pub struct DivState {
+//!     count: Mutex<usize>,
+//! }
+//!
+//! impl<Deps: AsRef<DivState>> Div for DivProxy<Deps> {
+//!     fn div(&self, a: u64, b: u64) -> Result<u64, DivError> {
+//!         *self.count.lock().unwrap() += 1;
+//!         let mut count = self.count.lock().unwrap();
+//!         // panics while holding the lock if `b` is 0
+//!         *count += (a / b) as usize;
+//!         Ok(a / b)
+//!     }
+//! }
+//!
+//! #[derive(Default, Container)]
+//! pub struct GlobalStruct {
+//!     #[component(isolate, proxy = DivProxy, provide(Div))]
+//!     div_state: DivState,
+//!     #[poison]
+//!     poison: Poison,
+//! }
+//!
+//! let mut global = GlobalStruct::default();
+//! # std::panic::set_hook(Box::new(|_| {}));
+//! assert_eq!(global.div(6, 3).unwrap(), 2);
+//! assert!(global.div(6, 0).is_err());
+//! // the lock is poisoned, and so is the component
+//! assert_eq!(global.poisoned(), ["div_state"]);
+//! assert!(global.div(6, 3).is_err());
+//!
+//! assert_eq!(global.reset_poisoned(), ["div_state"]);
+//! assert_eq!(global.div(6, 3).unwrap(), 2);
+//! ```
+
+use std::{
+    any::Any,
+    error::Error,
+    fmt::{self, Display},
+    panic::{catch_unwind, UnwindSafe},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+/// The poisoned components of a container, at most 64 of them can be isolated.
+#[derive(Debug, Default)]
+pub struct Poison {
+    // shared with the calls in flight, which may consume the container
+    bits: Arc<AtomicU64>,
+}
+
+impl Poison {
+    /// The maximum number of the components.
+    pub const CAPACITY: usize = 64;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// If the `index`th component is poisoned.
+    pub fn is_poisoned(&self, index: usize) -> bool {
+        self.bits.load(Ordering::SeqCst) & (1 << index) != 0
+    }
+
+    pub fn poison(&self, index: usize) {
+        self.bits.fetch_or(1 << index, Ordering::SeqCst);
+    }
+
+    pub fn clear(&self, index: usize) {
+        self.bits.fetch_and(!(1 << index), Ordering::SeqCst);
+    }
+
+    /// The poison shared with a call in flight.
+    #[doc(hidden)]
+    pub fn share(&self) -> Self {
+        Self {
+            bits: self.bits.clone(),
+        }
+    }
+}
+
+/// Implemented by the containers with a `#[poison]`.
+pub trait Isolation {
+    /// The names of the isolated components, in the order of declaration.
+    fn isolated(&self) -> &'static [&'static str];
+
+    /// If the isolated `component` is poisoned, `false` for the other names.
+    fn is_poisoned(&self, component: &str) -> bool;
+
+    /// Poison the isolated `component`, so that the calls to it fail fast.
+    fn poison(&self, component: &str);
+
+    /// Reset the isolated `component` to `Default` and clear its poison, returning `false` for
+    /// the other names.
+    fn reset(&mut self, component: &str) -> bool;
+
+    /// The names of the poisoned components.
+    fn poisoned(&self) -> Vec<&'static str> {
+        self.isolated()
+            .iter()
+            .copied()
+            .filter(|component| self.is_poisoned(component))
+            .collect()
+    }
+
+    /// Reset the poisoned components to `Default`, returning their names.
+    fn reset_poisoned(&mut self) -> Vec<&'static str> {
+        let poisoned = self.poisoned();
+        for component in &poisoned {
+            self.reset(component);
+        }
+        poisoned
+    }
+}
+
+/// A panic caught at the interface boundary of a component.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IsolationError {
+    /// The component panicked, and has been poisoned.
+    Panicked {
+        component: &'static str,
+        message: String,
+    },
+    /// The component was poisoned by a previous panic.
+    Poisoned { component: &'static str },
+}
+
+impl Display for IsolationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Panicked { component, message } => {
+                write!(f, "component `{component}` panicked: {message}")
+            }
+            Self::Poisoned { component } => write!(f, "component `{component}` is poisoned"),
+        }
+    }
+}
+
+impl Error for IsolationError {}
+
+/// Call `f` on the `index`th component, unless it is poisoned, and poison it if `f` panics.
+pub fn isolate<R>(
+    poison: &Poison,
+    index: usize,
+    component: &'static str,
+    f: impl FnOnce() -> R + UnwindSafe,
+) -> Result<R, IsolationError> {
+    if poison.is_poisoned(index) {
+        return Err(IsolationError::Poisoned { component });
+    }
+    catch_unwind(f).map_err(|payload| {
+        poison.poison(index);
+        IsolationError::Panicked {
+            component,
+            message: panic_message(&*payload),
+        }
+    })
+}
+
+pub(crate) fn panic_message(payload: &(dyn Any + Send)) -> String {
+    match payload.downcast_ref::<&str>() {
+        Some(message) => message.to_string(),
+        None => match payload.downcast_ref::<String>() {
+            Some(message) => message.clone(),
+            None => "Box<dyn Any>".to_string(),
+        },
+    }
+}
+
+/// Turns an [`IsolationError`] into the `Err` of the result of the isolated method `M`.
+#[doc(hidden)]
+#[diagnostic::on_unimplemented(
+    message = "the isolated method `{M}` returns `{Self}`, which cannot carry an `IsolationError`",
+    label = "the method of an isolated component",
+    note = "an isolated method returns a `Result` whose error implements `From<IsolationError>`"
+)]
+pub trait Recover<M> {
+    fn __dep_inj_recover(err: IsolationError) -> Self;
+}
+
+impl<M, T, E: From<IsolationError>> Recover<M> for Result<T, E> {
+    fn __dep_inj_recover(err: IsolationError) -> Self {
+        Err(err.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Container, DepInj};
+
+    #[crate::interface]
+    pub trait Div {
+        fn div(&self, a: u64, b: u64) -> Result<u64, IsolationError>;
+    }
+
+    #[derive(Default, DepInj)]
+    #[target(DivProxy)]
+    pub struct DivState;
+
+    impl<Deps: AsRef<DivState>> Div for DivProxy<Deps> {
+        fn div(&self, a: u64, b: u64) -> Result<u64, IsolationError> {
+            Ok(a / b)
+        }
+    }
+
+    #[derive(Default)]
+    pub struct Filler<const N: usize>;
+
+    macro_rules! wide {
+        ($($field:ident: $n:literal),*) => {
+            #[derive(Default, Container)]
+            pub struct WideStruct {
+                $(
+                    #[component]
+                    $field: Filler<$n>,
+                )*
+                // the 64th component, at the highest bit of the poison
+                #[component(isolate, proxy = DivProxy, provide(Div))]
+                div_state: DivState,
+                #[component]
+                after: Filler<64>,
+                #[poison]
+                poison: Poison,
+            }
+        };
+    }
+
+    wide!(
+            f0: 0, f1: 1, f2: 2, f3: 3, f4: 4, f5: 5, f6: 6, f7: 7,
+            f8: 8, f9: 9, f10: 10, f11: 11, f12: 12, f13: 13, f14: 14, f15: 15,
+            f16: 16, f17: 17, f18: 18, f19: 19, f20: 20, f21: 21, f22: 22, f23: 23,
+            f24: 24, f25: 25, f26: 26, f27: 27, f28: 28, f29: 29, f30: 30, f31: 31,
+            f32: 32, f33: 33, f34: 34, f35: 35, f36: 36, f37: 37, f38: 38, f39: 39,
+            f40: 40, f41: 41, f42: 42, f43: 43, f44: 44, f45: 45, f46: 46, f47: 47,
+            f48: 48, f49: 49, f50: 50, f51: 51, f52: 52, f53: 53, f54: 54, f55: 55,
+            f56: 56, f57: 57, f58: 58, f59: 59, f60: 60, f61: 61, f62: 62
+    );
+
+    #[test]
+    fn highest_bit() {
+        let poison = Poison::new();
+        poison.poison(Poison::CAPACITY - 1);
+        assert!(poison.is_poisoned(Poison::CAPACITY - 1));
+        assert!((0..Poison::CAPACITY - 1).all(|index| !poison.is_poisoned(index)));
+        poison.clear(Poison::CAPACITY - 1);
+        assert!(!poison.is_poisoned(Poison::CAPACITY - 1));
+    }
+
+    #[test]
+    fn wide_container() {
+        assert_eq!(WideStruct::component_metadata().len(), 65);
+        let graph = WideStruct::dependency_graph();
+        assert_eq!(graph.component("div_state").unwrap().provides, ["Div"]);
+
+        let mut wide = WideStruct::default();
+        assert_eq!(wide.div(6, 3), Ok(2));
+        assert!(matches!(
+            wide.div(6, 0),
+            Err(IsolationError::Panicked {
+                component: "div_state",
+                ..
+            })
+        ));
+        assert_eq!(wide.poisoned(), ["div_state"]);
+        assert_eq!(
+            wide.div(6, 3),
+            Err(IsolationError::Poisoned {
+                component: "div_state"
+            })
+        );
+        assert_eq!(wide.reset_poisoned(), ["div_state"]);
+        assert_eq!(wide.div(6, 3), Ok(2));
+    }
+}
@@ -0,0 +1,277 @@
+//! Bounded caches for the methods marked by `#[dep_inj::memoize(cache)]`.
+//!
+//! A cache is a field of the component state, [`SyncCache`] for the proxies shared between
+//! threads and [`LocalCache`] for the single-threaded ones. When a cache is full, the oldest
+//! entry is evicted.
+
+use std::{
+    cell::RefCell,
+    collections::{HashMap, VecDeque},
+    fmt::{self, Debug},
+    hash::Hash,
+    sync::{Mutex, PoisonError},
+};
+
+/// The capacity of a cache created by `Default`.
+pub const DEFAULT_CAPACITY: usize = 1024;
+
+/// Implemented by the caches, through which a memoized method reads and writes its results.
+pub trait Cache<K, V> {
+    /// The value cached for `key`.
+    fn get(&self, key: &K) -> Option<V>;
+
+    /// Cache the value for `key`, evicting the oldest entry if the cache is full.
+    fn insert(&self, key: K, value: V);
+}
+
+struct Entries<K, V> {
+    values: HashMap<K, V>,
+    /// the keys in insertion order, the oldest first
+    order: VecDeque<K>,
+    capacity: usize,
+}
+
+impl<K: Hash + Eq + Clone, V: Clone> Entries<K, V> {
+    fn new(capacity: usize) -> Self {
+        Self {
+            values: HashMap::new(),
+            order: VecDeque::new(),
+            capacity,
+        }
+    }
+
+    fn get(&self, key: &K) -> Option<V> {
+        self.values.get(key).cloned()
+    }
+
+    fn insert(&mut self, key: K, value: V) {
+        if self.capacity == 0 {
+            return;
+        }
+        if let Some(cached) = self.values.get_mut(&key) {
+            *cached = value;
+            return;
+        }
+        if self.values.len() == self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.values.remove(&oldest);
+            }
+        }
+        self.order.push_back(key.clone());
+        self.values.insert(key, value);
+    }
+
+    fn clear(&mut self) {
+        self.values.clear();
+        self.order.clear();
+    }
+}
+
+/// A thread-safe cache holding at most `capacity` entries.
+pub struct SyncCache<K, V> {
+    entries: Mutex<Entries<K, V>>,
+}
+
+impl<K: Hash + Eq + Clone, V: Clone> SyncCache<K, V> {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: Mutex::new(Entries::new(capacity)),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.lock(|entries| entries.capacity)
+    }
+
+    pub fn len(&self) -> usize {
+        self.lock(|entries| entries.values.len())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn clear(&self) {
+        self.lock(Entries::clear)
+    }
+
+    fn lock<R>(&self, f: impl FnOnce(&mut Entries<K, V>) -> R) -> R {
+        // the entries are consistent even if a panic happened while holding the lock
+        let mut entries = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+        f(&mut entries)
+    }
+}
+
+impl<K: Hash + Eq + Clone, V: Clone> Cache<K, V> for SyncCache<K, V> {
+    fn get(&self, key: &K) -> Option<V> {
+        self.lock(|entries| entries.get(key))
+    }
+
+    fn insert(&self, key: K, value: V) {
+        self.lock(|entries| entries.insert(key, value))
+    }
+}
+
+impl<K: Hash + Eq + Clone, V: Clone> Default for SyncCache<K, V> {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY)
+    }
+}
+
+impl<K: Hash + Eq + Clone, V: Clone> Debug for SyncCache<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SyncCache")
+            .field("len", &self.len())
+            .field("capacity", &self.capacity())
+            .finish()
+    }
+}
+
+/// A single-threaded cache holding at most `capacity` entries.
+pub struct LocalCache<K, V> {
+    entries: RefCell<Entries<K, V>>,
+}
+
+impl<K: Hash + Eq + Clone, V: Clone> LocalCache<K, V> {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: RefCell::new(Entries::new(capacity)),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.entries.borrow().capacity
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.borrow().values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn clear(&self) {
+        self.entries.borrow_mut().clear()
+    }
+}
+
+impl<K: Hash + Eq + Clone, V: Clone> Cache<K, V> for LocalCache<K, V> {
+    fn get(&self, key: &K) -> Option<V> {
+        self.entries.borrow().get(key)
+    }
+
+    fn insert(&self, key: K, value: V) {
+        self.entries.borrow_mut().insert(key, value)
+    }
+}
+
+impl<K: Hash + Eq + Clone, V: Clone> Default for LocalCache<K, V> {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY)
+    }
+}
+
+impl<K: Hash + Eq + Clone, V: Clone> Debug for LocalCache<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LocalCache")
+            .field("len", &self.len())
+            .field("capacity", &self.capacity())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DepInj;
+    use std::rc::Rc;
+
+    #[test]
+    fn eviction_order() {
+        let cache = SyncCache::new(2);
+        cache.insert(1, "one");
+        cache.insert(2, "two");
+        cache.insert(3, "three");
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get(&1), None);
+        assert_eq!(cache.get(&2), Some("two"));
+        assert_eq!(cache.get(&3), Some("three"));
+
+        cache.insert(4, "four");
+        assert_eq!(cache.get(&2), None);
+        assert_eq!(cache.get(&3), Some("three"));
+    }
+
+    #[test]
+    fn overwrite() {
+        let cache = SyncCache::new(2);
+        cache.insert(1, "one");
+        cache.insert(2, "two");
+        cache.insert(1, "uno");
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get(&1), Some("uno"));
+
+        // overwriting keeps the key at its place in the order
+        cache.insert(3, "three");
+        assert_eq!(cache.get(&1), None);
+        assert_eq!(cache.get(&2), Some("two"));
+    }
+
+    #[test]
+    fn zero_capacity() {
+        let cache = SyncCache::new(0);
+        cache.insert(1, "one");
+        assert!(cache.is_empty());
+        assert_eq!(cache.get(&1), None);
+
+        let cache = LocalCache::new(0);
+        cache.insert(1, "one");
+        assert!(cache.is_empty());
+    }
+
+    #[derive(Default, DepInj)]
+    #[target(SquareProxy)]
+    pub struct SquareState {
+        cache: LocalCache<u64, u64>,
+        calls: RefCell<usize>,
+    }
+
+    impl<Deps: AsRef<SquareState>> SquareProxy<Deps> {
+        #[crate::memoize(cache)]
+        fn square(self: Rc<Self>, n: u64) -> u64 {
+            *self.calls.borrow_mut() += 1;
+            n * n
+        }
+    }
+
+    #[derive(Default)]
+    struct Global {
+        square_state: SquareState,
+    }
+
+    impl AsRef<SquareState> for Global {
+        fn as_ref(&self) -> &SquareState {
+            &self.square_state
+        }
+    }
+
+    #[test]
+    fn local_cache() {
+        let cache = LocalCache::new(1);
+        cache.insert(1, "one");
+        cache.insert(2, "two");
+        assert_eq!(cache.get(&1), None);
+        assert_eq!(cache.get(&2), Some("two"));
+        cache.clear();
+        assert!(cache.is_empty());
+        assert_eq!(cache.capacity(), 1);
+
+        let global = Rc::new(Global::default());
+        let proxy = || SquareProxy::inj_rc(global.clone());
+        assert_eq!(proxy().square(3), 9);
+        assert_eq!(proxy().square(3), 9);
+        assert_eq!(*global.square_state.calls.borrow(), 1);
+        assert_eq!(global.square_state.cache.len(), 1);
+    }
+}
@@ -0,0 +1,91 @@
+//! Static metadata of the components, for introspection at runtime.
+//!
+//! Every proxy derived by `DepInj` implements [`Describe`], naming its state, itself and the
+//! interfaces declared by `#[target(OddProxy, provide(IsOdd), depend(IsEven))]`. A container
+//! deriving `Container` lists its components by `fn component_metadata()`:
+//!
+//! ```
+//! use dep_inj::metadata::{Describe, Metadata};
+//! use dep_inj::{Container, DepInj};
+//! use std::sync::Arc;
+//!
+//! #[dep_inj::interface]
+//! pub trait IsOdd {
+//!     fn is_odd(self: Arc<Self>, n: u64) -> bool;
+//! }
+//!
+//! #[derive(Default, DepInj)]
+//! #[target(OddProxy, provide(IsOdd))]
+//! pub struct OddState {
+//!     count: u64,
+//! }
+//!
+//! impl<Deps: AsRef<OddState>> IsOdd for OddProxy<Deps> {
+//!     fn is_odd(self: Arc<Self>, n: u64) -> bool {
+//!         n % 2 == 1
+//!     }
+//! }
+//!
+//! #[derive(Default, Container)]
+//! pub struct GlobalStruct {
+//!     #[component(proxy = OddProxy, provide(IsOdd))]
+//!     odd_state: OddState,
+//!     #[component]
+//!     config: Vec<u8>,
+//! }
+//!
+//! assert_eq!(
+//!     <OddProxy<GlobalStruct>>::METADATA,
+//!     Metadata {
+//!         state: "OddState",
+//!         proxy: "OddProxy",
+//!         module: module_path!(),
+//!         layer: false,
+//!         provides: &["IsOdd"],
+//!         depends: &[],
+//!     }
+//! );
+//!
+//! let components = GlobalStruct::component_metadata();
+//! assert_eq!(components[0].name, "odd_state");
+//! assert_eq!(components[0].size, 8);
+//! assert_eq!(components[0].proxy, Some(<OddProxy<GlobalStruct>>::METADATA));
+//! assert_eq!(components[1].ty, "Vec<u8>");
+//! assert_eq!(components[1].proxy, None);
+//! ```
+
+/// Implemented by the proxies derived by `DepInj`.
+pub trait Describe {
+    const METADATA: Metadata;
+}
+
+/// The metadata of a proxy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Metadata {
+    /// the name of the state, like `OddState`
+    pub state: &'static str,
+    /// the name of the proxy, like `OddProxy`
+    pub proxy: &'static str,
+    /// the module defining the state
+    pub module: &'static str,
+    /// a layer wrapping another proxy, `#[target(Foo, layer)]`
+    pub layer: bool,
+    /// the interfaces declared by `#[target(Foo, provide(..))]`
+    pub provides: &'static [&'static str],
+    /// the interfaces declared by `#[target(Foo, depend(..))]`
+    pub depends: &'static [&'static str],
+}
+
+/// The metadata of a component of a container.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ComponentMetadata {
+    /// the field of the container
+    pub name: &'static str,
+    pub ty: &'static str,
+    /// `size_of` the field
+    pub size: usize,
+    /// the interfaces the container forwards to the component
+    pub provides: Vec<&'static str>,
+    /// the metadata of the proxy given by `#[component(proxy = ..)]`
+    pub proxy: Option<Metadata>,
+}
@@ -0,0 +1,87 @@
+//! Structural pinning of the component states.
+//!
+//! The `#[pin]` fields of a state deriving `DepInj` can be projected from a pinned proxy by
+//! `project()`, if the state is structurally pinned in the deps, which is guaranteed by
+//! [`StructuralPin`] and derived by `#[component(pin)]` of the `Container` derive.
+
+/// A container which never moves its `State` while the container is pinned.
+///
+/// # Safety
+///
+/// `as_mut` always returns the same field of the container itself, the container doesn't
+/// implement `Drop` or anything else moving out of the field, and it is `Unpin` only if the
+/// `State` is.
+pub unsafe trait StructuralPin<State: ?Sized>: AsMut<State> {}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Container, DepInj};
+    use std::{
+        marker::{PhantomData, PhantomPinned},
+        pin::Pin,
+    };
+
+    /// `Probe::<T>::UNPIN` is the inherent const if `T: Unpin`, or the one of `NotUnpin`.
+    struct Probe<T>(PhantomData<T>);
+
+    trait NotUnpin {
+        const UNPIN: bool = false;
+    }
+
+    impl<T> NotUnpin for Probe<T> {}
+
+    impl<T: Unpin> Probe<T> {
+        const UNPIN: bool = true;
+    }
+
+    #[derive(Default, DepInj)]
+    #[target(TaskProxy)]
+    pub struct TaskState {
+        #[pin]
+        pinned: PhantomPinned,
+        polls: usize,
+    }
+
+    #[derive(Default, DepInj)]
+    #[target(CountProxy)]
+    pub struct CountState {
+        #[pin]
+        count: usize,
+    }
+
+    #[derive(Default, Container)]
+    pub struct GlobalStruct {
+        #[component(pin)]
+        task_state: TaskState,
+    }
+
+    #[derive(Default, Container)]
+    pub struct UnpinStruct {
+        #[component(pin)]
+        count_state: CountState,
+    }
+
+    #[test]
+    fn project() {
+        let mut global = Box::pin(GlobalStruct::default());
+        for _ in 0..2 {
+            let projection = TaskProxy::inj_pin_ref_mut(global.as_mut()).project();
+            let _: Pin<&mut PhantomPinned> = projection.pinned;
+            *projection.polls += 1;
+        }
+        assert_eq!(global.task_state.polls, 2);
+    }
+
+    // a `!Unpin` pinned field makes the state and the container `!Unpin`
+    const _: () = assert!(!Probe::<TaskState>::UNPIN && !Probe::<GlobalStruct>::UNPIN);
+    // otherwise they stay `Unpin`, which the guards don't prevent
+    const _: () = assert!(Probe::<CountState>::UNPIN && Probe::<UnpinStruct>::UNPIN);
+
+    #[test]
+    fn project_unpin() {
+        let mut unpin = UnpinStruct::default();
+        let projection = CountProxy::inj_pin_ref_mut(Pin::new(&mut unpin)).project();
+        *projection.count.get_mut() += 1;
+        assert_eq!(unpin.count_state.count, 1);
+    }
+}
@@ -0,0 +1,212 @@
+//! A dedicated trait for a container to provide the states, instead of `AsRef`.
+//!
+//! By default a proxy reaches its state by `Deps: AsRef<FooState>`, which is a general purpose
+//! trait a container may implement for other reasons, like `AsRef<Path>`. A proxy derived with
+//! `#[target(Foo, access = Provide)]` reaches it by [`Provide<FooState>`](Provide) and
+//! [`ProvideMut`] instead.
+//!
+//! A container implements both `AsRef` and [`Provide`] for its components, so a proxy of either
+//! kind can be injected with it. A component marked by `#[component(access = Provide)]` is only
+//! provided by [`Provide`], leaving `AsRef` free for the container, and one marked by
+//! `#[component(access = AsRef)]` only by `AsRef`, for the containers implementing [`Provide`] by
+//! hand. The deps written by hand implement [`Provide`] themselves, it is not implied by `AsRef`,
+//! or bridge their `AsRef` and `AsMut` impls to it by [`provide_by_as_ref!`](crate::provide_by_as_ref).
+//!
+//! ```
+//! use dep_inj::provide::Provide;
+//! use dep_inj::{Container, DepInj};
+//! use std::path::{Path, PathBuf};
+//!
+//! #[derive(Default, DepInj)]
+//! #[target(RootProxy, access = Provide)]
+//! pub struct RootState {
+//!     root: PathBuf,
+//! }
+//!
+//! impl<Deps: Provide<RootState>> RootProxy<Deps> {
+//!     fn resolve(&self, file: &str) -> PathBuf {
+//!         self.root.join(file)
+//!     }
+//! }
+//!
+//! #[derive(Default, Container)]
+//! pub struct GlobalStruct {
+//!     #[component(access = Provide, proxy = RootProxy)]
+//!     root_state: RootState,
+//! }
+//!
+//! // the container is free to implement `AsRef` for its own purposes
+//! impl AsRef<Path> for GlobalStruct {
+//!     fn as_ref(&self) -> &Path {
+//!         &self.root_state.root
+//!     }
+//! }
+//!
+//! let mut global = GlobalStruct::default();
+//! global.root_state.root = PathBuf::from("/srv");
+//! assert_eq!(RootProxy::inj_ref(&global).resolve("app.conf"), Path::new("/srv/app.conf"));
+//!
+//! // a container keeping `AsRef` provides the state as well
+//! #[derive(Default, Container)]
+//! pub struct LegacyStruct {
+//!     #[component(proxy = RootProxy)]
+//!     root_state: RootState,
+//! }
+//!
+//! let legacy = LegacyStruct::default();
+//! assert_eq!(RootProxy::inj_ref(&legacy).resolve("app.conf"), Path::new("app.conf"));
+//! let _: &RootState = legacy.as_ref();
+//! ```
+
+/// Borrow a state from a container, like `AsRef<State>`.
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` does not provide `{State}`",
+    note = "a container provides it by `#[component]`, unless marked by `access = AsRef`"
+)]
+pub trait Provide<State: ?Sized> {
+    fn provide(&self) -> &State;
+}
+
+/// Mutably borrow a state from a container, like `AsMut<State>`.
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` does not provide a mutable `{State}`",
+    note = "a container provides it by `#[component]`, unless marked by `access = AsRef`"
+)]
+pub trait ProvideMut<State: ?Sized>: Provide<State> {
+    fn provide_mut(&mut self) -> &mut State;
+}
+
+/// Implement [`Provide`] for a container by its `AsRef` impls, and [`ProvideMut`] by its `AsMut`
+/// impls for the states marked by `mut`, so that the containers written by hand can be injected
+/// into the proxies derived with `access = Provide`.
+///
+/// ```
+/// use dep_inj::DepInj;
+///
+/// #[derive(Default, DepInj)]
+/// #[target(CountProxy, access = Provide)]
+/// pub struct CountState {
+///     count: usize,
+/// }
+///
+/// #[derive(Default)]
+/// pub struct GlobalStruct {
+///     count_state: CountState,
+/// }
+///
+/// impl AsRef<CountState> for GlobalStruct {
+///     fn as_ref(&self) -> &CountState {
+///         &self.count_state
+///     }
+/// }
+///
+/// impl AsMut<CountState> for GlobalStruct {
+///     fn as_mut(&mut self) -> &mut CountState {
+///         &mut self.count_state
+///     }
+/// }
+///
+/// dep_inj::provide_by_as_ref!(GlobalStruct: mut CountState);
+///
+/// let mut global = GlobalStruct::default();
+/// CountProxy::inj_ref_mut(&mut global).count += 1;
+/// assert_eq!(global.count_state.count, 1);
+/// ```
+#[macro_export]
+macro_rules! provide_by_as_ref {
+    ($container:ty: $($states:tt)+) => {
+        $crate::provide_by_as_ref!(@impl $container; $($states)+);
+    };
+    (@impl $container:ty; $(,)?) => {};
+    (@impl $container:ty; mut $state:ty $(, $($rest:tt)*)?) => {
+        $crate::provide_by_as_ref!(@impl $container; $state);
+        impl $crate::provide::ProvideMut<$state> for $container {
+            fn provide_mut(&mut self) -> &mut $state {
+                ::core::convert::AsMut::<$state>::as_mut(self)
+            }
+        }
+        $crate::provide_by_as_ref!(@impl $container; $($($rest)*)?);
+    };
+    (@impl $container:ty; $state:ty $(, $($rest:tt)*)?) => {
+        impl $crate::provide::Provide<$state> for $container {
+            fn provide(&self) -> &$state {
+                ::core::convert::AsRef::<$state>::as_ref(self)
+            }
+        }
+        $crate::provide_by_as_ref!(@impl $container; $($($rest)*)?);
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DepInj;
+    use std::path::{Path, PathBuf};
+
+    #[derive(Default, DepInj)]
+    #[target(RootProxy, access = Provide)]
+    pub struct RootState {
+        root: PathBuf,
+    }
+
+    #[derive(Default, DepInj)]
+    #[target(CountProxy, access = Provide)]
+    pub struct CountState {
+        count: usize,
+    }
+
+    impl<Deps: ProvideMut<CountState> + Provide<RootState>> CountProxy<Deps> {
+        fn resolve(&mut self, file: &str) -> PathBuf {
+            self.count += 1;
+            let root: &RootState = self.prj_ref().provide();
+            root.root.join(file)
+        }
+    }
+
+    // written by hand, with an unrelated `AsRef` impl
+    #[derive(Default)]
+    pub struct GlobalStruct {
+        root_state: RootState,
+        count_state: CountState,
+    }
+
+    impl AsRef<RootState> for GlobalStruct {
+        fn as_ref(&self) -> &RootState {
+            &self.root_state
+        }
+    }
+
+    impl AsRef<CountState> for GlobalStruct {
+        fn as_ref(&self) -> &CountState {
+            &self.count_state
+        }
+    }
+
+    impl AsMut<CountState> for GlobalStruct {
+        fn as_mut(&mut self) -> &mut CountState {
+            &mut self.count_state
+        }
+    }
+
+    impl AsRef<Path> for GlobalStruct {
+        fn as_ref(&self) -> &Path {
+            &self.root_state.root
+        }
+    }
+
+    crate::provide_by_as_ref!(GlobalStruct: RootState, mut CountState,);
+
+    #[test]
+    fn by_as_ref() {
+        let mut global = GlobalStruct::default();
+        global.root_state.root = PathBuf::from("/srv");
+        assert_eq!(
+            CountProxy::inj_ref_mut(&mut global).resolve("app.conf"),
+            Path::new("/srv/app.conf")
+        );
+        assert_eq!(RootProxy::inj_ref(&global).root, Path::new("/srv"));
+        assert_eq!(global.count_state.count, 1);
+        let root: &Path = global.as_ref();
+        assert_eq!(root, Path::new("/srv"));
+    }
+}
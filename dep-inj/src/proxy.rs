@@ -0,0 +1,10 @@
+/// Implemented by the proxies generated by `DepInj`, through which a layer wrapping a proxy
+/// reaches the container at the bottom.
+pub trait Proxy {
+    /// The deps of a proxy, or the container under the wrapped proxy of a layer.
+    type Container: ?Sized;
+
+    fn container(&self) -> &Self::Container;
+
+    fn container_mut(&mut self) -> &mut Self::Container;
+}
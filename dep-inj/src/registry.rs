@@ -0,0 +1,257 @@
+//! A dynamic interface registry, the Method 4 of the design doc.
+//!
+//! Components register their states and the interfaces they provide into a [`GlobalCtxt`],
+//! and query the interfaces provided by other components through `TypeId` at runtime.
+//! It is useful for plugin-style components which are only discovered at runtime.
+//!
+//! Statically wired proxies can be registered as well, since `Arc<Proxy<Container>>` can be
+//! coerced to `Arc<dyn Interface>`. So the runtime discovered components can call the statically
+//! wired ones, and the other way round:
+//!
+//! ```
+//! use dep_inj::registry::GlobalCtxt;
+//! use dep_inj::DepInj;
+//! use std::sync::Arc;
+//!
+//! // provided by a statically wired component
+//! pub trait IsOdd {
+//!     fn is_odd(self: Arc<Self>, n: u64) -> bool;
+//! }
+//!
+//! // provided by a plugin
+//! pub trait IsEven {
+//!     fn is_even(&self, ctxt: &GlobalCtxt, n: u64) -> bool;
+//! }
+//!
+//! #[derive(Default, DepInj)]
+//! #[target(OddProxy)]
+//! pub struct OddState;
+//!
+//! impl<Deps: AsRef<GlobalCtxt>> IsOdd for OddProxy<Deps> {
+//!     fn is_odd(self: Arc<Self>, n: u64) -> bool {
+//!         let ctxt: &GlobalCtxt = self.prj_ref().as_ref();
+//!         // static -> dynamic
+//!         let even = ctxt.interface::<dyn IsEven + Send + Sync>().unwrap();
+//!         n != 0 && even.is_even(ctxt, n - 1)
+//!     }
+//! }
+//!
+//! struct EvenPlugin;
+//!
+//! impl IsEven for EvenPlugin {
+//!     fn is_even(&self, ctxt: &GlobalCtxt, n: u64) -> bool {
+//!         // dynamic -> static
+//!         let odd = ctxt.interface::<dyn IsOdd + Send + Sync>().unwrap();
+//!         n == 0 || odd.is_odd(n - 1)
+//!     }
+//! }
+//!
+//! struct Container {
+//!     ctxt: Arc<GlobalCtxt>,
+//! }
+//!
+//! impl AsRef<GlobalCtxt> for Container {
+//!     fn as_ref(&self) -> &GlobalCtxt {
+//!         &self.ctxt
+//!     }
+//! }
+//!
+//! let ctxt = Arc::new(GlobalCtxt::new());
+//! ctxt.register_interface::<dyn IsEven + Send + Sync>(Arc::new(EvenPlugin))
+//!     .unwrap();
+//!
+//! let container = Arc::new(Container { ctxt: ctxt.clone() });
+//! // the statically wired proxy registers itself
+//! ctxt.register_interface::<dyn IsOdd + Send + Sync>(OddProxy::inj_arc(container.clone()))
+//!     .unwrap();
+//!
+//! assert!(OddProxy::inj_arc(container.clone()).is_odd(7));
+//! assert!(!EvenPlugin.is_even(&ctxt, 7));
+//!
+//! // `container` -> `ctxt` -> `OddProxy<Container>` is a reference cycle
+//! ctxt.clear();
+//! ```
+//!
+//! Note that `dyn IsOdd` and `dyn IsOdd + Send + Sync` are different types,
+//! an interface should be queried by the same type as it was registered.
+
+use std::{
+    any::{type_name, Any, TypeId},
+    collections::HashMap,
+    error::Error,
+    fmt::{self, Debug, Display},
+    sync::{Arc, PoisonError, RwLock},
+};
+
+/// The registry of the components and the interfaces they provide.
+#[derive(Default)]
+pub struct GlobalCtxt {
+    /// All the components will be registered in here.
+    components: RwLock<HashMap<TypeId, Entry>>,
+    /// All the interfaces provided by the components will be registered in here.
+    /// The values are `Arc<Interface>`s.
+    interfaces: RwLock<HashMap<TypeId, Entry>>,
+}
+
+struct Entry {
+    name: &'static str,
+    value: Box<dyn Any + Send + Sync>,
+}
+
+impl GlobalCtxt {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register the state of a component, fails if a component of the same type has been registered.
+    pub fn register_component<Component>(
+        &self,
+        component: Arc<Component>,
+    ) -> Result<(), RegistryError>
+    where
+        Component: Any + Send + Sync,
+    {
+        insert(&self.components, component)
+            .map_err(|name| RegistryError::ComponentRegistered { name })
+    }
+
+    /// Register an interface, which is usually a trait object like `dyn Foo + Send + Sync`.
+    /// Fails if the interface has been registered.
+    pub fn register_interface<Interface>(
+        &self,
+        interface: Arc<Interface>,
+    ) -> Result<(), RegistryError>
+    where
+        Interface: ?Sized + 'static,
+        Arc<Interface>: Send + Sync,
+    {
+        insert(&self.interfaces, interface)
+            .map_err(|name| RegistryError::InterfaceRegistered { name })
+    }
+
+    pub fn get_component<Component>(&self) -> Option<Arc<Component>>
+    where
+        Component: Any + Send + Sync,
+    {
+        get(&self.components)
+    }
+
+    pub fn get_interface<Interface>(&self) -> Option<Arc<Interface>>
+    where
+        Interface: ?Sized + 'static,
+    {
+        get(&self.interfaces)
+    }
+
+    /// Like [`GlobalCtxt::get_component`], but the error tells which component is missing.
+    pub fn component<Component>(&self) -> Result<Arc<Component>, RegistryError>
+    where
+        Component: Any + Send + Sync,
+    {
+        self.get_component()
+            .ok_or(RegistryError::ComponentNotFound {
+                name: type_name::<Component>(),
+            })
+    }
+
+    /// Like [`GlobalCtxt::get_interface`], but the error tells which interface is missing.
+    pub fn interface<Interface>(&self) -> Result<Arc<Interface>, RegistryError>
+    where
+        Interface: ?Sized + 'static,
+    {
+        self.get_interface()
+            .ok_or(RegistryError::InterfaceNotFound {
+                name: type_name::<Interface>(),
+            })
+    }
+
+    /// Unregister all the components and interfaces,
+    /// which breaks the reference cycles between the registry and the registered containers.
+    pub fn clear(&self) {
+        // drop the entries outside the locks, since they may own this registry.
+        let components = std::mem::take(&mut *write(&self.components));
+        let interfaces = std::mem::take(&mut *write(&self.interfaces));
+        drop((components, interfaces));
+    }
+}
+
+impl Debug for GlobalCtxt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn names(map: &RwLock<HashMap<TypeId, Entry>>) -> Vec<&'static str> {
+            map.read()
+                .unwrap_or_else(PoisonError::into_inner)
+                .values()
+                .map(|entry| entry.name)
+                .collect()
+        }
+
+        f.debug_struct("GlobalCtxt")
+            .field("components", &names(&self.components))
+            .field("interfaces", &names(&self.interfaces))
+            .finish()
+    }
+}
+
+fn write(
+    map: &RwLock<HashMap<TypeId, Entry>>,
+) -> std::sync::RwLockWriteGuard<'_, HashMap<TypeId, Entry>> {
+    // the maps are always in a consistent state, so the poison can be ignored.
+    map.write().unwrap_or_else(PoisonError::into_inner)
+}
+
+fn insert<T>(map: &RwLock<HashMap<TypeId, Entry>>, value: Arc<T>) -> Result<(), &'static str>
+where
+    T: ?Sized + 'static,
+    Arc<T>: Send + Sync,
+{
+    let name = type_name::<T>();
+    let mut map = write(map);
+    if map.contains_key(&TypeId::of::<T>()) {
+        return Err(name);
+    }
+
+    map.insert(
+        TypeId::of::<T>(),
+        Entry {
+            name,
+            value: Box::new(value),
+        },
+    );
+    Ok(())
+}
+
+fn get<T>(map: &RwLock<HashMap<TypeId, Entry>>) -> Option<Arc<T>>
+where
+    T: ?Sized + 'static,
+{
+    map.read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .get(&TypeId::of::<T>())
+        .and_then(|entry| entry.value.downcast_ref::<Arc<T>>())
+        .cloned()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RegistryError {
+    ComponentRegistered { name: &'static str },
+    InterfaceRegistered { name: &'static str },
+    ComponentNotFound { name: &'static str },
+    InterfaceNotFound { name: &'static str },
+}
+
+impl Display for RegistryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ComponentRegistered { name } => {
+                write!(f, "component `{name}` has been registered")
+            }
+            Self::InterfaceRegistered { name } => {
+                write!(f, "interface `{name}` has been registered")
+            }
+            Self::ComponentNotFound { name } => write!(f, "component `{name}` is not registered"),
+            Self::InterfaceNotFound { name } => write!(f, "interface `{name}` is not registered"),
+        }
+    }
+}
+
+impl Error for RegistryError {}
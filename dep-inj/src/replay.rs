@@ -0,0 +1,816 @@
+//! Recording the calls between the components, and replaying them against a component in
+//! isolation, for deterministic regression tests.
+//!
+//! `#[dep_inj::replayable(ReplayIsEven)]` on an interface generates the hooks:
+//!
+//! * `impl IsEven for Recorded<Inner>`, a layer recording the calls to the wrapped proxy, with
+//!   their arguments and results, into the [`Recorder`] component of the container. The calls
+//!   made by a component while serving a call are recorded as nested in it.
+//! * `impl IsEven for Replayer<State>`, serving the calls of a component under replay from the
+//!   recording, and checking that they match the recorded ones.
+//! * `ReplayIsEven`, driving the component under replay with the recorded calls to `IsEven`.
+//!
+//! ```
+//! use dep_inj::replay::{Recorded, Recorder, Recording, Replayer};
+//! use dep_inj::{Container, DepInj};
+//! use std::sync::Arc;
+//!
+//! #[dep_inj::replayable(ReplayIsOdd)]
+//! #[dep_inj::interface]
+//! pub trait IsOdd {
+//!     fn is_odd(self: Arc<Self>, n: u64) -> bool;
+//! }
+//!
+//! #[dep_inj::replayable(ReplayIsEven)]
+//! #[dep_inj::interface]
+//! pub trait IsEven {
+//!     fn is_even(self: Arc<Self>, n: u64) -> bool;
+//! }
+//!
+//! #[derive(Default, DepInj)]
+//! #[target(OddProxy)]
+//! pub struct OddState;
+//!
+//! impl<Deps: IsEven> IsOdd for OddProxy<Deps> {
+//!     fn is_odd(self: Arc<Self>, n: u64) -> bool {
+//!         n != 0 && self.prj_arc().is_even(n - 1)
+//!     }
+//! }
+//!
+//! #[derive(Default, DepInj)]
+//! #[target(EvenProxy)]
+//! pub struct EvenState;
+//!
+//! impl<Deps: IsOdd> IsEven for EvenProxy<Deps> {
+//!     fn is_even(self: Arc<Self>, n: u64) -> bool {
+//!         n == 0 || self.prj_arc().is_odd(n - 1)
+//!     }
+//! }
+//!
+//! #[derive(Default, Container)]
+//! pub struct GlobalStruct {
+//!     #[component(proxy = OddProxy, layers(Recorded), provide(IsOdd))]
+//!     odd_state: OddState,
+//!     #[component(proxy = EvenProxy, layers(Recorded), provide(IsEven))]
+//!     even_state: EvenState,
+//!     #[component]
+//!     recorder: Recorder,
+//! }
+//!
+//! let global = Arc::new(GlobalStruct::default());
+//! assert!(global.clone().is_even(2));
+//! let text = global.recorder.recording().to_string();
+//!
+//! // `is_even(2)` calls `is_odd(1)`, served from the recording
+//! let recording = text.parse::<Recording>().unwrap();
+//! let replayed = Replayer::new(EvenState)
+//!     .replay::<ReplayIsEven, _>(&recording, EvenProxy::inj_arc)
+//!     .unwrap();
+//! assert_eq!(replayed, 1);
+//!
+//! // an implementation calling `is_odd(0)` instead fails the replay
+//! #[derive(Default, DepInj)]
+//! #[target(BrokenProxy)]
+//! pub struct BrokenState;
+//!
+//! impl<Deps: IsOdd> IsEven for BrokenProxy<Deps> {
+//!     fn is_even(self: Arc<Self>, n: u64) -> bool {
+//!         n == 0 || self.prj_arc().is_odd(0)
+//!     }
+//! }
+//!
+//! # std::panic::set_hook(Box::new(|_| {}));
+//! let err = Replayer::new(BrokenState)
+//!     .replay::<ReplayIsEven, _>(&recording, BrokenProxy::inj_arc)
+//!     .unwrap_err();
+//! assert_eq!(
+//!     err.to_string(),
+//!     "expect the call `IsOdd::is_odd(1)`, found `IsOdd::is_odd(0)`"
+//! );
+//! ```
+//!
+//! The arguments and the results are [`Record`]ed into [`Value`]s, and the results of the calls
+//! served from the recording are [`Replay`]ed from them. The generic methods and the methods
+//! returning references are forwarded without being recorded, and cannot be replayed. The
+//! `&mut` arguments are compared with the recording, but not changed by the calls served from it.
+//!
+//! [`Record`] and [`Replay`] are implemented for the primitives, `String`, [`Value`], `Box`, `Vec`,
+//! `Option`, `Result`, the maps keyed by `String` and the tuples of up to 4 items, and `str`, the
+//! slices and the references are [`Record`]ed too. The other types implement them by hand, often
+//! through [`Snapshot`] like the primitives do:
+//!
+//! ```
+//! use dep_inj::replay::{Record, Replay};
+//! use dep_inj::snapshot::{Snapshot, SnapshotError, Value};
+//! use dep_inj::Snapshot;
+//!
+//! #[derive(Default, Snapshot)]
+//! pub struct Stats {
+//!     calls: u64,
+//! }
+//!
+//! impl Record for Stats {
+//!     fn record(&self) -> Value {
+//!         self.snapshot()
+//!     }
+//! }
+//!
+//! impl Replay for Stats {
+//!     fn replay(value: &Value) -> Result<Self, SnapshotError> {
+//!         let mut stats = Stats::default();
+//!         stats.restore(value)?;
+//!         Ok(stats)
+//!     }
+//! }
+//!
+//! assert_eq!(Stats::replay(&Stats { calls: 2 }.record()).unwrap().calls, 2);
+//! ```
+
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    error::Error,
+    fmt::{self, Display},
+    fs, io,
+    panic::{catch_unwind, AssertUnwindSafe},
+    path::Path,
+    str::FromStr,
+    sync::{Arc, Mutex, PoisonError},
+    thread::{self, ThreadId},
+};
+
+use crate::snapshot::{Snapshot, SnapshotError, Value};
+use crate::DepInj;
+
+/// Captures an argument or a result of a call.
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` cannot be recorded as an argument or a result",
+    note = "implement `Record` for it, through `Snapshot` for example, see `dep_inj::replay`",
+    note = "the tuples of up to 4 items are recorded"
+)]
+pub trait Record {
+    fn record(&self) -> Value;
+}
+
+/// Rebuilds a result of a call from the recording.
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` cannot be replayed as a result",
+    note = "implement `Replay` for it, through `Default + Snapshot` for example, see `dep_inj::replay`",
+    note = "the tuples of up to 4 items are replayed"
+)]
+pub trait Replay: Sized {
+    fn replay(value: &Value) -> Result<Self, SnapshotError>;
+}
+
+macro_rules! impl_by_snapshot {
+    ($($ty:ty),*) => {$(
+        impl Record for $ty {
+            fn record(&self) -> Value {
+                self.snapshot()
+            }
+        }
+
+        impl Replay for $ty {
+            fn replay(value: &Value) -> Result<Self, SnapshotError> {
+                let mut this = <$ty>::default();
+                this.restore(value)?;
+                Ok(this)
+            }
+        }
+    )*};
+}
+
+impl_by_snapshot!(
+    bool,
+    i8,
+    i16,
+    i32,
+    i64,
+    i128,
+    isize,
+    u8,
+    u16,
+    u32,
+    u64,
+    usize,
+    f32,
+    f64,
+    char,
+    String,
+    ()
+);
+
+impl Record for Value {
+    fn record(&self) -> Value {
+        self.clone()
+    }
+}
+
+impl Replay for Value {
+    fn replay(value: &Value) -> Result<Self, SnapshotError> {
+        Ok(value.clone())
+    }
+}
+
+impl Record for str {
+    fn record(&self) -> Value {
+        Value::Str(self.to_string())
+    }
+}
+
+impl<T: Record + ?Sized> Record for &T {
+    fn record(&self) -> Value {
+        (**self).record()
+    }
+}
+
+impl<T: Record + ?Sized> Record for &mut T {
+    fn record(&self) -> Value {
+        (**self).record()
+    }
+}
+
+impl<T: Record + ?Sized> Record for Box<T> {
+    fn record(&self) -> Value {
+        (**self).record()
+    }
+}
+
+impl<T: Replay> Replay for Box<T> {
+    fn replay(value: &Value) -> Result<Self, SnapshotError> {
+        T::replay(value).map(Box::new)
+    }
+}
+
+impl<T: Record> Record for [T] {
+    fn record(&self) -> Value {
+        Value::List(self.iter().map(Record::record).collect())
+    }
+}
+
+impl<T: Record> Record for Vec<T> {
+    fn record(&self) -> Value {
+        self.as_slice().record()
+    }
+}
+
+impl<T: Replay> Replay for Vec<T> {
+    fn replay(value: &Value) -> Result<Self, SnapshotError> {
+        let Value::List(items) = value else {
+            return Err(SnapshotError::mismatch("a list"));
+        };
+        items
+            .iter()
+            .enumerate()
+            .map(|(i, item)| T::replay(item).map_err(|err| err.within(&i.to_string())))
+            .collect()
+    }
+}
+
+/// `None` as `[]`, and `Some(x)` as `[x]`, as the snapshots do.
+impl<T: Record> Record for Option<T> {
+    fn record(&self) -> Value {
+        Value::List(self.iter().map(Record::record).collect())
+    }
+}
+
+impl<T: Replay> Replay for Option<T> {
+    fn replay(value: &Value) -> Result<Self, SnapshotError> {
+        match value {
+            Value::List(items) if items.is_empty() => Ok(None),
+            Value::List(items) if items.len() == 1 => T::replay(&items[0])
+                .map(Some)
+                .map_err(|err| err.within("0")),
+            _ => Err(SnapshotError::mismatch("a list of at most one item")),
+        }
+    }
+}
+
+/// `Ok(x)` as `{Ok: x}`, and `Err(e)` as `{Err: e}`.
+impl<T: Record, E: Record> Record for Result<T, E> {
+    fn record(&self) -> Value {
+        match self {
+            Ok(value) => Value::Map(vec![("Ok".to_string(), value.record())]),
+            Err(err) => Value::Map(vec![("Err".to_string(), err.record())]),
+        }
+    }
+}
+
+impl<T: Replay, E: Replay> Replay for Result<T, E> {
+    fn replay(value: &Value) -> Result<Self, SnapshotError> {
+        match value.fields()? {
+            [(key, value)] if key == "Ok" => {
+                T::replay(value).map(Ok).map_err(|err| err.within("Ok"))
+            }
+            [(key, err)] if key == "Err" => {
+                E::replay(err).map(Err).map_err(|err| err.within("Err"))
+            }
+            _ => Err(SnapshotError::mismatch("`{Ok: ..}` or `{Err: ..}`")),
+        }
+    }
+}
+
+impl<V: Record> Record for BTreeMap<String, V> {
+    fn record(&self) -> Value {
+        Value::Map(
+            self.iter()
+                .map(|(key, value)| (key.clone(), value.record()))
+                .collect(),
+        )
+    }
+}
+
+impl<V: Replay> Replay for BTreeMap<String, V> {
+    fn replay(value: &Value) -> Result<Self, SnapshotError> {
+        value
+            .fields()?
+            .iter()
+            .map(|(key, value)| {
+                Ok((
+                    key.clone(),
+                    V::replay(value).map_err(|err| err.within(key))?,
+                ))
+            })
+            .collect()
+    }
+}
+
+impl<V: Record, S> Record for HashMap<String, V, S> {
+    fn record(&self) -> Value {
+        // sorted, so that the recording is deterministic
+        let mut fields = self
+            .iter()
+            .map(|(key, value)| (key.clone(), value.record()))
+            .collect::<Vec<_>>();
+        fields.sort_by(|(a, _), (b, _)| a.cmp(b));
+        Value::Map(fields)
+    }
+}
+
+impl<V: Replay> Replay for HashMap<String, V> {
+    fn replay(value: &Value) -> Result<Self, SnapshotError> {
+        value
+            .fields()?
+            .iter()
+            .map(|(key, value)| {
+                Ok((
+                    key.clone(),
+                    V::replay(value).map_err(|err| err.within(key))?,
+                ))
+            })
+            .collect()
+    }
+}
+
+macro_rules! impl_tuple {
+    ($(($($ty:ident $i:tt),+))*) => {$(
+        impl<$($ty: Record),+> Record for ($($ty,)+) {
+            fn record(&self) -> Value {
+                Value::List(vec![$(self.$i.record()),+])
+            }
+        }
+
+        impl<$($ty: Replay),+> Replay for ($($ty,)+) {
+            fn replay(value: &Value) -> Result<Self, SnapshotError> {
+                match value {
+                    Value::List(items) if items.len() == [$($i),+].len() => Ok(($(
+                        $ty::replay(&items[$i]).map_err(|err| err.within(stringify!($i)))?,
+                    )+)),
+                    _ => Err(SnapshotError::mismatch("a tuple")),
+                }
+            }
+        }
+    )*};
+}
+
+impl_tuple!((A 0)(A 0, B 1)(A 0, B 1, C 2)(A 0, B 1, C 2, D 3));
+
+/// A recorded call, and the calls made while serving it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Call {
+    pub interface: String,
+    pub method: String,
+    pub args: Vec<Value>,
+    /// `None` if the call panicked.
+    pub result: Option<Value>,
+    pub calls: Vec<Call>,
+}
+
+impl Call {
+    fn to_value(&self) -> Value {
+        Value::Map(vec![
+            ("interface".to_string(), Value::Str(self.interface.clone())),
+            ("method".to_string(), Value::Str(self.method.clone())),
+            ("args".to_string(), Value::List(self.args.clone())),
+            (
+                "result".to_string(),
+                Value::List(self.result.iter().cloned().collect()),
+            ),
+            (
+                "calls".to_string(),
+                Value::List(self.calls.iter().map(Call::to_value).collect()),
+            ),
+        ])
+    }
+
+    fn from_value(value: &Value) -> Result<Self, SnapshotError> {
+        let field = |key| {
+            value
+                .get(key)
+                .ok_or(SnapshotError::mismatch("a field"))
+                .map_err(|err| err.within(key))
+        };
+        let list = |key| match field(key)? {
+            Value::List(items) => Ok(items),
+            _ => Err(SnapshotError::mismatch("a list").within(key)),
+        };
+        let calls = list("calls")?
+            .iter()
+            .enumerate()
+            .map(|(i, call)| Call::from_value(call).map_err(|err| err.within(&i.to_string())))
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            interface: String::replay(field("interface")?)
+                .map_err(|err| err.within("interface"))?,
+            method: String::replay(field("method")?).map_err(|err| err.within("method"))?,
+            args: list("args")?.clone(),
+            result: Option::<Value>::replay(field("result")?)
+                .map_err(|err| err.within("result"))?,
+            calls,
+        })
+    }
+}
+
+impl Display for Call {
+    /// `IsEven::is_even(2)`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}::{}(", self.interface, self.method)?;
+        for (i, arg) in self.args.iter().enumerate() {
+            if i != 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{arg}")?;
+        }
+        f.write_str(")")
+    }
+}
+
+/// The calls recorded by a [`Recorder`], printed and parsed as a list of [`Value`] maps.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Recording {
+    /// The calls made from outside the components, in order.
+    pub calls: Vec<Call>,
+}
+
+impl Recording {
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        fs::read_to_string(path)?
+            .parse()
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, format!("{self:#}\n"))
+    }
+}
+
+impl Display for Recording {
+    /// `{:#}` prints a call in multiple lines.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = Value::List(self.calls.iter().map(Call::to_value).collect());
+        match f.alternate() {
+            true => write!(f, "{value:#}"),
+            false => write!(f, "{value}"),
+        }
+    }
+}
+
+impl FromStr for Recording {
+    type Err = SnapshotError;
+
+    fn from_str(s: &str) -> Result<Self, SnapshotError> {
+        let Value::List(calls) = s.parse::<Value>()? else {
+            return Err(SnapshotError::mismatch("a list of calls"));
+        };
+        let calls = calls
+            .iter()
+            .enumerate()
+            .map(|(i, call)| Call::from_value(call).map_err(|err| err.within(&i.to_string())))
+            .collect::<Result<_, _>>()?;
+        Ok(Self { calls })
+    }
+}
+
+/// Records the calls through the [`Recorded`] layers, a component of the container.
+#[derive(Debug, Default, DepInj)]
+#[target(Recorded, layer)]
+pub struct Recorder {
+    log: Arc<Mutex<Log>>,
+}
+
+#[derive(Debug, Default)]
+struct Log {
+    /// the calls in flight of each thread, the innermost last
+    stacks: HashMap<ThreadId, Vec<Call>>,
+    calls: Vec<Call>,
+}
+
+impl Recorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The calls finished so far.
+    pub fn recording(&self) -> Recording {
+        Recording {
+            calls: self.lock().calls.clone(),
+        }
+    }
+
+    /// Take the calls finished so far, starting a new recording.
+    pub fn take(&self) -> Recording {
+        Recording {
+            calls: std::mem::take(&mut self.lock().calls),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Log> {
+        self.log.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Start recording a call, finished by [`RecordingCall::finish`].
+    #[doc(hidden)]
+    pub fn enter(
+        &self,
+        interface: &'static str,
+        method: &'static str,
+        args: Vec<Value>,
+    ) -> RecordingCall {
+        self.lock()
+            .stacks
+            .entry(thread::current().id())
+            .or_default()
+            .push(Call {
+                interface: interface.to_string(),
+                method: method.to_string(),
+                args,
+                result: None,
+                calls: vec![],
+            });
+        RecordingCall {
+            log: self.log.clone(),
+            result: None,
+        }
+    }
+}
+
+/// A call being recorded, which is recorded as panicked if it is not finished.
+#[doc(hidden)]
+pub struct RecordingCall {
+    log: Arc<Mutex<Log>>,
+    result: Option<Value>,
+}
+
+impl RecordingCall {
+    pub fn finish(mut self, result: Value) {
+        self.result = Some(result);
+    }
+}
+
+impl Drop for RecordingCall {
+    fn drop(&mut self) {
+        let mut log = self.log.lock().unwrap_or_else(PoisonError::into_inner);
+        let log = &mut *log;
+        let Some(stack) = log.stacks.get_mut(&thread::current().id()) else {
+            return;
+        };
+        let Some(mut call) = stack.pop() else {
+            return;
+        };
+        call.result = self.result.take();
+        match stack.last_mut() {
+            Some(parent) => parent.calls.push(call),
+            None => {
+                log.stacks.remove(&thread::current().id());
+                log.calls.push(call);
+            }
+        }
+    }
+}
+
+/// Drives the component under replay with the recorded calls of an interface, generated by
+/// `#[dep_inj::replayable]`.
+pub trait Drive<T: ?Sized> {
+    const INTERFACE: &'static str;
+
+    /// Call `method` on `target` with the recorded `args`, recording the result.
+    fn call(target: &mut Arc<T>, method: &str, args: &[Value]) -> Result<Value, ReplayError>;
+}
+
+/// The deps of a component under replay, serving its calls from the recording.
+#[derive(Debug, Default)]
+pub struct Replayer<S> {
+    state: S,
+    /// the recorded calls expected from the call being replayed, shared with the driver
+    expected: Arc<Mutex<VecDeque<Call>>>,
+}
+
+impl<S> AsRef<S> for Replayer<S> {
+    fn as_ref(&self) -> &S {
+        &self.state
+    }
+}
+
+impl<S> AsMut<S> for Replayer<S> {
+    fn as_mut(&mut self) -> &mut S {
+        &mut self.state
+    }
+}
+
+impl<S> Replayer<S> {
+    pub fn new(state: S) -> Self {
+        Self {
+            state,
+            expected: Arc::default(),
+        }
+    }
+
+    /// Replay the recorded calls of the interface driven by `I` against the proxy made by `inj`,
+    /// like `EvenProxy::inj_arc`, returning the number of the replayed calls.
+    ///
+    /// The calls are replayed in order, skipping the ones made inside a replayed call.
+    pub fn replay<I, P>(
+        self,
+        recording: &Recording,
+        inj: fn(Arc<Self>) -> Arc<P>,
+    ) -> Result<usize, ReplayError>
+    where
+        I: Drive<P>,
+    {
+        fn targets<'a>(calls: &'a [Call], interface: &str, found: &mut Vec<&'a Call>) {
+            for call in calls {
+                match call.interface == interface {
+                    true => found.push(call),
+                    false => targets(&call.calls, interface, found),
+                }
+            }
+        }
+
+        let mut found = vec![];
+        targets(&recording.calls, I::INTERFACE, &mut found);
+
+        let expected = self.expected.clone();
+        let lock = || expected.lock().unwrap_or_else(PoisonError::into_inner);
+        // not shared, for the `&mut self` calls
+        let mut target = inj(Arc::new(self));
+        for call in &found {
+            *lock() = call.calls.iter().cloned().collect();
+            let result = catch_unwind(AssertUnwindSafe(|| {
+                I::call(&mut target, &call.method, &call.args)
+            }));
+            let result = match result {
+                Ok(Ok(value)) => Some(value),
+                Ok(Err(err)) => return Err(err),
+                Err(payload) => match payload.downcast::<ReplayError>() {
+                    Ok(err) => return Err(*err),
+                    Err(_) => None,
+                },
+            };
+            if result != call.result {
+                return Err(ReplayError::Result {
+                    call: call.to_string(),
+                    expected: call.result.clone(),
+                    found: result,
+                });
+            }
+            if let Some(missing) = lock().pop_front() {
+                return Err(ReplayError::Missing {
+                    call: missing.to_string(),
+                });
+            }
+        }
+        Ok(found.len())
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, VecDeque<Call>> {
+        self.expected.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Serve a call made by the component under replay from the recording.
+    ///
+    /// # Panics
+    ///
+    /// Panics with a [`ReplayError`] if the call doesn't match the recording.
+    #[doc(hidden)]
+    pub fn serve<R: Replay>(&self, interface: &str, method: &str, args: Vec<Value>) -> R {
+        let found = Call {
+            interface: interface.to_string(),
+            method: method.to_string(),
+            args,
+            result: None,
+            calls: vec![],
+        };
+        let Some(expected) = self.lock().pop_front() else {
+            std::panic::panic_any(ReplayError::Unexpected {
+                call: found.to_string(),
+            });
+        };
+        if (&expected.interface, &expected.method, &expected.args)
+            != (&found.interface, &found.method, &found.args)
+        {
+            std::panic::panic_any(ReplayError::Mismatch {
+                expected: expected.to_string(),
+                found: found.to_string(),
+            });
+        }
+        let Some(result) = &expected.result else {
+            panic!("`{expected}` panicked in the recording");
+        };
+        R::replay(result).unwrap_or_else(|err| {
+            std::panic::panic_any(ReplayError::Decode {
+                call: expected.to_string(),
+                error: err,
+            })
+        })
+    }
+}
+
+/// A replay not matching the recording.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReplayError {
+    /// The component made a call not in the recording.
+    Unexpected { call: String },
+    /// The component made a call other than the recorded one.
+    Mismatch { expected: String, found: String },
+    /// The component didn't make a recorded call.
+    Missing { call: String },
+    /// The result of a replayed call is not the recorded one, `None` if it panicked.
+    Result {
+        call: String,
+        expected: Option<Value>,
+        found: Option<Value>,
+    },
+    /// A recorded value cannot be replayed.
+    Decode { call: String, error: SnapshotError },
+    /// The method is not known by the interface, or cannot be replayed.
+    UnknownMethod { interface: String, method: String },
+}
+
+impl Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn result(result: &Option<Value>) -> String {
+            match result {
+                Some(value) => format!("`{value}`"),
+                None => "a panic".to_string(),
+            }
+        }
+
+        match self {
+            Self::Unexpected { call } => write!(f, "unexpected call `{call}`"),
+            Self::Mismatch { expected, found } => {
+                write!(f, "expect the call `{expected}`, found `{found}`")
+            }
+            Self::Missing { call } => write!(f, "missing the call `{call}`"),
+            Self::Result {
+                call,
+                expected,
+                found,
+            } => write!(
+                f,
+                "expect {} from `{call}`, found {}",
+                result(expected),
+                result(found)
+            ),
+            Self::Decode { call, error } => write!(f, "cannot replay `{call}`: {error}"),
+            Self::UnknownMethod { interface, method } => {
+                write!(f, "`{interface}::{method}` cannot be replayed")
+            }
+        }
+    }
+}
+
+impl Error for ReplayError {}
+
+/// Turns a recorded argument into the argument of a replayed call.
+#[doc(hidden)]
+pub fn replay_arg<T: Replay>(
+    interface: &str,
+    method: &str,
+    args: &[Value],
+    index: usize,
+) -> Result<T, ReplayError> {
+    let call = || Call {
+        interface: interface.to_string(),
+        method: method.to_string(),
+        args: args.to_vec(),
+        result: None,
+        calls: vec![],
+    };
+    let value = args.get(index).ok_or_else(|| ReplayError::Decode {
+        call: call().to_string(),
+        error: SnapshotError::mismatch("more arguments"),
+    })?;
+    T::replay(value).map_err(|err| ReplayError::Decode {
+        call: call().to_string(),
+        error: err.within(&index.to_string()),
+    })
+}
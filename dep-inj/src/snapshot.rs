@@ -0,0 +1,739 @@
+//! Snapshots of the component states, for crash recovery and test fixtures.
+//!
+//! A state implements or derives [`Snapshot`] to be captured into a [`Value`] and restored from
+//! it. A container marked by `#[snapshot]` captures all its components into a map keyed by the
+//! component names, so a snapshot survives reordering the fields. A [`Value`] is printed and
+//! parsed as text like `{even_state: {count: 2, name: "even"}, odd_state: {}}`.
+//!
+//! ```
+//! use dep_inj::snapshot::{Snapshot, Value};
+//! use dep_inj::{Container, DepInj, Snapshot};
+//!
+//! #[derive(Default, DepInj, Snapshot)]
+//! #[target(CounterProxy)]
+//! pub struct CounterState {
+//!     count: u64,
+//!     history: Vec<u64>,
+//!     // not captured, kept as it is on restoring
+//!     #[snapshot(skip)]
+//!     scratch: String,
+//! }
+//!
+//! #[derive(Default, Container)]
+//! #[snapshot]
+//! pub struct GlobalStruct {
+//!     #[component]
+//!     counter_state: CounterState,
+//! }
+//!
+//! let mut global = GlobalStruct::default();
+//! global.counter_state.count = 2;
+//! global.counter_state.history.push(1);
+//! let text = global.snapshot().to_string();
+//! assert_eq!(text, "{counter_state: {count: 2, history: [1]}}");
+//!
+//! let mut restored = GlobalStruct::default();
+//! restored.restore(&text.parse::<Value>().unwrap()).unwrap();
+//! assert_eq!(restored.counter_state.count, 2);
+//! assert_eq!(restored.counter_state.history, [1]);
+//! ```
+//!
+//! On restoring, the fields missing in the snapshot are kept as they are, and the keys unknown
+//! to the state are ignored, so that the snapshots taken before adding or removing a field can
+//! still be restored.
+//!
+//! The text is parsed with the maps and the lists nested at most [`MAX_DEPTH`] deep, so that a
+//! malformed snapshot or recording fails to parse rather than overflowing the stack.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    error::Error,
+    fmt::{self, Display, Write},
+    hash::BuildHasher,
+    marker::PhantomData,
+    str::FromStr,
+    sync::{
+        atomic::{
+            AtomicBool, AtomicI16, AtomicI32, AtomicI64, AtomicI8, AtomicIsize, AtomicU16,
+            AtomicU32, AtomicU64, AtomicU8, AtomicUsize, Ordering,
+        },
+        Mutex, PoisonError, RwLock,
+    },
+};
+
+/// A state which can be captured into a [`Value`] and restored from it.
+pub trait Snapshot {
+    fn snapshot(&self) -> Value;
+
+    /// Restore the state in place, keeping the parts not captured by the snapshot.
+    fn restore(&mut self, value: &Value) -> Result<(), SnapshotError>;
+}
+
+/// A self-describing value of a snapshot.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Bool(bool),
+    Int(i128),
+    Float(f64),
+    Str(String),
+    List(Vec<Value>),
+    /// The fields in order, keyed by their names.
+    Map(Vec<(String, Value)>),
+}
+
+impl Value {
+    /// The field `key` of a map.
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Self::Map(fields) => fields
+                .iter()
+                .find(|(name, _)| name == key)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    /// The fields of a map, or an error expecting a map.
+    pub fn fields(&self) -> Result<&[(String, Value)], SnapshotError> {
+        match self {
+            Self::Map(fields) => Ok(fields),
+            _ => Err(SnapshotError::mismatch("a map")),
+        }
+    }
+}
+
+/// Restore `field` from the field `key` of a map, or keep it if it is missing.
+pub fn restore_field<T: Snapshot + ?Sized>(
+    fields: &[(String, Value)],
+    key: &str,
+    field: &mut T,
+) -> Result<(), SnapshotError> {
+    match fields.iter().find(|(name, _)| name == key) {
+        Some((_, value)) => field.restore(value).map_err(|err| err.within(key)),
+        None => Ok(()),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SnapshotError {
+    /// The text is not a valid snapshot.
+    Parse {
+        offset: usize,
+        message: &'static str,
+    },
+    /// The value at `path` is not the expected one.
+    Mismatch {
+        path: String,
+        expected: &'static str,
+    },
+}
+
+impl SnapshotError {
+    pub fn mismatch(expected: &'static str) -> Self {
+        Self::Mismatch {
+            path: String::new(),
+            expected,
+        }
+    }
+
+    /// Prefix the path of a mismatch by the field `key` it happened in.
+    pub fn within(self, key: &str) -> Self {
+        match self {
+            Self::Mismatch { path, expected } if path.is_empty() => Self::Mismatch {
+                path: key.to_string(),
+                expected,
+            },
+            Self::Mismatch { path, expected } => Self::Mismatch {
+                path: format!("{key}.{path}"),
+                expected,
+            },
+            err => err,
+        }
+    }
+}
+
+impl Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Parse { offset, message } => write!(f, "{message} at offset {offset}"),
+            Self::Mismatch { path, expected } if path.is_empty() => write!(f, "expect {expected}"),
+            Self::Mismatch { path, expected } => write!(f, "expect {expected} at `{path}`"),
+        }
+    }
+}
+
+impl Error for SnapshotError {}
+
+impl Display for Value {
+    /// `{:#}` prints the maps and the lists in multiple lines.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let indent = f.alternate().then_some(0);
+        write_value(f, self, indent)
+    }
+}
+
+fn write_value(f: &mut fmt::Formatter<'_>, value: &Value, indent: Option<usize>) -> fmt::Result {
+    match value {
+        Value::Bool(value) => write!(f, "{value}"),
+        Value::Int(value) => write!(f, "{value}"),
+        // `{:?}` always prints a `.`, an exponent, `inf` or `NaN`, unlike an integer
+        Value::Float(value) => write!(f, "{value:?}"),
+        Value::Str(value) => write!(f, "{value:?}"),
+        Value::List(items) => write_seq(f, ('[', ']'), items, indent, |f, item, indent| {
+            write_value(f, item, indent)
+        }),
+        Value::Map(fields) => {
+            write_seq(f, ('{', '}'), fields, indent, |f, (key, value), indent| {
+                if is_bare_key(key) {
+                    write!(f, "{key}: ")?;
+                } else {
+                    write!(f, "{key:?}: ")?;
+                }
+                write_value(f, value, indent)
+            })
+        }
+    }
+}
+
+fn write_seq<T>(
+    f: &mut fmt::Formatter<'_>,
+    (open, close): (char, char),
+    items: &[T],
+    indent: Option<usize>,
+    mut write_item: impl FnMut(&mut fmt::Formatter<'_>, &T, Option<usize>) -> fmt::Result,
+) -> fmt::Result {
+    f.write_char(open)?;
+    match indent {
+        Some(indent) if !items.is_empty() => {
+            for item in items {
+                write!(f, "\n{:1$}", "", (indent + 1) * 4)?;
+                write_item(f, item, Some(indent + 1))?;
+                f.write_char(',')?;
+            }
+            write!(f, "\n{:1$}", "", indent * 4)?;
+        }
+        _ => {
+            for (i, item) in items.iter().enumerate() {
+                if i != 0 {
+                    f.write_str(", ")?;
+                }
+                write_item(f, item, None)?;
+            }
+        }
+    }
+    f.write_char(close)
+}
+
+fn is_bare_key(key: &str) -> bool {
+    !key.is_empty()
+        && key
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || ch == '_')
+}
+
+/// The deepest nesting of the maps and the lists parsed from a text.
+pub const MAX_DEPTH: usize = 128;
+
+impl FromStr for Value {
+    type Err = SnapshotError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser {
+            text,
+            offset: 0,
+            depth: 0,
+        };
+        let value = parser.value()?;
+        parser.skip_whitespace();
+        if parser.offset != text.len() {
+            return Err(parser.error("unexpected trailing characters"));
+        }
+        Ok(value)
+    }
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    offset: usize,
+    /// the maps and the lists the parser is in
+    depth: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, message: &'static str) -> SnapshotError {
+        SnapshotError::Parse {
+            offset: self.offset,
+            message,
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.text[self.offset..].chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let ch = self.peek()?;
+        self.offset += ch.len_utf8();
+        Some(ch)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.bump();
+        }
+    }
+
+    fn expect(&mut self, expected: char, message: &'static str) -> Result<(), SnapshotError> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(ch) if ch == expected => {
+                self.bump();
+                Ok(())
+            }
+            _ => Err(self.error(message)),
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, SnapshotError> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('"') => self.string().map(Value::Str),
+            Some('[') => {
+                let items = self.seq(']', Self::value)?;
+                Ok(Value::List(items))
+            }
+            Some('{') => {
+                let fields = self.seq('}', |parser| {
+                    parser.skip_whitespace();
+                    let key = match parser.peek() {
+                        Some('"') => parser.string()?,
+                        _ => parser.word().to_string(),
+                    };
+                    if key.is_empty() {
+                        return Err(parser.error("expect a key"));
+                    }
+                    parser.expect(':', "expect `:`")?;
+                    Ok((key, parser.value()?))
+                })?;
+                Ok(Value::Map(fields))
+            }
+            Some(_) => {
+                let start = self.offset;
+                let word = self.word();
+                let value = match word {
+                    "true" => Value::Bool(true),
+                    "false" => Value::Bool(false),
+                    _ => match word.parse::<i128>() {
+                        Ok(value) => Value::Int(value),
+                        Err(_) => match word.parse::<f64>() {
+                            Ok(value) => Value::Float(value),
+                            Err(_) => {
+                                self.offset = start;
+                                return Err(self.error("expect a value"));
+                            }
+                        },
+                    },
+                };
+                Ok(value)
+            }
+            None => Err(self.error("unexpected end")),
+        }
+    }
+
+    /// `[a, b]` or `{a: 1, b: 2}`, with an optional trailing comma.
+    fn seq<T>(
+        &mut self,
+        close: char,
+        mut item: impl FnMut(&mut Self) -> Result<T, SnapshotError>,
+    ) -> Result<Vec<T>, SnapshotError> {
+        if self.depth == MAX_DEPTH {
+            return Err(self.error("nested too deep"));
+        }
+        self.bump();
+        self.depth += 1;
+        let mut items = vec![];
+        loop {
+            self.skip_whitespace();
+            if self.peek() == Some(close) {
+                self.bump();
+                self.depth -= 1;
+                return Ok(items);
+            }
+            items.push(item(self)?);
+            self.skip_whitespace();
+            match self.peek() {
+                Some(',') => {
+                    self.bump();
+                }
+                Some(ch) if ch == close => {}
+                _ => return Err(self.error("expect `,` or a closing bracket")),
+            }
+        }
+    }
+
+    /// A keyword, a number or a bare key.
+    fn word(&mut self) -> &str {
+        let start = self.offset;
+        while self
+            .peek()
+            .is_some_and(|ch| ch.is_ascii_alphanumeric() || "_+-.".contains(ch))
+        {
+            self.bump();
+        }
+        &self.text[start..self.offset]
+    }
+
+    /// A string escaped like `{:?}`.
+    fn string(&mut self) -> Result<String, SnapshotError> {
+        self.bump();
+        let mut string = String::new();
+        loop {
+            match self.bump() {
+                Some('"') => return Ok(string),
+                Some('\\') => {
+                    let ch = match self.bump() {
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some('0') => '\0',
+                        Some(ch @ ('\\' | '"' | '\'')) => ch,
+                        Some('u') => self.unicode_escape()?,
+                        _ => return Err(self.error("invalid escape")),
+                    };
+                    string.push(ch);
+                }
+                Some(ch) => string.push(ch),
+                None => return Err(self.error("unterminated string")),
+            }
+        }
+    }
+
+    /// `{1F600}` after `\u`
+    fn unicode_escape(&mut self) -> Result<char, SnapshotError> {
+        if self.bump() != Some('{') {
+            return Err(self.error("invalid unicode escape"));
+        }
+        let start = self.offset;
+        while self.peek().is_some_and(|ch| ch.is_ascii_hexdigit()) {
+            self.bump();
+        }
+        let digits = &self.text[start..self.offset];
+        if self.bump() != Some('}') {
+            return Err(self.error("invalid unicode escape"));
+        }
+        u32::from_str_radix(digits, 16)
+            .ok()
+            .and_then(char::from_u32)
+            .ok_or_else(|| self.error("invalid unicode escape"))
+    }
+}
+
+impl Snapshot for Value {
+    fn snapshot(&self) -> Value {
+        self.clone()
+    }
+
+    fn restore(&mut self, value: &Value) -> Result<(), SnapshotError> {
+        *self = value.clone();
+        Ok(())
+    }
+}
+
+impl Snapshot for bool {
+    fn snapshot(&self) -> Value {
+        Value::Bool(*self)
+    }
+
+    fn restore(&mut self, value: &Value) -> Result<(), SnapshotError> {
+        match value {
+            Value::Bool(value) => {
+                *self = *value;
+                Ok(())
+            }
+            _ => Err(SnapshotError::mismatch("a bool")),
+        }
+    }
+}
+
+macro_rules! impl_int {
+    ($($int:ty),*) => {$(
+        impl Snapshot for $int {
+            fn snapshot(&self) -> Value {
+                Value::Int(*self as i128)
+            }
+
+            fn restore(&mut self, value: &Value) -> Result<(), SnapshotError> {
+                match value {
+                    Value::Int(value) => {
+                        *self = <$int>::try_from(*value)
+                            .map_err(|_| SnapshotError::mismatch(stringify!(a $int)))?;
+                        Ok(())
+                    }
+                    _ => Err(SnapshotError::mismatch("an integer")),
+                }
+            }
+        }
+    )*};
+}
+
+impl_int!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, usize);
+
+macro_rules! impl_float {
+    ($($float:ty),*) => {$(
+        impl Snapshot for $float {
+            fn snapshot(&self) -> Value {
+                // the shortest decimal of a `f32`, instead of its exact value as a `f64`
+                Value::Float(format!("{self:?}").parse().unwrap())
+            }
+
+            fn restore(&mut self, value: &Value) -> Result<(), SnapshotError> {
+                match value {
+                    Value::Float(value) => *self = *value as $float,
+                    // `1` is a valid float
+                    Value::Int(value) => *self = *value as $float,
+                    _ => return Err(SnapshotError::mismatch("a float")),
+                }
+                Ok(())
+            }
+        }
+    )*};
+}
+
+impl_float!(f32, f64);
+
+macro_rules! impl_atomic {
+    ($($atomic:ty),*) => {$(
+        impl Snapshot for $atomic {
+            fn snapshot(&self) -> Value {
+                self.load(Ordering::SeqCst).snapshot()
+            }
+
+            fn restore(&mut self, value: &Value) -> Result<(), SnapshotError> {
+                self.get_mut().restore(value)
+            }
+        }
+    )*};
+}
+
+impl_atomic!(
+    AtomicBool,
+    AtomicI8,
+    AtomicI16,
+    AtomicI32,
+    AtomicI64,
+    AtomicIsize,
+    AtomicU8,
+    AtomicU16,
+    AtomicU32,
+    AtomicU64,
+    AtomicUsize
+);
+
+impl Snapshot for char {
+    fn snapshot(&self) -> Value {
+        Value::Str(self.to_string())
+    }
+
+    fn restore(&mut self, value: &Value) -> Result<(), SnapshotError> {
+        let mut chars = match value {
+            Value::Str(value) => value.chars(),
+            _ => return Err(SnapshotError::mismatch("a char")),
+        };
+        match (chars.next(), chars.next()) {
+            (Some(ch), None) => {
+                *self = ch;
+                Ok(())
+            }
+            _ => Err(SnapshotError::mismatch("a char")),
+        }
+    }
+}
+
+impl Snapshot for String {
+    fn snapshot(&self) -> Value {
+        Value::Str(self.clone())
+    }
+
+    fn restore(&mut self, value: &Value) -> Result<(), SnapshotError> {
+        match value {
+            Value::Str(value) => {
+                self.clone_from(value);
+                Ok(())
+            }
+            _ => Err(SnapshotError::mismatch("a string")),
+        }
+    }
+}
+
+impl Snapshot for () {
+    fn snapshot(&self) -> Value {
+        Value::List(vec![])
+    }
+
+    fn restore(&mut self, _value: &Value) -> Result<(), SnapshotError> {
+        Ok(())
+    }
+}
+
+impl<T: ?Sized> Snapshot for PhantomData<T> {
+    fn snapshot(&self) -> Value {
+        Value::List(vec![])
+    }
+
+    fn restore(&mut self, _value: &Value) -> Result<(), SnapshotError> {
+        Ok(())
+    }
+}
+
+impl<T: Snapshot + ?Sized> Snapshot for Box<T> {
+    fn snapshot(&self) -> Value {
+        (**self).snapshot()
+    }
+
+    fn restore(&mut self, value: &Value) -> Result<(), SnapshotError> {
+        (**self).restore(value)
+    }
+}
+
+impl<T: Snapshot + ?Sized> Snapshot for Mutex<T> {
+    fn snapshot(&self) -> Value {
+        self.lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .snapshot()
+    }
+
+    fn restore(&mut self, value: &Value) -> Result<(), SnapshotError> {
+        self.get_mut()
+            .unwrap_or_else(PoisonError::into_inner)
+            .restore(value)
+    }
+}
+
+impl<T: Snapshot + ?Sized> Snapshot for RwLock<T> {
+    fn snapshot(&self) -> Value {
+        self.read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .snapshot()
+    }
+
+    fn restore(&mut self, value: &Value) -> Result<(), SnapshotError> {
+        self.get_mut()
+            .unwrap_or_else(PoisonError::into_inner)
+            .restore(value)
+    }
+}
+
+/// `None` as `[]`, and `Some(x)` as `[x]`.
+impl<T: Snapshot + Default> Snapshot for Option<T> {
+    fn snapshot(&self) -> Value {
+        Value::List(self.iter().map(Snapshot::snapshot).collect())
+    }
+
+    fn restore(&mut self, value: &Value) -> Result<(), SnapshotError> {
+        match value {
+            Value::List(items) if items.is_empty() => {
+                *self = None;
+                Ok(())
+            }
+            Value::List(items) if items.len() == 1 => self
+                .get_or_insert_with(T::default)
+                .restore(&items[0])
+                .map_err(|err| err.within("0")),
+            _ => Err(SnapshotError::mismatch("a list of at most one item")),
+        }
+    }
+}
+
+impl<T: Snapshot + Default> Snapshot for Vec<T> {
+    fn snapshot(&self) -> Value {
+        Value::List(self.iter().map(Snapshot::snapshot).collect())
+    }
+
+    fn restore(&mut self, value: &Value) -> Result<(), SnapshotError> {
+        let Value::List(items) = value else {
+            return Err(SnapshotError::mismatch("a list"));
+        };
+        self.resize_with(items.len(), T::default);
+        for (i, (item, value)) in self.iter_mut().zip(items).enumerate() {
+            item.restore(value)
+                .map_err(|err| err.within(&i.to_string()))?;
+        }
+        Ok(())
+    }
+}
+
+impl<V: Snapshot + Default> Snapshot for BTreeMap<String, V> {
+    fn snapshot(&self) -> Value {
+        Value::Map(
+            self.iter()
+                .map(|(key, value)| (key.clone(), value.snapshot()))
+                .collect(),
+        )
+    }
+
+    fn restore(&mut self, value: &Value) -> Result<(), SnapshotError> {
+        let fields = value.fields()?;
+        self.retain(|key, _| fields.iter().any(|(name, _)| name == key));
+        for (key, value) in fields {
+            self.entry(key.clone())
+                .or_default()
+                .restore(value)
+                .map_err(|err| err.within(key))?;
+        }
+        Ok(())
+    }
+}
+
+/// Sorted by the keys, so that the snapshots are stable.
+impl<V: Snapshot + Default, S: BuildHasher> Snapshot for HashMap<String, V, S> {
+    fn snapshot(&self) -> Value {
+        let mut fields = self
+            .iter()
+            .map(|(key, value)| (key.clone(), value.snapshot()))
+            .collect::<Vec<_>>();
+        fields.sort_by(|(a, _), (b, _)| a.cmp(b));
+        Value::Map(fields)
+    }
+
+    fn restore(&mut self, value: &Value) -> Result<(), SnapshotError> {
+        let fields = value.fields()?;
+        self.retain(|key, _| fields.iter().any(|(name, _)| name == key));
+        for (key, value) in fields {
+            self.entry(key.clone())
+                .or_default()
+                .restore(value)
+                .map_err(|err| err.within(key))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_nested() {
+        let text = format!("{}{}", "[".repeat(MAX_DEPTH), "]".repeat(MAX_DEPTH));
+        let mut value = text.parse::<Value>().unwrap();
+        for _ in 1..MAX_DEPTH {
+            let Value::List(mut items) = value else {
+                panic!("expect a list");
+            };
+            value = items.pop().unwrap();
+        }
+        assert_eq!(value, Value::List(vec![]));
+    }
+
+    #[test]
+    fn parse_too_deep() {
+        let text = "[{a: ".repeat(100_000);
+        assert_eq!(
+            text.parse::<Value>(),
+            Err(SnapshotError::Parse {
+                offset: MAX_DEPTH / 2 * 5,
+                message: "nested too deep",
+            })
+        );
+    }
+}
@@ -0,0 +1,396 @@
+//! Restarting the failed components of a container.
+//!
+//! A container with a [`Supervisor`] field marked by `#[supervisor]` implements [`Supervised`].
+//! [`Supervised::supervise`] detects the components poisoned by a panic (see
+//! [`isolation`](crate::isolation)) and restarts them by resetting their states to `Default`,
+//! by one of the [`Strategy`]s. The components are restarted in the order of the
+//! `dependency_graph()` of the container: a component after the ones it depends on.
+//!
+//! The isolated components are restarted, and so are the ones marked by
+//! `#[component(restart)]`, which are reset to `Default` too. [`Strategy::RestForOne`] restarts
+//! the isolated and `restart` components depending on a failed one as well. The other components,
+//! which need not be `Default`, are never restarted. The pinned and actor components cannot be
+//! reset in place, and cannot be marked by `restart`.
+//!
+//! If the components are restarted more than [`Intensity::max_restarts`] times within
+//! [`Intensity::period`], the supervisor gives up and shuts the container down, poisoning all
+//! the isolated components:
+//!
+//! ```
+//! use dep_inj::isolation::{Isolation, IsolationError, Poison};
+//! use dep_inj::supervisor::{Intensity, Strategy, Supervised, Supervisor};
+//! use dep_inj::{Container, DepInj};
+//! use std::time::Duration;
+//!
+//! #[dep_inj::interface]
+//! pub trait Parse {
+//!     fn parse(&mut self, input: &str) -> Result<u64, IsolationError>;
+//! }
+//!
+//! #[derive(Default, DepInj)]
+//! #[target(ParserProxy)]
+//! pub struct ParserState {
+//!     parsed: usize,
+//! }
+//!
+//! impl<Deps: AsRef<ParserState> + AsMut<ParserState>> Parse for ParserProxy<Deps> {
+//!     fn parse(&mut self, input: &str) -> Result<u64, IsolationError> {
+//!         self.parsed += 1;
+//!         Ok(input.parse().unwrap())
+//!     }
+//! }
+//!
+//! // depends on the parser, restarted with it
+//! #[derive(Default, DepInj)]
+//! #[target(CacheProxy, depend(Parse))]
+//! pub struct CacheState {
+//!     entries: Vec<u64>,
+//! }
+//!
+//! #[derive(Container)]
+//! pub struct GlobalStruct {
+//!     #[component(isolate, proxy = ParserProxy, provide(Parse))]
+//!     parser_state: ParserState,
+//!     #[component(restart, proxy = CacheProxy)]
+//!     cache_state: CacheState,
+//!     #[poison]
+//!     poison: Poison,
+//!     #[supervisor]
+//!     supervisor: Supervisor,
+//! }
+//!
+//! let mut global = GlobalStruct {
+//!     parser_state: ParserState::default(),
+//!     cache_state: CacheState::default(),
+//!     poison: Poison::new(),
+//!     supervisor: Supervisor::new(Strategy::RestForOne)
+//!         .with_intensity(Intensity::new(1, Duration::from_secs(60))),
+//! };
+//! # std::panic::set_hook(Box::new(|_| {}));
+//! global.cache_state.entries.push(42);
+//! assert!(global.parse("x").is_err());
+//! assert_eq!(global.poisoned(), ["parser_state"]);
+//!
+//! assert_eq!(global.supervise().unwrap(), ["parser_state", "cache_state"]);
+//! assert!(global.cache_state.entries.is_empty());
+//! assert_eq!(global.parse("42"), Ok(42));
+//!
+//! // the second restart within a minute escalates
+//! assert!(global.parse("x").is_err());
+//! assert!(global.supervise().is_err());
+//! assert!(global.supervisor.is_shut_down());
+//! assert_eq!(global.poisoned(), ["parser_state"]);
+//! ```
+
+use std::{
+    collections::VecDeque,
+    error::Error,
+    fmt::{self, Display},
+    time::{Duration, Instant},
+};
+
+use crate::{graph::Graph, isolation::Isolation};
+
+/// Which components are restarted when one of them fails.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Strategy {
+    /// Restart only the failed components.
+    #[default]
+    OneForOne,
+    /// Restart the failed components and all the components depending on them, directly or
+    /// not, by the dependency graph.
+    RestForOne,
+}
+
+/// The restarts tolerated before the supervisor shuts the container down.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Intensity {
+    pub max_restarts: usize,
+    pub period: Duration,
+}
+
+impl Intensity {
+    pub fn new(max_restarts: usize, period: Duration) -> Self {
+        Self {
+            max_restarts,
+            period,
+        }
+    }
+}
+
+impl Default for Intensity {
+    /// 3 restarts within 5 seconds.
+    fn default() -> Self {
+        Self::new(3, Duration::from_secs(5))
+    }
+}
+
+/// The restart strategy and history of a container.
+#[derive(Debug, Default)]
+pub struct Supervisor {
+    strategy: Strategy,
+    intensity: Intensity,
+    /// the times of the restarts within the period
+    restarts: VecDeque<Instant>,
+    shut_down: bool,
+}
+
+impl Supervisor {
+    pub fn new(strategy: Strategy) -> Self {
+        Self {
+            strategy,
+            ..Self::default()
+        }
+    }
+
+    pub fn with_intensity(mut self, intensity: Intensity) -> Self {
+        self.intensity = intensity;
+        self
+    }
+
+    pub fn strategy(&self) -> Strategy {
+        self.strategy
+    }
+
+    pub fn intensity(&self) -> Intensity {
+        self.intensity
+    }
+
+    /// If the restarts exceeded the intensity, and the container has been shut down.
+    pub fn is_shut_down(&self) -> bool {
+        self.shut_down
+    }
+
+    /// Record a restart at `now`, returning `false` if the intensity is exceeded.
+    pub fn record_restart(&mut self, now: Instant) -> bool {
+        while let Some(&first) = self.restarts.front() {
+            if now.duration_since(first) < self.intensity.period {
+                break;
+            }
+            self.restarts.pop_front();
+        }
+        self.restarts.push_back(now);
+        self.restarts.len() <= self.intensity.max_restarts
+    }
+
+    /// Shut the container down, the later supervisions fail.
+    pub fn shut_down(&mut self) {
+        self.shut_down = true;
+        self.restarts.clear();
+    }
+}
+
+/// The restarts exceeded the intensity of the supervisor, which shut the container down.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Escalation {
+    /// the components failed last
+    pub failed: Vec<&'static str>,
+    pub intensity: Intensity,
+}
+
+impl Display for Escalation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "restarted more than {} times within {:?}, shut down",
+            self.intensity.max_restarts, self.intensity.period
+        )?;
+        if !self.failed.is_empty() {
+            write!(f, " (failed: {})", self.failed.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for Escalation {}
+
+/// Implemented by the containers with a `#[supervisor]`.
+pub trait Supervised: Isolation {
+    fn supervisor(&mut self) -> &mut Supervisor;
+
+    /// The `dependency_graph()` of the container.
+    fn graph(&self) -> Graph;
+
+    /// Reset `component` to `Default`, and clear its poison if it is isolated, returning `false`
+    /// for the components neither isolated nor marked by `#[component(restart)]`, and the other
+    /// names.
+    fn restart(&mut self, component: &str) -> bool;
+
+    /// Restart the failed components by the strategy, returning their names in the dependency
+    /// order, or shut the container down if the intensity is exceeded.
+    fn supervise(&mut self) -> Result<Vec<&'static str>, Escalation> {
+        let intensity = self.supervisor().intensity();
+        if self.supervisor().is_shut_down() {
+            return Err(Escalation {
+                failed: vec![],
+                intensity,
+            });
+        }
+
+        let failed = self.poisoned();
+        if failed.is_empty() {
+            return Ok(vec![]);
+        }
+        let graph = self.graph();
+        let restarted = match self.supervisor().strategy() {
+            Strategy::OneForOne => graph
+                .dependents(&failed)
+                .into_iter()
+                .filter(|component| failed.contains(component))
+                .collect(),
+            Strategy::RestForOne => graph.dependents(&failed),
+        };
+
+        let now = Instant::now();
+        let supervisor = self.supervisor();
+        if !failed.iter().all(|_| supervisor.record_restart(now)) {
+            supervisor.shut_down();
+            for component in self.isolated() {
+                self.poison(component);
+            }
+            return Err(Escalation { failed, intensity });
+        }
+
+        let restarted = restarted
+            .into_iter()
+            .filter(|component| self.restart(component))
+            .collect();
+        Ok(restarted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::isolation::{IsolationError, Poison};
+    use crate::{Container, DepInj};
+
+    #[crate::interface]
+    pub trait Fetch {
+        fn fetch(&mut self) -> Result<u64, IsolationError>;
+    }
+
+    #[crate::interface]
+    pub trait Render {
+        fn render(&mut self) -> Result<u64, IsolationError>;
+    }
+
+    #[derive(Default, DepInj)]
+    #[target(ViewProxy, depend(Render))]
+    pub struct ViewState {
+        frames: u64,
+    }
+
+    impl<Deps> ViewProxy<Deps>
+    where
+        Deps: AsRef<ViewState> + AsMut<ViewState> + Render,
+    {
+        fn view(&mut self) -> Result<u64, IsolationError> {
+            self.frames += 1;
+            self.prj_ref_mut().render()
+        }
+    }
+
+    #[derive(Default, DepInj)]
+    #[target(RenderProxy, depend(Fetch))]
+    pub struct RenderState {
+        rendered: u64,
+    }
+
+    impl<Deps> Render for RenderProxy<Deps>
+    where
+        Deps: AsRef<RenderState> + AsMut<RenderState> + Fetch,
+    {
+        fn render(&mut self) -> Result<u64, IsolationError> {
+            self.rendered += 1;
+            self.prj_ref_mut().fetch()
+        }
+    }
+
+    #[derive(Default, DepInj)]
+    #[target(FetchProxy)]
+    pub struct FetchState {
+        fail: bool,
+    }
+
+    impl<Deps: AsRef<FetchState> + AsMut<FetchState>> Fetch for FetchProxy<Deps> {
+        fn fetch(&mut self) -> Result<u64, IsolationError> {
+            assert!(!self.fail, "fetch failed");
+            Ok(1)
+        }
+    }
+
+    #[derive(Default, DepInj)]
+    #[target(LogProxy)]
+    pub struct LogState {
+        lines: u64,
+    }
+
+    pub struct PoolState {
+        url: &'static str,
+    }
+
+    // declared in the reverse order of the dependencies
+    #[derive(Container)]
+    pub struct GlobalStruct {
+        #[component(restart, proxy = ViewProxy)]
+        view_state: ViewState,
+        #[component(isolate, proxy = RenderProxy, provide(Render))]
+        render_state: RenderState,
+        #[component(isolate, proxy = FetchProxy, provide(Fetch))]
+        fetch_state: FetchState,
+        #[component(restart, proxy = LogProxy)]
+        log_state: LogState,
+        // neither `Default` nor restarted
+        #[component]
+        pool_state: PoolState,
+        #[poison]
+        poison: Poison,
+        #[supervisor]
+        supervisor: Supervisor,
+    }
+
+    fn failed(strategy: Strategy) -> GlobalStruct {
+        let mut global = GlobalStruct {
+            view_state: ViewState::default(),
+            render_state: RenderState::default(),
+            fetch_state: FetchState::default(),
+            log_state: LogState::default(),
+            pool_state: PoolState { url: "db" },
+            poison: Poison::new(),
+            supervisor: Supervisor::new(strategy),
+        };
+        global.view_state.frames = 1;
+        global.log_state.lines = 1;
+        global.fetch_state.fail = true;
+        assert!(global.fetch().is_err());
+        global
+    }
+
+    #[test]
+    fn rest_for_one_by_dependencies() {
+        let mut global = failed(Strategy::RestForOne);
+        assert_eq!(global.poisoned(), ["fetch_state"]);
+        assert_eq!(
+            global.supervise().unwrap(),
+            ["fetch_state", "render_state", "view_state"]
+        );
+        assert!(!global.fetch_state.fail);
+        assert_eq!(global.view_state.frames, 0);
+        // depending on nothing failed
+        assert_eq!(global.log_state.lines, 1);
+        assert_eq!(global.render(), Ok(1));
+        assert_eq!(ViewProxy::inj_ref_mut(&mut global).view(), Ok(1));
+        assert_eq!(global.view_state.frames, 1);
+    }
+
+    #[test]
+    fn one_for_one() {
+        let mut global = failed(Strategy::OneForOne);
+        assert_eq!(global.supervise().unwrap(), ["fetch_state"]);
+        assert_eq!(global.view_state.frames, 1);
+        // reported as not restarted
+        assert!(!global.restart("pool_state"));
+        assert_eq!(global.pool_state.url, "db");
+    }
+}
@@ -0,0 +1,79 @@
+//! Several instances of the same state in a container, told apart by tags.
+//!
+//! `AsRef<CacheState>` allows only one `CacheState` per container. A state can instead declare a
+//! proxy per tag, `#[target(ReplicaCache, tag = Replica)]`, which reaches its state by
+//! [`Has<Replica, CacheState>`](Has) rather than `AsRef`. The container implements [`Has`] and
+//! [`HasMut`] instead of `AsRef` and `AsMut` for the components tagged by
+//! `#[component(tag = Replica, ..)]`.
+//!
+//! ```
+//! use dep_inj::tagged::Has;
+//! use dep_inj::{Container, DepInj};
+//!
+//! pub struct Primary;
+//! pub struct Replica;
+//!
+//! #[derive(Default, DepInj)]
+//! #[target(PrimaryCache, tag = Primary)]
+//! #[target(ReplicaCache, tag = Replica)]
+//! pub struct CacheState {
+//!     hits: usize,
+//! }
+//!
+//! impl<Deps: Has<Primary, CacheState>> PrimaryCache<Deps> {
+//!     fn hits(&self) -> usize {
+//!         self.hits
+//!     }
+//! }
+//!
+//! impl<Deps: Has<Replica, CacheState>> ReplicaCache<Deps> {
+//!     fn hits(&self) -> usize {
+//!         self.hits
+//!     }
+//! }
+//!
+//! #[derive(Default, DepInj)]
+//! #[target(ReportProxy)]
+//! pub struct ReportState;
+//!
+//! // the reports only read from the replica
+//! impl<Deps: AsRef<ReportState> + Has<Replica, CacheState>> ReportProxy<Deps> {
+//!     fn replica_hits(&self) -> usize {
+//!         ReplicaCache::inj_ref(self.prj_ref()).hits()
+//!     }
+//! }
+//!
+//! #[derive(Default, Container)]
+//! pub struct GlobalStruct {
+//!     #[component(tag = Primary, proxy = PrimaryCache)]
+//!     primary: CacheState,
+//!     #[component(tag = Replica, proxy = ReplicaCache)]
+//!     replica: CacheState,
+//!     #[component(proxy = ReportProxy)]
+//!     report_state: ReportState,
+//! }
+//!
+//! let mut global = GlobalStruct::default();
+//! global.primary.hits = 1;
+//! global.replica.hits = 2;
+//! assert_eq!(PrimaryCache::inj_ref(&global).hits(), 1);
+//! assert_eq!(ReportProxy::inj_ref(&global).replica_hits(), 2);
+//! ```
+
+/// Borrow the state tagged by `Tag`, like `AsRef<State>` for one of the several `State`s.
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` has no `{State}` tagged by `{Tag}`",
+    note = "a container has it by `#[component(tag = {Tag}, ..)]`"
+)]
+pub trait Has<Tag, State: ?Sized> {
+    fn get(&self) -> &State;
+}
+
+/// Mutably borrow the state tagged by `Tag`, like `AsMut<State>`.
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` has no mutable `{State}` tagged by `{Tag}`",
+    note = "a container has it by `#[component(tag = {Tag}, ..)]`"
+)]
+pub trait HasMut<Tag, State: ?Sized>: Has<Tag, State> {
+    fn get_mut(&mut self) -> &mut State;
+}
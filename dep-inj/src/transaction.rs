@@ -0,0 +1,276 @@
+//! Transactional calls rolling back the component states on failure.
+//!
+//! A container with a [`Transaction`] field marked by `#[transaction]` runs each `&mut self`
+//! interface call as a transaction. The first time a component marked by
+//! `#[component(rollback)]` is borrowed mutably in the call tree, its state is checkpointed by
+//! [`Checkpoint`], and all the checkpointed states are rolled back if the top-level call returns
+//! `Err` or panics. The calls by `&self` or `Arc<Self>` are not transactions, since the states
+//! they change cannot be rolled back, so a container whose provided interfaces have no
+//! `&mut self` method fails to compile with a `#[transaction]`. A `Clone` state derives
+//! [`Checkpoint`](macro@crate::Checkpoint):
+//!
+//! ```
+//! use dep_inj::transaction::Transaction;
+//! use dep_inj::{Checkpoint, Container, DepInj};
+//!
+//! #[dep_inj::interface]
+//! pub trait Withdraw {
+//!     fn withdraw(&mut self, amount: u64) -> Result<(), String>;
+//! }
+//!
+//! #[dep_inj::interface]
+//! pub trait Charge {
+//!     fn charge(&mut self, amount: u64) -> Result<(), String>;
+//! }
+//!
+//! #[derive(Default, Clone, Checkpoint, DepInj)]
+//! #[target(AccountProxy)]
+//! pub struct AccountState {
+//!     balance: u64,
+//! }
+//!
+//! impl<Deps> Withdraw for AccountProxy<Deps>
+//! where
+//!     Deps: AsRef<AccountState> + AsMut<AccountState> + Charge,
+//! {
+//!     fn withdraw(&mut self, amount: u64) -> Result<(), String> {
+//!         self.balance = self.balance.checked_sub(amount).ok_or("insufficient")?;
+//!         // fails after the balance has been changed
+//!         self.prj_ref_mut().charge(amount)
+//!     }
+//! }
+//!
+//! #[derive(Default, Clone, Checkpoint, DepInj)]
+//! #[target(LedgerProxy)]
+//! pub struct LedgerState {
+//!     entries: Vec<u64>,
+//! }
+//!
+//! impl<Deps> Charge for LedgerProxy<Deps>
+//! where
+//!     Deps: AsRef<LedgerState> + AsMut<LedgerState>,
+//! {
+//!     fn charge(&mut self, amount: u64) -> Result<(), String> {
+//!         self.entries.push(amount);
+//!         if amount > 10 {
+//!             return Err("over the limit".to_string());
+//!         }
+//!         Ok(())
+//!     }
+//! }
+//!
+//! #[derive(Default, Container)]
+//! pub struct GlobalStruct {
+//!     #[component(rollback, proxy = AccountProxy, provide(Withdraw))]
+//!     account_state: AccountState,
+//!     #[component(rollback, proxy = LedgerProxy, provide(Charge))]
+//!     ledger_state: LedgerState,
+//!     #[transaction]
+//!     tx: Transaction,
+//! }
+//!
+//! let mut global = GlobalStruct::default();
+//! global.account_state.balance = 100;
+//!
+//! global.withdraw(5).unwrap();
+//! assert_eq!(global.account_state.balance, 95);
+//!
+//! // both the account and the ledger are rolled back
+//! assert!(global.withdraw(20).is_err());
+//! assert_eq!(global.account_state.balance, 95);
+//! assert_eq!(global.ledger_state.entries, [5]);
+//! ```
+//!
+//! Only the interface calls taking `&mut self` run as transactions, since the others cannot
+//! borrow the states mutably through the container. A call taking `&self`, `Arc<Self>`,
+//! `Pin<&mut Self>` or `self` is not rolled back, and neither are the states changed through the
+//! interior mutability, even in a `&mut self` call. A panic is caught by the top-level call only,
+//! to roll back and resume unwinding, so the nested calls pay nothing for it.
+//!
+//! A state which is not `Clone`, or too large to be cloned, implements [`Checkpoint`] by hand,
+//! with an undo log for example.
+
+use std::any::Any;
+
+/// Saves the state of a component before a transaction changes it, and rolls back to it.
+///
+/// Derived for the `Clone` states by cloning them, and can be implemented by the other states
+/// with an undo log.
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` cannot be rolled back by a transaction",
+    note = "derive `Checkpoint` for a `Clone` state, or implement it by hand"
+)]
+pub trait Checkpoint {
+    type Saved: Send + Sync + 'static;
+
+    fn checkpoint(&self) -> Self::Saved;
+
+    fn rollback(&mut self, saved: Self::Saved);
+}
+
+/// The checkpoints of the states changed in a transaction.
+#[derive(Debug, Default)]
+pub struct Transaction {
+    /// the depth of the running calls, `0` outside a transaction.
+    depth: usize,
+    journal: Journal,
+}
+
+/// The checkpoints taken in a transaction, indexed by the `#[component(rollback)]`s.
+#[derive(Default)]
+pub struct Journal {
+    saved: Vec<(usize, Box<dyn Any + Send + Sync>)>,
+}
+
+impl Transaction {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// If a transaction is running.
+    pub fn is_active(&self) -> bool {
+        self.depth != 0
+    }
+
+    /// Enter a call, which starts a transaction if it is the top-level one.
+    pub fn enter(&mut self) {
+        self.depth += 1;
+    }
+
+    /// Leave a nested call.
+    pub fn leave(&mut self) {
+        self.depth -= 1;
+    }
+
+    /// Finish the top-level call, returning the journal of the transaction. The nested calls
+    /// unwound by a panic are left as well.
+    pub fn finish(&mut self) -> Journal {
+        self.depth = 0;
+        std::mem::take(&mut self.journal)
+    }
+
+    /// Checkpoint the `index`th component by `checkpoint`, if it is changed for the first time
+    /// in a running transaction.
+    pub fn touch<S: Send + Sync + 'static>(
+        &mut self,
+        index: usize,
+        checkpoint: impl FnOnce() -> S,
+    ) {
+        if self.is_active() && !self.journal.saved.iter().any(|(i, _)| *i == index) {
+            self.journal.saved.push((index, Box::new(checkpoint())));
+        }
+    }
+}
+
+impl Journal {
+    /// The checkpoints by the indices of the components.
+    pub fn into_saved(self) -> impl Iterator<Item = (usize, Box<dyn Any + Send + Sync>)> {
+        self.saved.into_iter()
+    }
+}
+
+impl std::fmt::Debug for Journal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list()
+            .entries(self.saved.iter().map(|(index, _)| index))
+            .finish()
+    }
+}
+
+/// Implemented by the containers with a `#[transaction]`.
+pub trait Transactional {
+    fn transaction(&mut self) -> &mut Transaction;
+
+    /// Roll back the components checkpointed in the journal.
+    fn rollback(&mut self, journal: Journal);
+}
+
+/// If the result of a call is a failure, an `Err` here.
+#[doc(hidden)]
+pub trait Failed {
+    fn __dep_inj_failed(&self) -> bool;
+}
+
+impl<T, E> Failed for Result<T, E> {
+    fn __dep_inj_failed(&self) -> bool {
+        self.is_err()
+    }
+}
+
+/// The fallback of [`Failed`] for the other results, picked by autoref.
+#[doc(hidden)]
+pub trait NotFailed {
+    fn __dep_inj_failed(&self) -> bool;
+}
+
+impl<T: ?Sized> NotFailed for &T {
+    fn __dep_inj_failed(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Checkpoint, Container, DepInj};
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    #[crate::interface]
+    pub trait Push {
+        fn push(&mut self, item: u64);
+    }
+
+    #[crate::interface]
+    pub trait Check {
+        fn check(&mut self, item: u64);
+    }
+
+    #[derive(Default, Clone, Checkpoint, DepInj)]
+    #[target(ListProxy)]
+    pub struct ListState {
+        items: Vec<u64>,
+    }
+
+    impl<Deps> Push for ListProxy<Deps>
+    where
+        Deps: AsRef<ListState> + AsMut<ListState> + Check,
+    {
+        fn push(&mut self, item: u64) {
+            self.items.push(item);
+            self.prj_ref_mut().check(item);
+        }
+    }
+
+    #[derive(Default, DepInj)]
+    #[target(CheckProxy)]
+    pub struct CheckState;
+
+    impl<Deps: AsRef<CheckState>> Check for CheckProxy<Deps> {
+        fn check(&mut self, item: u64) {
+            assert!(item < 10, "too large");
+        }
+    }
+
+    #[derive(Default, Container)]
+    pub struct GlobalStruct {
+        #[component(rollback, proxy = ListProxy, provide(Push))]
+        list_state: ListState,
+        #[component(proxy = CheckProxy, provide(Check))]
+        check_state: CheckState,
+        #[transaction]
+        tx: Transaction,
+    }
+
+    #[test]
+    fn nested_panic() {
+        let mut global = GlobalStruct::default();
+        global.push(1);
+        // the nested `check` panics, caught by the top-level `push`
+        assert!(catch_unwind(AssertUnwindSafe(|| global.push(10))).is_err());
+        assert_eq!(global.list_state.items, [1]);
+        assert!(!global.tx.is_active());
+
+        global.push(2);
+        assert_eq!(global.list_state.items, [1, 2]);
+    }
+}
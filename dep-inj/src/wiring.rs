@@ -0,0 +1,131 @@
+//! Compile-time assertions of the wiring of a container.
+//!
+//! A missing `impl IsEven for GlobalStruct` usually shows up deep inside the bounds of a component
+//! which depends on it. The assertions check the container where it is defined instead:
+//!
+//! * `assert_provides!(GlobalStruct: IsOdd, IsEven, AsRef<OddState>)` checks that the container
+//!   implements every listed trait.
+//! * `assert_wired!(GlobalStruct: OddProxy, EvenProxy)` checks that each proxy injected with the
+//!   container implements the interfaces it declares by `#[target(OddProxy, provide(IsOdd))]`,
+//!   that is, the container meets every dep the proxies require.
+//!
+//! Besides, a container deriving `Container` asserts that it implements the interfaces the
+//! proxies of its components declare by `#[target(OddProxy, depend(IsEven))]`, see [`Satisfied`].
+//!
+//! ```compile_fail
+//! # use dep_inj::{Container, DepInj};
+//! # use std::sync::Arc;
+//! #[dep_inj::interface]
+//! pub trait IsOdd {
+//!     fn is_odd(self: Arc<Self>, n: u64) -> bool;
+//! }
+//!
+//! #[dep_inj::interface]
+//! pub trait IsEven {
+//!     fn is_even(self: Arc<Self>, n: u64) -> bool;
+//! }
+//!
+//! #[derive(Default, DepInj)]
+//! #[target(OddProxy, provide(IsOdd))]
+//! pub struct OddState;
+//!
+//! impl<Deps: IsEven + AsRef<OddState>> IsOdd for OddProxy<Deps> {
+//!     fn is_odd(self: Arc<Self>, n: u64) -> bool {
+//!         n != 0 && self.prj_arc().is_even(n - 1)
+//!     }
+//! }
+//!
+//! #[derive(Default, Container)]
+//! pub struct GlobalStruct {
+//!     #[component]
+//!     odd_state: OddState,
+//! }
+//!
+//! // error: `GlobalStruct` does not provide the interface `IsEven`
+//! //        required for `OddProxy<GlobalStruct>` to implement `IsOdd`
+//! dep_inj::assert_wired!(GlobalStruct: OddProxy);
+//! ```
+
+/// Implemented by a proxy `Foo<Deps>` declared by `#[target(Foo, provide(I, ..))]` when it
+/// implements every declared interface, which is checked by [`assert_wired!`](crate::assert_wired).
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` declares no interfaces to be wired",
+    note = "declare them by `#[target(.., provide(..))]` on the state"
+)]
+pub trait Wired {}
+
+/// Implemented by a proxy `Foo<Deps>` declared by `#[target(Foo, depend(IsBar, ..))]` when `Deps`
+/// implements every declared dep, which a container deriving `Container` asserts for the proxies
+/// of its components.
+pub trait Satisfied {}
+
+/// Assert that a container implements every listed trait, at compile time.
+///
+/// ```
+/// # use dep_inj::Container;
+/// #[derive(Default)]
+/// pub struct OddState;
+///
+/// #[derive(Default, Container)]
+/// pub struct GlobalStruct {
+///     #[component]
+///     odd_state: OddState,
+/// }
+///
+/// dep_inj::assert_provides!(GlobalStruct: Default, AsRef<OddState>, AsMut<OddState>);
+/// ```
+#[macro_export]
+macro_rules! assert_provides {
+    ($container:ty: $($bound:path),+ $(,)?) => {
+        const _: fn() = || {
+            $({
+                fn assert_provides<__C: ?::core::marker::Sized + $bound>() {}
+                assert_provides::<$container>();
+            })+
+        };
+    };
+}
+
+/// Assert that a container meets the deps of every listed proxy, at compile time, see
+/// [`wiring`](crate::wiring).
+///
+/// ```
+/// # use dep_inj::{Container, DepInj};
+/// # use std::sync::Arc;
+/// #[dep_inj::interface]
+/// pub trait IsEven {
+///     fn is_even(self: Arc<Self>, n: u64) -> bool;
+/// }
+///
+/// #[derive(Default, DepInj)]
+/// #[target(EvenProxy, provide(IsEven))]
+/// pub struct EvenState;
+///
+/// impl<Deps: IsEven + AsRef<EvenState>> IsEven for EvenProxy<Deps> {
+///     fn is_even(self: Arc<Self>, n: u64) -> bool {
+///         n == 0 || !self.prj_arc().is_even(n - 1)
+///     }
+/// }
+///
+/// #[derive(Default, Container)]
+/// pub struct GlobalStruct {
+///     #[component(proxy = EvenProxy, provide(IsEven))]
+///     even_state: EvenState,
+/// }
+///
+/// dep_inj::assert_wired!(GlobalStruct: EvenProxy);
+/// ```
+///
+/// Only the interfaces listed in `provide(..)` are checked: an impl of another interface for the
+/// proxy may still require a dep the container lacks, which shows up where that impl is used.
+#[macro_export]
+macro_rules! assert_wired {
+    ($container:ty: $($($proxy:ident)::+),+ $(,)?) => {
+        const _: fn() = || {
+            $({
+                fn assert_wired<__P: ?::core::marker::Sized + $crate::wiring::Wired>() {}
+                assert_wired::<$($proxy)::+<$container>>();
+            })+
+        };
+    };
+}
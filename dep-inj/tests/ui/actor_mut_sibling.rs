@@ -0,0 +1,45 @@
+use dep_inj::{Container, DepInj};
+use std::sync::Arc;
+
+#[dep_inj::interface]
+pub trait Log {
+    fn log(&mut self, line: String);
+}
+
+#[dep_inj::interface]
+pub trait Count {
+    fn incr(self: Arc<Self>) -> usize;
+}
+
+#[derive(Default, DepInj)]
+#[target(LogProxy)]
+pub struct LogState {
+    lines: Vec<String>,
+}
+
+impl<Deps: AsRef<LogState> + AsMut<LogState>> Log for LogProxy<Deps> {
+    fn log(&mut self, line: String) {
+        self.lines.push(line);
+    }
+}
+
+#[derive(Default, DepInj)]
+#[target(CounterProxy, depend(Log))]
+pub struct CounterState;
+
+impl<Deps: AsRef<CounterState> + Log + Send + Sync> Count for CounterProxy<Deps> {
+    fn incr(self: Arc<Self>) -> usize {
+        0
+    }
+}
+
+// the actor reaches `Log` by `&mut self`, which it cannot call through the container
+#[derive(Default, Container)]
+pub struct GlobalStruct {
+    #[component(proxy = LogProxy, provide(Log))]
+    log_state: LogState,
+    #[component(actor = CounterActor, proxy = CounterProxy, provide(Count))]
+    counter: CounterActor<CounterState>,
+}
+
+fn main() {}
@@ -0,0 +1,29 @@
+use dep_inj::{Container, DepInj};
+
+#[derive(DepInj)]
+#[target(PoolProxy)]
+pub struct PoolState {
+    url: String,
+}
+
+#[derive(Default, DepInj)]
+#[target(CacheProxy)]
+pub struct CacheState {
+    capacity: usize,
+}
+
+#[derive(Container)]
+#[builder(pub struct GlobalBuilder)]
+pub struct GlobalStruct {
+    #[component(proxy = PoolProxy)]
+    pool_state: PoolState,
+    #[component(default, proxy = CacheProxy)]
+    cache_state: CacheState,
+}
+
+fn main() {
+    // the defaulted `cache_state` is supplied, but not `pool_state`
+    let _ = GlobalStruct::builder()
+        .with_cache_state(CacheState { capacity: 8 })
+        .build();
+}
@@ -0,0 +1,31 @@
+use dep_inj::{Container, DepInj};
+use std::sync::Arc;
+
+#[dep_inj::interface]
+pub trait IsOdd {
+    fn is_odd(self: Arc<Self>, n: u64) -> bool;
+}
+
+#[dep_inj::interface]
+pub trait IsEven {
+    fn is_even(self: Arc<Self>, n: u64) -> bool;
+}
+
+#[derive(Default, DepInj)]
+#[target(OddProxy, depend(IsEven))]
+pub struct OddState;
+
+impl<Deps: IsEven + AsRef<OddState>> IsOdd for OddProxy<Deps> {
+    fn is_odd(self: Arc<Self>, n: u64) -> bool {
+        n != 0 && self.prj_arc().is_even(n - 1)
+    }
+}
+
+// `OddProxy` depends on `IsEven`, which no component provides
+#[derive(Default, Container)]
+pub struct GlobalStruct {
+    #[component(proxy = OddProxy)]
+    odd_state: OddState,
+}
+
+fn main() {}
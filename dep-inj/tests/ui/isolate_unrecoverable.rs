@@ -0,0 +1,28 @@
+use dep_inj::isolation::Poison;
+use dep_inj::{Container, DepInj};
+
+#[dep_inj::interface]
+pub trait Div {
+    fn div(&self, a: u64, b: u64) -> u64;
+}
+
+#[derive(Default, DepInj)]
+#[target(DivProxy)]
+pub struct DivState;
+
+impl<Deps: AsRef<DivState>> Div for DivProxy<Deps> {
+    fn div(&self, a: u64, b: u64) -> u64 {
+        a / b
+    }
+}
+
+// `Div::div` cannot return the `IsolationError` of a panic
+#[derive(Default, Container)]
+pub struct GlobalStruct {
+    #[component(isolate, proxy = DivProxy, provide(Div))]
+    div_state: DivState,
+    #[poison]
+    poison: Poison,
+}
+
+fn main() {}
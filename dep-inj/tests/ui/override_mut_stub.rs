@@ -0,0 +1,28 @@
+use dep_inj::{Container, DepInj};
+
+#[dep_inj::interface]
+pub trait Log {
+    fn log(&mut self, line: String);
+}
+
+#[derive(Default, DepInj)]
+#[target(LogProxy)]
+pub struct LogState {
+    lines: Vec<String>,
+}
+
+impl<Deps: AsRef<LogState> + AsMut<LogState>> Log for LogProxy<Deps> {
+    fn log(&mut self, line: String) {
+        self.lines.push(line);
+    }
+}
+
+// the stub is shared in an `Arc`, so `Log::log` cannot borrow it mutably
+#[derive(Default, Container)]
+#[overridable(pub struct LogStubbed: Log)]
+pub struct GlobalStruct {
+    #[component(proxy = LogProxy, provide(Log))]
+    log_state: LogState,
+}
+
+fn main() {}
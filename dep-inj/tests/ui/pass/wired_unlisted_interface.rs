@@ -0,0 +1,50 @@
+use dep_inj::{Container, DepInj};
+use std::sync::Arc;
+
+#[dep_inj::interface]
+pub trait IsOdd {
+    fn is_odd(self: Arc<Self>, n: u64) -> bool;
+}
+
+#[dep_inj::interface]
+pub trait IsEven {
+    fn is_even(self: Arc<Self>, n: u64) -> bool;
+}
+
+#[dep_inj::interface]
+pub trait Parity {
+    fn parity(self: Arc<Self>, n: u64) -> &'static str;
+}
+
+#[derive(Default, DepInj)]
+#[target(OddProxy, provide(IsOdd))]
+pub struct OddState;
+
+impl<Deps: AsRef<OddState>> IsOdd for OddProxy<Deps> {
+    fn is_odd(self: Arc<Self>, n: u64) -> bool {
+        n % 2 == 1
+    }
+}
+
+// not listed in `provide(..)`, so its dep on `IsEven` is not checked
+impl<Deps: IsEven + AsRef<OddState>> Parity for OddProxy<Deps> {
+    fn parity(self: Arc<Self>, n: u64) -> &'static str {
+        match self.prj_arc().is_even(n) {
+            true => "even",
+            false => "odd",
+        }
+    }
+}
+
+#[derive(Default, Container)]
+pub struct GlobalStruct {
+    #[component(proxy = OddProxy, provide(IsOdd))]
+    odd_state: OddState,
+}
+
+// passes, though `GlobalStruct` does not provide `IsEven` for `Parity`
+dep_inj::assert_wired!(GlobalStruct: OddProxy);
+
+fn main() {
+    assert!(Arc::new(GlobalStruct::default()).is_odd(1));
+}
@@ -0,0 +1,22 @@
+use dep_inj::{Container, DepInj};
+use std::marker::PhantomPinned;
+
+#[derive(Default, DepInj)]
+#[target(TaskProxy)]
+pub struct TaskState {
+    #[pin]
+    pinned: PhantomPinned,
+}
+
+#[derive(Default, Container)]
+pub struct GlobalStruct {
+    #[component(pin)]
+    task_state: TaskState,
+}
+
+// could move the structurally pinned state out
+impl Drop for GlobalStruct {
+    fn drop(&mut self) {}
+}
+
+fn main() {}
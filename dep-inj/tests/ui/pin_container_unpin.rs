@@ -0,0 +1,20 @@
+use dep_inj::{Container, DepInj};
+use std::marker::PhantomPinned;
+
+#[derive(Default, DepInj)]
+#[target(TaskProxy)]
+pub struct TaskState {
+    #[pin]
+    pinned: PhantomPinned,
+}
+
+#[derive(Default, Container)]
+pub struct GlobalStruct {
+    #[component(pin)]
+    task_state: TaskState,
+}
+
+// would let the pinned state be moved out of a `Pin<&mut GlobalStruct>`
+impl Unpin for GlobalStruct {}
+
+fn main() {}
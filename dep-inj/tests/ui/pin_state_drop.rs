@@ -0,0 +1,16 @@
+use dep_inj::DepInj;
+use std::marker::PhantomPinned;
+
+#[derive(Default, DepInj)]
+#[target(TaskProxy)]
+pub struct TaskState {
+    #[pin]
+    pinned: PhantomPinned,
+}
+
+// could move the pinned field out
+impl Drop for TaskState {
+    fn drop(&mut self) {}
+}
+
+fn main() {}
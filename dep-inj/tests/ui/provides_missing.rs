@@ -0,0 +1,17 @@
+use dep_inj::Container;
+
+#[derive(Default)]
+pub struct OddState;
+
+#[derive(Default)]
+pub struct EvenState;
+
+#[derive(Default, Container)]
+pub struct GlobalStruct {
+    #[component]
+    odd_state: OddState,
+}
+
+dep_inj::assert_provides!(GlobalStruct: AsRef<OddState>, AsRef<EvenState>);
+
+fn main() {}
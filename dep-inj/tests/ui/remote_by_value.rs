@@ -0,0 +1,9 @@
+#[dep_inj::remote(CounterClient)]
+pub trait Counter {
+    fn incr(&self) -> u64;
+
+    // the server keeps the target, which cannot be consumed
+    fn finish(self: Box<Self>) -> u64;
+}
+
+fn main() {}
@@ -0,0 +1,33 @@
+use dep_inj::transaction::Transaction;
+use dep_inj::{Checkpoint, Container, DepInj};
+use std::sync::{Arc, Mutex};
+
+#[dep_inj::interface]
+pub trait Withdraw {
+    fn withdraw(self: Arc<Self>, amount: u64) -> Result<(), String>;
+}
+
+#[derive(Default, Clone, Checkpoint, DepInj)]
+#[target(AccountProxy)]
+pub struct AccountState {
+    balance: Arc<Mutex<u64>>,
+}
+
+impl<Deps: AsRef<AccountState>> Withdraw for AccountProxy<Deps> {
+    fn withdraw(self: Arc<Self>, amount: u64) -> Result<(), String> {
+        let mut balance = self.balance.lock().unwrap();
+        *balance = balance.checked_sub(amount).ok_or("insufficient")?;
+        Ok(())
+    }
+}
+
+// no call would run as a transaction
+#[derive(Default, Container)]
+pub struct GlobalStruct {
+    #[component(rollback, proxy = AccountProxy, provide(Withdraw))]
+    account_state: AccountState,
+    #[transaction]
+    tx: Transaction,
+}
+
+fn main() {}
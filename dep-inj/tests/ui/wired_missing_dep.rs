@@ -0,0 +1,32 @@
+use dep_inj::{Container, DepInj};
+use std::sync::Arc;
+
+#[dep_inj::interface]
+pub trait IsOdd {
+    fn is_odd(self: Arc<Self>, n: u64) -> bool;
+}
+
+#[dep_inj::interface]
+pub trait IsEven {
+    fn is_even(self: Arc<Self>, n: u64) -> bool;
+}
+
+#[derive(Default, DepInj)]
+#[target(OddProxy, provide(IsOdd))]
+pub struct OddState;
+
+impl<Deps: IsEven + AsRef<OddState>> IsOdd for OddProxy<Deps> {
+    fn is_odd(self: Arc<Self>, n: u64) -> bool {
+        n != 0 && self.prj_arc().is_even(n - 1)
+    }
+}
+
+#[derive(Default, Container)]
+pub struct GlobalStruct {
+    #[component]
+    odd_state: OddState,
+}
+
+dep_inj::assert_wired!(GlobalStruct: OddProxy);
+
+fn main() {}
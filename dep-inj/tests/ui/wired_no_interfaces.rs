@@ -0,0 +1,15 @@
+use dep_inj::{Container, DepInj};
+
+#[derive(Default, DepInj)]
+#[target(OddProxy)]
+pub struct OddState;
+
+#[derive(Default, Container)]
+pub struct GlobalStruct {
+    #[component]
+    odd_state: OddState,
+}
+
+dep_inj::assert_wired!(GlobalStruct: OddProxy);
+
+fn main() {}
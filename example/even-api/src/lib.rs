@@ -1,5 +1,8 @@
 use std::sync::Arc;
 
+// `DynIsEven` 是 `IsEven` 的dyn safe版本，使得`Proxy<dyn Deps>`也能调用`emit_count`
+#[dep_inj::dyn_safe(DynIsEven)]
+#[dep_inj::interface]
 pub trait IsEven {
     // 支持`Arc<Self>`，意味着允许跨线程使用`Self`
     fn is_even(self: Arc<Self>, n: u64) -> bool;
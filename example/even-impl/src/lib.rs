@@ -7,7 +7,7 @@ use std::{
 };
 
 #[derive(Default, Debug, DepInj)]
-#[target(EvenProxy)]
+#[target(EvenProxy, provide(IsEven), depend(IsOdd))]
 pub struct EvenState {
     count: Mutex<usize>,
 }
@@ -17,14 +17,17 @@ pub struct EvenState {
 pub(crate) trait EvenDeps: AsRef<EvenState> + IsOdd + Send + Sync + 'static {}
 impl<T: AsRef<EvenState> + IsOdd + Send + Sync + 'static> EvenDeps for T {}
 
-// dyn may benefit compilation speed
-pub(crate) type DynEvenApp = EvenProxy<dyn EvenDeps>;
-
+// dyn may benefit compilation speed,
+// `outline` moves the bodies onto `EvenProxy<dyn EvenDeps>`
+#[dep_inj::outline]
 impl<Ctx: EvenDeps> IsEven for EvenProxy<Ctx> {
     fn is_even(self: Arc<Self>, n: u64) -> bool {
-        is_even_impl(self, n)
+        *self.count.lock().unwrap() += 1;
+
+        (n == 0) || self.prj_arc().is_odd(n - 1)
     }
 
+    // not dyn safe, kept generic
     fn emit_count<F>(&self, f: F)
     where
         F: FnOnce(usize),
@@ -33,9 +36,3 @@ impl<Ctx: EvenDeps> IsEven for EvenProxy<Ctx> {
         f(*self.count.lock().unwrap());
     }
 }
-
-fn is_even_impl(app: Arc<DynEvenApp>, n: u64) -> bool {
-    *app.count.lock().unwrap() += 1;
-
-    (n == 0) || app.prj_arc().is_odd(n - 1)
-}
@@ -1,55 +1,24 @@
 use std::sync::Arc;
 
+use dep_inj::Container;
 use even_api::IsEven;
 use even_impl::{EvenProxy, EvenState};
 use odd_api::IsOdd;
 use odd_impl::{OddProxy, OddState};
 
 // 将所有的State管理起来
-// 实现所有的trait，以及AsRef<XXState>
-#[derive(Default, Debug)]
+// 由`Container`实现所有的trait，以及AsRef<XXState>
+#[derive(Default, Debug, Container)]
 pub struct GlobalStruct {
     // 可以是lazy的field
+    #[component(proxy = OddProxy, provide(IsOdd))]
     odd_state: OddState,
+    #[component(proxy = EvenProxy, provide(IsEven))]
     even_state: EvenState,
 }
 
-mod boilerplate {
-    use super::{Arc, EvenProxy, EvenState, GlobalStruct, IsEven, IsOdd, OddProxy, OddState};
-    impl AsRef<OddState> for GlobalStruct {
-        fn as_ref(&self) -> &OddState {
-            &self.odd_state
-        }
-    }
-
-    impl AsRef<EvenState> for GlobalStruct {
-        fn as_ref(&self) -> &EvenState {
-            &self.even_state
-        }
-    }
-
-    impl IsOdd for GlobalStruct {
-        #[inline]
-        fn is_odd(self: Arc<Self>, n: u64) -> bool {
-            OddProxy::inj_arc(self).is_odd(n)
-        }
-    }
-
-    impl IsEven for GlobalStruct {
-        #[inline]
-        fn is_even(self: Arc<Self>, n: u64) -> bool {
-            EvenProxy::inj_arc(self).is_even(n)
-        }
-
-        #[inline]
-        fn emit_count<F>(&self, f: F)
-        where
-            F: FnOnce(usize),
-        {
-            EvenProxy::inj_ref(self).emit_count(f)
-        }
-    }
-}
+// 在编译期检查`GlobalStruct`满足各个组件的依赖
+dep_inj::assert_wired!(GlobalStruct: OddProxy, EvenProxy);
 
 fn main() {
     let global = Arc::new(GlobalStruct::default());
@@ -58,4 +27,11 @@ fn main() {
     assert!(global.clone().is_odd(101));
 
     dbg!(global);
+
+    // 打印组件之间的依赖关系，odd_state与even_state互相依赖
+    print!("{}", GlobalStruct::dependency_graph().to_dot());
+    assert_eq!(
+        GlobalStruct::dependency_graph().cycles(),
+        [["odd_state", "even_state"]]
+    );
 }
@@ -1,4 +1,7 @@
 use std::sync::Arc;
+
+// 使得容器可以通过`#[derive(Container)]`转发`IsOdd`
+#[dep_inj::interface]
 pub trait IsOdd {
     fn is_odd(self: Arc<Self>, n: u64) -> bool;
 }
@@ -1,4 +1,4 @@
-use even_api::IsEven;
+use even_api::{DynIsEven, IsEven};
 use odd_api::IsOdd;
 use std::{
     fmt::Debug,
@@ -6,15 +6,15 @@ use std::{
 };
 
 #[derive(Default, Debug, dep_inj::DepInj)]
-#[target(OddProxy)]
+#[target(OddProxy, provide(IsOdd), depend(IsEven))]
 pub struct OddState {
     count: Mutex<usize>,
 }
 
-pub(crate) trait OddDeps: AsRef<OddState> + IsEven + Send + Sync + 'static {}
-impl<T: AsRef<OddState> + IsEven + Send + Sync + 'static> OddDeps for T {}
+// `DynIsEven` instead of `IsEven`, so that `dyn OddDeps` is allowed
+pub(crate) trait OddDeps: AsRef<OddState> + DynIsEven + Send + Sync + 'static {}
+impl<T: AsRef<OddState> + DynIsEven + Send + Sync + 'static> OddDeps for T {}
 
-#[allow(unused)]
 pub(crate) type DynOddProxy = OddProxy<dyn OddDeps>;
 
 impl<Ctx: OddDeps> IsOdd for OddProxy<Ctx> {
@@ -24,18 +24,18 @@ impl<Ctx: OddDeps> IsOdd for OddProxy<Ctx> {
     }
 }
 
-fn is_odd_impl<Ctx: OddDeps>(app: Arc<OddProxy<Ctx>>, n: u64) -> bool {
+fn is_odd_impl(app: Arc<DynOddProxy>, n: u64) -> bool {
     *app.count.lock().unwrap() += 1;
 
     if n == 0 {
         return false;
     }
 
-    // `DynOddProxy` cannot call `emit_count`
-    app.prj_ref().emit_count(|even_count| {
+    // `DynOddProxy` calls `emit_count` through `DynIsEven`
+    app.prj_ref().emit_count(Box::new(|even_count| {
         if even_count > 100 {
             println!("IsEven::is_even was called over {even_count} times");
         }
-    });
+    }));
     app.prj_arc().is_even(n - 1)
 }